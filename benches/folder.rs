@@ -0,0 +1,54 @@
+//! Measure the cost of creating and removing folders in a tight loop.
+//!
+//! Run with `cargo bench --bench folder`.
+
+use std::time::Instant;
+
+use temporary::{Builder, Folder};
+
+const COUNT: usize = 10_000;
+
+fn main() {
+    let root = Folder::new("bench").unwrap();
+    measure("create and remove", || {
+        drop(Folder::with_parent(&root, "foo").unwrap());
+    });
+    measure("create and remove with affixes", || {
+        let folder = Builder::new()
+            .parent(&root)
+            .prefix("foo")
+            .suffix(".bar")
+            .timestamped(true)
+            .folder()
+            .unwrap();
+        drop(folder);
+    });
+    let mut builder = Builder::new();
+    builder
+        .parent(&root)
+        .prefix("foo")
+        .random_len(1)
+        .retries(64);
+    let folders = (0..20)
+        .map(|_| builder.folder().unwrap())
+        .collect::<Vec<_>>();
+    measure("create and remove with collisions", || {
+        drop(builder.folder().unwrap());
+    });
+    drop(folders);
+}
+
+fn measure<F: FnMut()>(name: &str, mut f: F) {
+    let start = Instant::now();
+    for _ in 0..COUNT {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{}: {:?} per folder ({} folders in {:?})",
+        name,
+        elapsed / COUNT as u32,
+        COUNT,
+        elapsed,
+    );
+}
@@ -0,0 +1,28 @@
+//! Measure the cost of removing a folder with many files.
+//!
+//! Run with `cargo bench --bench removal`.
+
+use std::time::Instant;
+
+use temporary::Folder;
+
+const COUNT: usize = 100_000;
+
+fn main() {
+    let root = Folder::new("bench").unwrap();
+    for threads in [1, 2, 4, 8] {
+        let folder = Folder::with_parent(&root, "foo").unwrap();
+        for i in 0..COUNT {
+            folder
+                .touch(format!("{}/{}/{}.txt", i % 10, i % 100, i))
+                .unwrap();
+        }
+        let start = Instant::now();
+        folder.remove_parallel(threads).unwrap();
+        let elapsed = start.elapsed();
+        println!(
+            "remove with {} threads: {:?} ({} files)",
+            threads, elapsed, COUNT,
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Macros for the [temporary](https://docs.rs/temporary) package.
+//!
+//! The macros are re-exported by the package with the `macros` feature
+//! enabled and should not be used directly.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Error, FnArg, ItemFn};
+
+/// Turn a function taking a temporary folder into a test.
+///
+/// The function takes a single argument, which is either `&Folder` or `&Path`.
+/// A folder with a prefix derived from the module path and the name of the
+/// function is created for each run and removed afterwards. If the test
+/// panics, the folder is kept, and its path is printed to the standard error.
+///
+/// `#[test]` is added unless another attribute with a path ending in `test`,
+/// such as `#[tokio::test]`, follows, in which case the latter is forwarded
+/// and the function may be asynchronous.
+///
+/// ```rust,ignore
+/// use temporary::Folder;
+///
+/// #[temporary::test]
+/// fn write(folder: &Folder) {
+///     folder.write("foo.txt", "Hi there!").unwrap();
+/// }
+///
+/// #[temporary::test]
+/// #[tokio::test]
+/// async fn read(folder: &std::path::Path) {
+///     assert!(folder.exists());
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test(arguments: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    match expand(arguments.into(), function) {
+        Ok(stream) => stream.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand(
+    arguments: proc_macro2::TokenStream,
+    function: ItemFn,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if !arguments.is_empty() {
+        return Err(Error::new_spanned(
+            arguments,
+            "the attribute takes no arguments",
+        ));
+    }
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = function;
+    let inputs = &sig.inputs;
+    match inputs.first() {
+        Some(FnArg::Typed(_)) if inputs.len() == 1 => {}
+        _ => {
+            return Err(Error::new_spanned(
+                &sig,
+                "the function has to take exactly one argument, such as `folder: &Folder`",
+            ))
+        }
+    }
+    // Attributes such as `#[tokio::test]` take the place of `#[test]`.
+    let runner = attrs.iter().any(|attribute| {
+        let path = attribute.path();
+        path.segments.len() > 1
+            && path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "test")
+    });
+    if sig.asyncness.is_some() && !runner {
+        return Err(Error::new_spanned(
+            &sig,
+            "asynchronous tests need an attribute such as `#[tokio::test]` after this one",
+        ));
+    }
+    let test = if runner { quote!() } else { quote!(#[test]) };
+    let name = &sig.ident;
+    let output = &sig.output;
+    let mut inner = sig.clone();
+    inner.ident = syn::Ident::new("inner", name.span());
+    let call = if sig.asyncness.is_some() {
+        quote!(inner(&folder).await)
+    } else {
+        quote!(inner(&folder))
+    };
+    let asyncness = &sig.asyncness;
+    Ok(quote! {
+        #test
+        #(#attrs)*
+        #vis #asyncness fn #name() #output {
+            #inner #block
+            let folder = ::temporary::__private::folder(
+                ::core::module_path!(),
+                ::core::stringify!(#name),
+            );
+            #call
+        }
+    })
+}
@@ -0,0 +1,253 @@
+use std::io::Result;
+use std::path::Path;
+
+/// Grant access to a folder only to the current user and the system.
+///
+/// On Windows, the folder gets a protected access control list, which does
+/// not inherit entries from the parent and is inherited by the content. It
+/// has no effect elsewhere.
+#[cfg(windows)]
+pub fn restrict(path: &Path) -> Result<()> {
+    platform::restrict(path)
+}
+
+#[cfg(not(windows))]
+#[inline]
+pub fn restrict(_: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+    use std::io::{Error, Result};
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+
+    pub type Handle = *mut c_void;
+    pub type Sid = *mut c_void;
+
+    const ACL_REVISION: u32 = 2;
+    const CONTAINER_INHERIT_ACE: u32 = 0x2;
+    const DACL_SECURITY_INFORMATION: u32 = 0x4;
+    const FILE_ALL_ACCESS: u32 = 0x1F01FF;
+    const OBJECT_INHERIT_ACE: u32 = 0x1;
+    const PROTECTED_DACL_SECURITY_INFORMATION: u32 = 0x80000000;
+    pub const SECURITY_MAX_SID_SIZE: usize = 68;
+    pub const SE_FILE_OBJECT: u32 = 1;
+    const TOKEN_QUERY: u32 = 0x8;
+    const TOKEN_USER: u32 = 1;
+    pub const WIN_LOCAL_SYSTEM_SID: u32 = 22;
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn AddAccessAllowedAceEx(
+            acl: *mut c_void,
+            revision: u32,
+            flags: u32,
+            mask: u32,
+            sid: Sid,
+        ) -> i32;
+        fn CreateWellKnownSid(kind: u32, domain: Sid, sid: Sid, size: *mut u32) -> i32;
+        fn GetLengthSid(sid: Sid) -> u32;
+        fn GetTokenInformation(
+            token: Handle,
+            class: u32,
+            information: *mut c_void,
+            length: u32,
+            returned: *mut u32,
+        ) -> i32;
+        fn InitializeAcl(acl: *mut c_void, length: u32, revision: u32) -> i32;
+        fn OpenProcessToken(process: Handle, access: u32, token: *mut Handle) -> i32;
+        fn SetNamedSecurityInfoW(
+            name: *const u16,
+            kind: u32,
+            information: u32,
+            owner: Sid,
+            group: Sid,
+            dacl: *mut c_void,
+            sacl: *mut c_void,
+        ) -> u32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CloseHandle(handle: Handle) -> i32;
+        fn GetCurrentProcess() -> Handle;
+    }
+
+    pub fn restrict(path: &Path) -> Result<()> {
+        let mut user = current_user()?;
+        let mut system = well_known(WIN_LOCAL_SYSTEM_SID)?;
+        let sids = [
+            user.as_mut_ptr().cast::<c_void>(),
+            system.as_mut_ptr().cast(),
+        ];
+        // The header of a list takes 8 bytes, and each entry takes 8 bytes
+        // followed by the identifier.
+        let length = 8 + sids
+            .iter()
+            .map(|&sid| 8 + unsafe { GetLengthSid(sid) })
+            .sum::<u32>();
+        let mut acl = vec![0u64; (length as usize).div_ceil(8)];
+        check(unsafe { InitializeAcl(acl.as_mut_ptr().cast(), length, ACL_REVISION) })?;
+        for sid in sids {
+            check(unsafe {
+                AddAccessAllowedAceEx(
+                    acl.as_mut_ptr().cast(),
+                    ACL_REVISION,
+                    OBJECT_INHERIT_ACE | CONTAINER_INHERIT_ACE,
+                    FILE_ALL_ACCESS,
+                    sid,
+                )
+            })?;
+        }
+        let name = wide(path);
+        let code = unsafe {
+            SetNamedSecurityInfoW(
+                name.as_ptr(),
+                SE_FILE_OBJECT,
+                DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                acl.as_mut_ptr().cast(),
+                std::ptr::null_mut(),
+            )
+        };
+        match code {
+            0 => Ok(()),
+            code => Err(Error::from_raw_os_error(code as i32)),
+        }
+    }
+
+    /// Return the identifier of the user of the process.
+    pub fn current_user() -> Result<Vec<u64>> {
+        let mut token = std::ptr::null_mut();
+        check(unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) })?;
+        // The information starts with a pointer to the identifier, which is
+        // stored right after it.
+        let mut buffer = vec![0u64; 64];
+        let mut returned = 0;
+        let result = check(unsafe {
+            GetTokenInformation(
+                token,
+                TOKEN_USER,
+                buffer.as_mut_ptr().cast(),
+                (buffer.len() * 8) as u32,
+                &mut returned,
+            )
+        });
+        unsafe { CloseHandle(token) };
+        result?;
+        let sid = unsafe { *buffer.as_ptr().cast::<Sid>() };
+        let length = unsafe { GetLengthSid(sid) } as usize;
+        let mut user = vec![0u64; length.div_ceil(8)];
+        unsafe {
+            std::ptr::copy_nonoverlapping(sid.cast::<u8>(), user.as_mut_ptr().cast(), length)
+        };
+        Ok(user)
+    }
+
+    /// Return a well-known identifier, such as the one of the system.
+    pub fn well_known(kind: u32) -> Result<Vec<u64>> {
+        let mut sid = vec![0u64; SECURITY_MAX_SID_SIZE / 8 + 1];
+        let mut size = (sid.len() * 8) as u32;
+        check(unsafe {
+            CreateWellKnownSid(
+                kind,
+                std::ptr::null_mut(),
+                sid.as_mut_ptr().cast(),
+                &mut size,
+            )
+        })?;
+        Ok(sid)
+    }
+
+    pub fn wide(path: &Path) -> Vec<u16> {
+        path.as_os_str().encode_wide().chain(Some(0)).collect()
+    }
+
+    fn check(value: i32) -> Result<()> {
+        if value == 0 {
+            Err(Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use std::ffi::c_void;
+
+    use super::platform::{self, Sid};
+    use crate::Builder;
+
+    const DACL_SECURITY_INFORMATION: u32 = 0x4;
+    const WIN_BUILTIN_USERS_SID: u32 = 27;
+    const WIN_WORLD_SID: u32 = 1;
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn EqualSid(one: Sid, other: Sid) -> i32;
+        fn GetAce(acl: *mut c_void, index: u32, ace: *mut *mut c_void) -> i32;
+        fn GetNamedSecurityInfoW(
+            name: *const u16,
+            kind: u32,
+            information: u32,
+            owner: *mut Sid,
+            group: *mut Sid,
+            dacl: *mut *mut c_void,
+            sacl: *mut *mut c_void,
+            descriptor: *mut *mut c_void,
+        ) -> u32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn LocalFree(memory: *mut c_void) -> *mut c_void;
+    }
+
+    #[test]
+    fn restrict() {
+        let folder = Builder::new()
+            .prefix("foo")
+            .restrict_to_current_user(true)
+            .folder()
+            .unwrap();
+        let name = platform::wide(folder.path());
+        let mut dacl = std::ptr::null_mut();
+        let mut descriptor = std::ptr::null_mut();
+        let code = unsafe {
+            GetNamedSecurityInfoW(
+                name.as_ptr(),
+                platform::SE_FILE_OBJECT,
+                DACL_SECURITY_INFORMATION,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut dacl,
+                std::ptr::null_mut(),
+                &mut descriptor,
+            )
+        };
+        assert_eq!(code, 0);
+        let mut user = platform::current_user().unwrap();
+        let mut system = platform::well_known(platform::WIN_LOCAL_SYSTEM_SID).unwrap();
+        let mut world = platform::well_known(WIN_WORLD_SID).unwrap();
+        let mut users = platform::well_known(WIN_BUILTIN_USERS_SID).unwrap();
+        // The number of entries follows the revision and the size of the list.
+        let count = unsafe { *dacl.cast::<u8>().add(4).cast::<u16>() };
+        assert_eq!(count, 2);
+        for index in 0..count as u32 {
+            let mut ace = std::ptr::null_mut();
+            assert_ne!(unsafe { GetAce(dacl, index, &mut ace) }, 0);
+            // The identifier follows the header and the access mask.
+            let sid = unsafe { ace.cast::<u8>().add(8).cast::<c_void>() };
+            let equal =
+                |other: &mut Vec<u64>| unsafe { EqualSid(sid, other.as_mut_ptr().cast()) != 0 };
+            assert!(!equal(&mut world) && !equal(&mut users));
+            assert!(equal(&mut user) || equal(&mut system));
+        }
+        unsafe { LocalFree(descriptor) };
+    }
+}
@@ -0,0 +1,250 @@
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::{Builder, Folder};
+
+/// A link pointing at a temporary folder.
+///
+/// The link is removed when the object goes out of scope, which happens before
+/// the folder itself goes out of scope, unless it has been replaced in the
+/// meantime to point elsewhere. See [`Folder::alias`].
+pub struct AliasGuard<'l> {
+    link: PathBuf,
+    folder: &'l Folder,
+}
+
+impl AliasGuard<'_> {
+    /// Return the path to the link.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.link
+    }
+}
+
+impl fmt::Debug for AliasGuard<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.link.fmt(formatter)
+    }
+}
+
+impl Drop for AliasGuard<'_> {
+    fn drop(&mut self) {
+        if points_at(&self.link, self.folder.path()) {
+            if let Err(error) = platform::remove(&self.link) {
+                crate::hook::report(&self.link, &error);
+            }
+        }
+    }
+}
+
+/// Make `link` point at the folder.
+pub fn create<'l>(folder: &'l Folder, link: &Path) -> Result<AliasGuard<'l>> {
+    let link = std::path::absolute(link)?;
+    let context = |error: Error| {
+        Error::new(
+            error.kind(),
+            format!(
+                "failed to point {} at {}: {}",
+                link.display(),
+                folder.path().display(),
+                error,
+            ),
+        )
+    };
+    let (Some(parent), Some(name)) = (link.parent(), link.file_name()) else {
+        return Err(context(Error::new(
+            ErrorKind::InvalidInput,
+            "the path does not name an entry",
+        )));
+    };
+    match fs::symlink_metadata(&link) {
+        Ok(metadata) if !platform::is_link(&metadata) => {
+            return Err(context(Error::new(
+                ErrorKind::AlreadyExists,
+                "the path exists and is not a link",
+            )));
+        }
+        _ => {}
+    }
+    // The link is created next to its destination and renamed into place so
+    // that there is no moment without a link.
+    let (staged, _) = crate::name::create(Builder::new().parent(parent).prefix(name), |path| {
+        platform::link(folder.path(), path)
+    })
+    .map_err(context)?;
+    if let Err(error) = platform::replace(&staged, &link) {
+        let _ = platform::remove(&staged);
+        return Err(context(error));
+    }
+    Ok(AliasGuard { link, folder })
+}
+
+fn points_at(link: &Path, target: &Path) -> bool {
+    match fs::read_link(link) {
+        Ok(other) => platform::normalize(&other) == platform::normalize(target),
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::fs;
+    use std::io::Result;
+    use std::path::{Path, PathBuf};
+
+    #[inline]
+    pub fn link(target: &Path, link: &Path) -> Result<()> {
+        std::os::unix::fs::symlink(target, link)
+    }
+
+    #[inline]
+    pub fn replace(source: &Path, target: &Path) -> Result<()> {
+        crate::interrupt::retry(|| fs::rename(source, target))
+    }
+
+    #[inline]
+    pub fn remove(link: &Path) -> Result<()> {
+        fs::remove_file(link)
+    }
+
+    #[inline]
+    pub fn is_link(metadata: &fs::Metadata) -> bool {
+        metadata.file_type().is_symlink()
+    }
+
+    #[inline]
+    pub fn normalize(path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::fs;
+    use std::io::{Error, ErrorKind, Result};
+    use std::os::windows::fs::MetadataExt;
+    use std::path::{Path, PathBuf};
+
+    const ERROR_PRIVILEGE_NOT_HELD: i32 = 1314;
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+
+    pub fn link(target: &Path, link: &Path) -> Result<()> {
+        // Symbolic links require a privilege or the developer mode, and
+        // junctions are used otherwise.
+        match std::os::windows::fs::symlink_dir(target, link) {
+            Err(error) if error.raw_os_error() == Some(ERROR_PRIVILEGE_NOT_HELD) => {
+                crate::junction::create(target, link).map_err(|other| {
+                    Error::new(
+                        other.kind(),
+                        format!(
+                            "neither a symbolic link ({}) nor a junction ({}) can be created",
+                            error, other,
+                        ),
+                    )
+                })
+            }
+            result => result,
+        }
+    }
+
+    pub fn replace(source: &Path, target: &Path) -> Result<()> {
+        // Links to folders are folders themselves and are not replaced by
+        // renaming, so that there is a moment without a link.
+        match fs::remove_dir(target) {
+            Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+            _ => {}
+        }
+        fs::rename(source, target)
+    }
+
+    #[inline]
+    pub fn remove(link: &Path) -> Result<()> {
+        fs::remove_dir(link)
+    }
+
+    #[inline]
+    pub fn is_link(metadata: &fs::Metadata) -> bool {
+        metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0
+    }
+
+    pub fn normalize(path: &Path) -> PathBuf {
+        crate::verbatim::path(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::fs;
+    use std::io::{Error, ErrorKind, Result};
+    use std::path::{Path, PathBuf};
+
+    #[inline]
+    pub fn link(_: &Path, _: &Path) -> Result<()> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "links cannot be created on this platform",
+        ))
+    }
+
+    #[inline]
+    pub fn replace(source: &Path, target: &Path) -> Result<()> {
+        fs::rename(source, target)
+    }
+
+    #[inline]
+    pub fn remove(link: &Path) -> Result<()> {
+        fs::remove_file(link)
+    }
+
+    #[inline]
+    pub fn is_link(metadata: &fs::Metadata) -> bool {
+        metadata.file_type().is_symlink()
+    }
+
+    #[inline]
+    pub fn normalize(path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+}
+
+#[cfg(all(test, any(unix, windows)))]
+mod tests {
+    use std::fs;
+
+    use crate::Folder;
+
+    #[test]
+    fn alias() {
+        let root = Folder::new("foo").unwrap();
+        let link = root.join("latest");
+        let one = Folder::with_parent(&root, "bar").unwrap();
+        let other = Folder::with_parent(&root, "bar").unwrap();
+        fs::write(one.join("bar.txt"), "Hi there!").unwrap();
+
+        let guard = one.alias(&link).unwrap();
+        assert_eq!(guard.path(), link);
+        assert!(super::points_at(&link, one.path()));
+        assert_eq!(
+            fs::read_to_string(link.join("bar.txt")).unwrap(),
+            "Hi there!"
+        );
+        drop(guard);
+        assert!(fs::symlink_metadata(&link).is_err());
+
+        let guard = one.alias(&link).unwrap();
+        let other_guard = other.alias(&link).unwrap();
+        assert!(super::points_at(&link, other.path()));
+        drop(guard);
+        assert!(super::points_at(&link, other.path()));
+        drop(other_guard);
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert_eq!(root.entries().unwrap().count(), 2);
+
+        fs::write(&link, "Hi there!").unwrap();
+        let error = one.alias(&link).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&link).unwrap(), "Hi there!");
+    }
+}
@@ -0,0 +1,153 @@
+use std::fs;
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+
+/// Write the content of `root` to a gzip-compressed tarball at `target`.
+///
+/// Entries are stored in lexicographic order with paths relative to `root`
+/// and without timestamps or owners, so that the same content results in the
+/// same archive. Symbolic links are stored as links. The size of the archive is
+/// returned.
+pub fn write(root: &Path, target: &Path) -> Result<u64> {
+    let context = |error: Error| {
+        Error::new(
+            error.kind(),
+            format!(
+                "failed to archive {} to {}: {}",
+                root.display(),
+                target.display(),
+                error,
+            ),
+        )
+    };
+    let absolute = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => {
+            let name = target.file_name().unwrap_or_default();
+            fs::canonicalize(parent).map_err(context)?.join(name)
+        }
+        _ => std::path::absolute(target).map_err(context)?,
+    };
+    let canonical = fs::canonicalize(root).map_err(context)?;
+    if absolute.starts_with(root) || absolute.starts_with(&canonical) {
+        return Err(context(Error::new(
+            ErrorKind::InvalidInput,
+            "the archive would be inside the folder being archived",
+        )));
+    }
+    let file = fs::File::create(target).map_err(context)?;
+    let encoder =
+        flate2::write::GzEncoder::new(BufWriter::new(file), flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    builder.mode(tar::HeaderMode::Deterministic);
+    builder.follow_symlinks(false);
+    append(&mut builder, root, &mut PathBuf::new()).map_err(context)?;
+    let mut writer = builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(context)?;
+    writer.flush().map_err(context)?;
+    let file = writer
+        .into_inner()
+        .map_err(|error| context(error.into_error()))?;
+    file.sync_all().map_err(context)?;
+    Ok(file.metadata().map_err(context)?.len())
+}
+
+fn append<W: Write>(
+    builder: &mut tar::Builder<W>,
+    root: &Path,
+    relative: &mut PathBuf,
+) -> Result<()> {
+    let folder = root.join(&*relative);
+    let mut entries = fs::read_dir(&folder)?
+        .map(|entry| entry.and_then(|entry| Ok((entry.file_name(), entry.file_type()?))))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort_by(|one, other| one.0.cmp(&other.0));
+    for (name, file_type) in entries {
+        relative.push(&name);
+        let path = folder.join(&name);
+        if file_type.is_dir() {
+            builder.append_dir(&*relative, &path)?;
+            append(builder, root, relative)?;
+        } else {
+            builder.append_path_with_name(&path, &*relative)?;
+        }
+        relative.pop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Read;
+
+    use crate::Folder;
+
+    #[test]
+    fn write() {
+        let root = Folder::new("foo").unwrap();
+        let folder = Folder::with_parent(&root, "bar").unwrap();
+        folder.write("b.txt", "Hi there!").unwrap();
+        folder.write("a/c.txt", "Bye!").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("b.txt", folder.join("d.txt")).unwrap();
+
+        let one = root.join("one.tar.gz");
+        let size = folder.archive_to(&one).unwrap();
+        assert_eq!(size, fs::metadata(&one).unwrap().len());
+        let other = root.join("other.tar.gz");
+        folder.archive_to(&other).unwrap();
+        assert_eq!(fs::read(&one).unwrap(), fs::read(&other).unwrap());
+
+        let mut archive =
+            tar::Archive::new(flate2::read::GzDecoder::new(fs::File::open(&one).unwrap()));
+        let mut names = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let name = entry.path().unwrap().to_string_lossy().replace('\\', "/");
+            match &*name {
+                "a/c.txt" => {
+                    let mut content = String::new();
+                    entry.read_to_string(&mut content).unwrap();
+                    assert_eq!(content, "Bye!");
+                }
+                "d.txt" => {
+                    assert!(entry.header().entry_type().is_symlink());
+                    let target = entry.link_name().unwrap().unwrap();
+                    assert_eq!(target.to_str(), Some("b.txt"));
+                }
+                _ => {}
+            }
+            names.push(name);
+        }
+        #[cfg(unix)]
+        assert_eq!(names, ["a", "a/c.txt", "b.txt", "d.txt"]);
+
+        assert!(folder.archive_to(folder.join("foo.tar.gz")).is_err());
+        assert!(!folder.join("foo.tar.gz").exists());
+    }
+
+    #[test]
+    fn archive_on_drop() {
+        let root = Folder::new("foo").unwrap();
+        let target = root.join("foo.tar.gz");
+
+        let mut folder = Folder::with_parent(&root, "bar").unwrap();
+        folder.archive_on_drop(&target);
+        drop(folder);
+        assert!(!target.exists());
+
+        let path = root.to_path_buf();
+        let result = std::thread::spawn(move || {
+            let mut folder = Folder::with_parent(&path, "bar").unwrap();
+            folder.write("bar.txt", "Hi there!").unwrap();
+            folder.archive_on_drop(path.join("foo.tar.gz"));
+            panic!("failed");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(fs::metadata(&target).unwrap().len() > 0);
+        assert_eq!(root.entries().unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,224 @@
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{Error, Result};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use crate::Folder;
+
+/// A temporary folder for asynchronous code.
+///
+/// It is the same as [`Folder`] except that creation and removal are
+/// performed on the blocking thread pool of Tokio. When the object goes out
+/// of scope inside a runtime, the removal is handed over to the pool without
+/// waiting for it to finish, and errors are passed to the cleanup error
+/// handler; outside a runtime, the removal blocks as usual. With the `async`
+/// feature enabled, the same applies to [`Folder`].
+pub struct AsyncFolder {
+    folder: Option<Folder>,
+}
+
+impl AsyncFolder {
+    /// Create a temporary folder.
+    ///
+    /// See [`Folder::new`] for details.
+    pub async fn new<T: AsRef<OsStr>>(prefix: T) -> crate::Result<AsyncFolder> {
+        let prefix = prefix.as_ref().to_os_string();
+        create(crate::env::default_parent(), move || Folder::new(prefix)).await
+    }
+
+    /// Create a temporary folder in a specific folder.
+    ///
+    /// See [`Folder::with_parent`] for details.
+    pub async fn with_parent<T: AsRef<Path>, U: AsRef<OsStr>>(
+        parent: T,
+        prefix: U,
+    ) -> crate::Result<AsyncFolder> {
+        let parent = parent.as_ref().to_path_buf();
+        let prefix = prefix.as_ref().to_os_string();
+        create(parent.clone(), move || Folder::with_parent(parent, prefix)).await
+    }
+
+    /// Convert a folder.
+    #[inline]
+    pub fn from_sync(folder: Folder) -> AsyncFolder {
+        AsyncFolder {
+            folder: Some(folder),
+        }
+    }
+
+    /// Convert into a folder.
+    #[inline]
+    pub fn into_sync(mut self) -> Folder {
+        self.folder.take().unwrap()
+    }
+
+    /// Return the path to the folder.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.as_ref()
+    }
+
+    /// Return the path to the folder and dispose the object without removing
+    /// the actual folder.
+    #[inline]
+    pub fn into_path(self) -> PathBuf {
+        self.into_sync().into_path()
+    }
+
+    /// Remove the folder.
+    pub async fn remove(self) -> Result<()> {
+        let folder = self.into_sync();
+        spawn(move || folder.remove().map_err(Error::from)).await
+    }
+}
+
+impl AsRef<Path> for AsyncFolder {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.folder.as_ref().unwrap().path()
+    }
+}
+
+impl fmt::Debug for AsyncFolder {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.path().fmt(formatter)
+    }
+}
+
+impl Deref for AsyncFolder {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        self.path()
+    }
+}
+
+thread_local! {
+    static BLOCKING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A folder whose removal is allowed to block.
+struct Blocking(Option<Folder>);
+
+impl Drop for Blocking {
+    fn drop(&mut self) {
+        let previous = BLOCKING.with(|blocking| blocking.replace(true));
+        drop(self.0.take());
+        BLOCKING.with(|blocking| blocking.set(previous));
+    }
+}
+
+/// Hand the removal of a folder going out of scope over to the blocking thread
+/// pool if inside a runtime.
+pub fn offload(folder: &mut Folder) -> bool {
+    if BLOCKING.with(Cell::get) {
+        return false;
+    }
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return false;
+    };
+    #[cfg(debug_assertions)]
+    if folder.options().panic_on_blocking_drop && !std::thread::panicking() {
+        panic!(
+            "the temporary folder {} went out of scope inside a runtime",
+            folder.path().display(),
+        );
+    }
+    let folder = Blocking(Some(folder.disarm()));
+    handle.spawn_blocking(move || drop(folder));
+    true
+}
+
+async fn create<F>(parent: PathBuf, f: F) -> crate::Result<AsyncFolder>
+where
+    F: FnOnce() -> crate::Result<Folder> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result.map(AsyncFolder::from_sync),
+        Err(error) => Err(crate::Error::new(
+            crate::Operation::CreateFolder,
+            parent,
+            Error::other(error),
+        )),
+    }
+}
+
+async fn spawn<F, T>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(error) => Err(Error::other(error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncFolder;
+
+    fn run<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn new() {
+        run(async {
+            let folder = AsyncFolder::new("foo").await.unwrap();
+            let path = folder.to_path_buf();
+            assert!(path.is_dir());
+            folder.remove().await.unwrap();
+            assert!(!path.exists());
+
+            let root = AsyncFolder::new("foo").await.unwrap();
+            let folder = AsyncFolder::with_parent(&root, "bar").await.unwrap();
+            assert_eq!(folder.parent(), Some(root.path()));
+            let folder = AsyncFolder::from_sync(folder.into_sync());
+            assert!(folder.is_dir());
+        });
+    }
+
+    #[test]
+    fn cleanup() {
+        let path = run(async {
+            let folder = AsyncFolder::new("foo").await.unwrap();
+            folder.to_path_buf()
+        });
+        assert!(!path.exists());
+
+        let folder = run(AsyncFolder::new("foo")).unwrap();
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+
+        let path = run(async {
+            let folder = crate::Folder::new("foo").unwrap();
+            let path = folder.to_path_buf();
+            drop(folder);
+            path
+        });
+        assert!(!path.exists());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn panic_on_blocking_drop() {
+        let result = std::panic::catch_unwind(|| {
+            run(async {
+                crate::Builder::new()
+                    .prefix("foo")
+                    .panic_on_blocking_drop(true)
+                    .folder()
+                    .unwrap();
+            })
+        });
+        assert!(result.is_err());
+    }
+}
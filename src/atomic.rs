@@ -0,0 +1,117 @@
+use std::fs;
+use std::io::{ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+
+use crate::File;
+
+/// A file replacing a target atomically once committed.
+///
+/// The content is written to a temporary file in the folder of the target,
+/// which ensures that the final rename does not cross file systems. If the
+/// object goes out of scope without being committed, the temporary file is
+/// removed, and the target is left intact.
+#[derive(Debug)]
+pub struct AtomicWriteFile {
+    file: File,
+    target: PathBuf,
+    sync_parent: bool,
+    preserve_mode: bool,
+}
+
+impl AtomicWriteFile {
+    /// Start writing a file that will replace `target`.
+    pub fn new<T: AsRef<Path>>(target: T) -> crate::Result<AtomicWriteFile> {
+        let target = target.as_ref();
+        let parent = match target.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let prefix = target.file_name().unwrap_or_default();
+        Ok(AtomicWriteFile {
+            file: File::with_parent(parent, prefix)?,
+            target: target.to_path_buf(),
+            sync_parent: false,
+            preserve_mode: false,
+        })
+    }
+
+    /// Synchronize the parent folder with the disk after the rename.
+    #[inline]
+    pub fn sync_parent(&mut self, value: bool) -> &mut Self {
+        self.sync_parent = value;
+        self
+    }
+
+    /// Copy the permissions of an existing target before the rename.
+    #[inline]
+    pub fn preserve_mode(&mut self, value: bool) -> &mut Self {
+        self.preserve_mode = value;
+        self
+    }
+
+    /// Return the path to the temporary file.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    /// Synchronize the content with the disk and replace the target.
+    pub fn commit(mut self) -> Result<()> {
+        self.file.flush()?;
+        self.file.sync_all()?;
+        if self.preserve_mode {
+            match fs::metadata(&self.target) {
+                Ok(metadata) => fs::set_permissions(self.file.path(), metadata.permissions())?,
+                Err(error) if error.kind() == ErrorKind::NotFound => {}
+                Err(error) => return Err(error),
+            }
+        }
+        self.file.persist(&self.target)?;
+        if self.sync_parent {
+            crate::sync::parent(&self.target)?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for AtomicWriteFile {
+    #[inline]
+    fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+        self.file.write(buffer)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AtomicWriteFile;
+    use crate::Folder;
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn commit() {
+        let folder = Folder::new("foo").unwrap();
+        let target = folder.join("foo.txt");
+        fs::write(&target, "Hello!").unwrap();
+
+        let mut file = AtomicWriteFile::new(&target).unwrap();
+        file.write_all(b"Hi there!").unwrap();
+        let path = file.path().to_path_buf();
+        assert_eq!(path.parent(), target.parent());
+        drop(file);
+        assert!(fs::metadata(&path).is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "Hello!");
+
+        let mut file = AtomicWriteFile::new(&target).unwrap();
+        file.sync_parent(true).preserve_mode(true);
+        file.write_all(b"Hi there!").unwrap();
+        file.commit().unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "Hi there!");
+        assert_eq!(fs::read_dir(folder.path()).unwrap().count(), 1);
+    }
+}
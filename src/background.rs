@@ -0,0 +1,136 @@
+use std::io::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::{fmt, thread};
+
+use crate::Folder;
+
+/// A removal of a temporary folder running in the background.
+///
+/// The removal runs to completion even if the object goes out of scope, in
+/// which case errors are passed to the cleanup error handler; see
+/// [`set_cleanup_error_handler`](crate::set_cleanup_error_handler).
+pub struct BackgroundRemoval {
+    path: PathBuf,
+    receiver: mpsc::Receiver<Result<()>>,
+}
+
+impl BackgroundRemoval {
+    pub(crate) fn spawn(folder: Folder) -> BackgroundRemoval {
+        let path = folder.to_path_buf();
+        let (sender, receiver) = mpsc::channel();
+        let context = path.clone();
+        // In case of failure, the closure is dropped, and so is the folder,
+        // which removes it right away.
+        let _ = thread::Builder::new()
+            .name("temporary".into())
+            .spawn(move || {
+                if let Err(mpsc::SendError(Err(error))) =
+                    sender.send(folder.remove().map_err(Error::from))
+                {
+                    crate::hook::report(&context, &error);
+                }
+            });
+        BackgroundRemoval { path, receiver }
+    }
+
+    /// Return the path to the folder being removed.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Wait for the removal to finish.
+    pub fn wait(self) -> Result<()> {
+        match self.receiver.recv() {
+            Ok(result) => result,
+            Err(_) => Err(Error::other(format!(
+                "the removal of {} was interrupted",
+                self.path.display(),
+            ))),
+        }
+    }
+
+    /// Let the removal finish on its own.
+    #[inline]
+    pub fn detach(self) {}
+}
+
+/// An outcome of a removal with a deadline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemovalOutcome {
+    /// The folder has been removed.
+    Completed,
+    /// The deadline has passed before the removal finished.
+    ///
+    /// The removal continues in the background for as long as the process
+    /// runs.
+    TimedOut {
+        /// The path to the folder.
+        path: PathBuf,
+    },
+}
+
+impl fmt::Debug for BackgroundRemoval {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.path.fmt(formatter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RemovalOutcome;
+    use crate::Folder;
+
+    #[test]
+    fn wait() {
+        let folder = Folder::new("foo").unwrap();
+        std::fs::create_dir_all(folder.join("bar").join("baz")).unwrap();
+        let path = folder.to_path_buf();
+        let removal = folder.remove_in_background();
+        assert_eq!(removal.path(), path);
+        removal.wait().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn detach() {
+        let folder = Folder::new("bar").unwrap();
+        let path = folder.to_path_buf();
+        folder.remove_in_background().detach();
+        for _ in 0..1000 {
+            if !path.exists() {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        panic!("the folder has not been removed");
+    }
+
+    #[test]
+    fn remove_with_timeout() {
+        let folder = Folder::new("foo").unwrap();
+        folder.write("bar/baz.txt", "Hi there!").unwrap();
+        let path = folder.to_path_buf();
+        let outcome = folder.remove_with_timeout(Duration::from_secs(60)).unwrap();
+        assert_eq!(outcome, RemovalOutcome::Completed);
+        assert!(!path.exists());
+
+        let folder = Folder::new("foo").unwrap();
+        for i in 0..5000 {
+            folder.touch(format!("{}/{i}.txt", i % 10)).unwrap();
+        }
+        let path = folder.to_path_buf();
+        let outcome = folder.remove_with_timeout(Duration::ZERO).unwrap();
+        assert_eq!(outcome, RemovalOutcome::TimedOut { path: path.clone() });
+        for _ in 0..10000 {
+            if !path.exists() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        panic!("the folder has not been removed");
+    }
+}
@@ -0,0 +1,770 @@
+use std::ffi::{OsStr, OsString};
+use std::fs::Permissions;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{Backend, File, Filesystem, Folder, NameStyle, Operation, ReservedPath, TempPath};
+
+/// A builder of temporary folders and files.
+///
+/// The builder can be reused to create several folders and files with the same
+/// configuration.
+///
+/// ```rust
+/// use temporary::Builder;
+///
+/// let folder = Builder::new()
+///     .prefix("job")
+///     .suffix(".work")
+///     .random_len(16)
+///     .folder()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Builder {
+    pub(crate) parents: Vec<PathBuf>,
+    pub(crate) prefix: OsString,
+    pub(crate) suffix: String,
+    pub(crate) random_len: usize,
+    pub(crate) max_name_len: usize,
+    pub(crate) truncate_prefix: bool,
+    pub(crate) name_style: NameStyle,
+    pub(crate) retries: u32,
+    pub(crate) create_parent: bool,
+    pub(crate) sanitize_prefix: bool,
+    pub(crate) canonicalize: bool,
+    pub(crate) timestamped: bool,
+    pub(crate) keep_on_panic: bool,
+    pub(crate) removal: crate::remove::Policy,
+    pub(crate) verify_identity: bool,
+    pub(crate) verify_owner: bool,
+    pub(crate) secure_parent: bool,
+    pub(crate) free_space: Option<u64>,
+    #[cfg(feature = "acl")]
+    pub(crate) restrict: bool,
+    #[cfg(feature = "owner")]
+    pub(crate) owner: Option<(u32, u32)>,
+    pub(crate) register_for_exit: bool,
+    pub(crate) session: Option<crate::session::Handle>,
+    pub(crate) filesystem: Option<Arc<dyn Filesystem>>,
+    pub(crate) sync: bool,
+    pub(crate) seed: Option<u64>,
+    pub(crate) on_retry: Option<fn(&Path, u32)>,
+    pub(crate) backend: Backend,
+    pub(crate) verbatim: bool,
+    pub(crate) temporary_hint: bool,
+    pub(crate) inheritable: bool,
+    pub(crate) delete_on_close: bool,
+    #[cfg(feature = "async")]
+    pub(crate) panic_on_blocking_drop: bool,
+    pub(crate) permissions: Option<Permissions>,
+}
+
+impl Builder {
+    /// Create a builder.
+    ///
+    /// By default, entries are created in the default temporary folder with
+    /// names consisting of 12 random characters.
+    #[inline]
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Set the folder to create entries in.
+    #[inline]
+    pub fn parent<T: AsRef<Path>>(&mut self, parent: T) -> &mut Self {
+        self.parents = vec![parent.as_ref().to_path_buf()];
+        self
+    }
+
+    /// Set several candidate folders to create entries in.
+    ///
+    /// The candidates are tried in order until an entry is created. Only
+    /// errors of kinds `NotFound`, `PermissionDenied`, `ReadOnlyFilesystem`,
+    /// and `StorageFull` lead to trying the next candidate; any other error is
+    /// returned right away. If all the candidates fail, the error mentions
+    /// each of them.
+    #[inline]
+    pub fn parents<I>(&mut self, parents: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        self.parents = parents
+            .into_iter()
+            .map(|parent| parent.as_ref().to_path_buf())
+            .collect();
+        self
+    }
+
+    /// Set the prefix of names.
+    ///
+    /// A nonempty prefix is separated from the random part by a dot.
+    #[inline]
+    pub fn prefix<T: AsRef<OsStr>>(&mut self, prefix: T) -> &mut Self {
+        self.prefix = prefix.as_ref().to_os_string();
+        self
+    }
+
+    /// Replace invalid characters in the prefix instead of failing.
+    ///
+    /// A prefix may not contain path separators and nul characters, and it may
+    /// not be `.` or `..`. By default, such a prefix results in an error of
+    /// kind `InvalidInput`; otherwise, offending characters are replaced with
+    /// underscores.
+    #[inline]
+    pub fn sanitize_prefix(&mut self, value: bool) -> &mut Self {
+        self.sanitize_prefix = value;
+        self
+    }
+
+    /// Set the suffix of names.
+    ///
+    /// The suffix is appended to the random part as is, and it may not contain
+    /// path separators.
+    #[inline]
+    pub fn suffix(&mut self, suffix: &str) -> &mut Self {
+        self.suffix = suffix.to_string();
+        self
+    }
+
+    /// Resolve symbolic links in the path to the parent folder.
+    ///
+    /// The paths of entries are then canonical, which matters, for instance,
+    /// on macOS, where the default temporary folder is behind a symbolic link.
+    /// On Windows, canonical paths have the extended-length prefix.
+    ///
+    /// Without it, relative parents are resolved against the current folder
+    /// once, and `.` and `..` components are normalized lexically, so `..`
+    /// after a symbolic link refers to the folder containing the link rather
+    /// than the one containing its target.
+    #[inline]
+    pub fn canonicalize(&mut self, value: bool) -> &mut Self {
+        self.canonicalize = value;
+        self
+    }
+
+    /// Create the parent folder and its ancestors if they do not exist.
+    ///
+    /// On Unix, the permissions of entries apply to the created folders too.
+    /// Only the entry itself is removed later on.
+    #[inline]
+    pub fn create_parent(&mut self, value: bool) -> &mut Self {
+        self.create_parent = value;
+        self
+    }
+
+    /// Generate names from a fixed seed.
+    ///
+    /// The same seed yields the same sequence of candidate names, and names
+    /// that are taken are skipped as usual, which makes it possible to have
+    /// stable names in snapshot tests. **It must never be used in production**:
+    /// predictable names allow other users to create entries at those paths in
+    /// advance, such as symbolic links pointing elsewhere.
+    #[inline]
+    pub fn deterministic_seed(&mut self, value: u64) -> &mut Self {
+        self.seed = Some(value);
+        self
+    }
+
+    /// Set the number of random characters in names.
+    #[inline]
+    pub fn random_len(&mut self, value: usize) -> &mut Self {
+        self.random_len = value;
+        self
+    }
+
+    /// Set the maximum length of names in bytes.
+    ///
+    /// Names that would be longer lead to an error unless the prefix is to be
+    /// truncated. The default is 255, which is the limit of most file systems;
+    /// some, such as eCryptfs, have lower limits.
+    #[inline]
+    pub fn max_name_len(&mut self, value: usize) -> &mut Self {
+        self.max_name_len = value;
+        self
+    }
+
+    /// Truncate the prefix when names would be too long.
+    ///
+    /// The prefix is cut at a character boundary so that names fit within
+    /// [`max_name_len`](Builder::max_name_len), and the random part is kept in
+    /// full. Prefixes that are not valid Unicode are not truncated.
+    #[inline]
+    pub fn truncate_prefix(&mut self, value: bool) -> &mut Self {
+        self.truncate_prefix = value;
+        self
+    }
+
+    /// Embed the time of creation in names.
+    ///
+    /// The time is in UTC and has the form `20240611T142233`, which sorts
+    /// chronologically. It is placed between the prefix and the random part
+    /// and is separated from the latter by a dot.
+    #[inline]
+    pub fn timestamped(&mut self, value: bool) -> &mut Self {
+        self.timestamped = value;
+        self
+    }
+
+    /// Set the style of the random part of names.
+    #[inline]
+    pub fn name_style(&mut self, value: NameStyle) -> &mut Self {
+        self.name_style = value;
+        self
+    }
+
+    /// Set the number of candidate names to try.
+    ///
+    /// Only collisions with existing entries lead to trying another name; any
+    /// other error is returned right away. The default is 2^16.
+    #[inline]
+    pub fn retries(&mut self, value: u32) -> &mut Self {
+        self.retries = value;
+        self
+    }
+
+    /// Call a function whenever a candidate name is taken.
+    ///
+    /// The function is called with the candidate path and the number of the
+    /// attempt starting from one. It is useful for monitoring the contention
+    /// between names.
+    #[inline]
+    pub fn on_retry(&mut self, value: fn(&Path, u32)) -> &mut Self {
+        self.on_retry = Some(value);
+        self
+    }
+
+    /// Set the way of creating folders.
+    ///
+    /// It has no effect on files. See [`Backend`] for details.
+    #[inline]
+    pub fn backend(&mut self, value: Backend) -> &mut Self {
+        self.backend = value;
+        self
+    }
+
+    /// Give folders paths in the extended-length form.
+    ///
+    /// On Windows, the paths of folders get the `\\?\` prefix, so that paths
+    /// joined onto them are not limited to `MAX_PATH` characters. Parents that
+    /// already have the prefix are accepted either way. It has no effect on
+    /// other platforms.
+    #[inline]
+    pub fn verbatim(&mut self, value: bool) -> &mut Self {
+        self.verbatim = value;
+        self
+    }
+
+    /// Hint that files are short-lived.
+    ///
+    /// On Windows, files are created with `FILE_ATTRIBUTE_TEMPORARY`, which
+    /// asks the system to keep the content in the cache instead of writing it
+    /// to the disk eagerly. The files behave as usual otherwise and can still
+    /// be persisted, in which case they keep the attribute. It has no effect on
+    /// folders or on other platforms. Anonymous files always have the hint.
+    #[inline]
+    pub fn temporary_hint(&mut self, value: bool) -> &mut Self {
+        self.temporary_hint = value;
+        self
+    }
+
+    /// Let child processes inherit the handles of files.
+    ///
+    /// Files are opened with `O_CLOEXEC` on Unix and with non-inheritable
+    /// handles on Windows by default, so that child processes do not keep them
+    /// open, which would prevent their removal on Windows. It is meant for
+    /// passing files to executed programs, and handles returned by
+    /// [`File::reopen`](crate::File::reopen) follow the same setting. It has
+    /// no effect on folders or on other platforms. On Unix without the `libc`
+    /// feature, the creation of files fails.
+    #[inline]
+    pub fn inheritable(&mut self, value: bool) -> &mut Self {
+        self.inheritable = value;
+        self
+    }
+
+    /// Let the system remove folders even if the process is terminated.
+    ///
+    /// On Windows, a handle to each folder is kept open with delete-on-close
+    /// semantics, so that the system removes the folder once the handle is
+    /// closed, which happens even if the process is killed. Only empty folders
+    /// can be removed this way, and the content is still removed as usual when
+    /// the object goes out of scope. Whenever the folder is meant to outlive the
+    /// object, such as after [`Folder::keep`] or [`Folder::into_path`], the
+    /// disposition is cleared. Other processes can open the folder only with
+    /// sharing for deletion, which the standard library uses by default. It
+    /// has no effect on other platforms.
+    #[inline]
+    pub fn delete_on_close(&mut self, value: bool) -> &mut Self {
+        self.delete_on_close = value;
+        self
+    }
+
+    /// Keep folders when they go out of scope while the thread is panicking.
+    ///
+    /// See [`Folder::keep_on_panic`] for details.
+    #[inline]
+    pub fn keep_on_panic(&mut self, value: bool) -> &mut Self {
+        self.keep_on_panic = value;
+        self
+    }
+
+    /// Panic when folders go out of scope inside an asynchronous runtime.
+    ///
+    /// By default, such folders are removed on the blocking thread pool of the
+    /// runtime. The panic helps finding the places where folders should be
+    /// closed explicitly instead. It has no effect without debug assertions.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub fn panic_on_blocking_drop(&mut self, value: bool) -> &mut Self {
+        self.panic_on_blocking_drop = value;
+        self
+    }
+
+    /// Set the time the removal of folders may spend retrying.
+    ///
+    /// On Windows, entries held open by other processes, such as antivirus
+    /// scanners, cannot be removed for a while, and the removal is retried
+    /// with an exponential backoff. It has no effect on other platforms. The
+    /// default is 1.5 seconds.
+    #[inline]
+    pub fn removal_retry(&mut self, value: Duration) -> &mut Self {
+        self.removal.retry = value;
+        self
+    }
+
+    /// Make read-only entries writable when removing folders.
+    ///
+    /// On Windows, read-only files, which are common in Git checkouts, cannot
+    /// be removed otherwise. Since the content of temporary folders is owned
+    /// by their creators, it is enabled by default. It has no effect on other
+    /// platforms.
+    #[inline]
+    pub fn clear_readonly(&mut self, value: bool) -> &mut Self {
+        self.removal.clear_readonly = value;
+        self
+    }
+
+    /// Extend permissions as needed when removing folders.
+    ///
+    /// See [`Folder::force_remove`] for details.
+    #[inline]
+    pub fn force_remove(&mut self, value: bool) -> &mut Self {
+        self.removal.force = value;
+        self
+    }
+
+    /// Overwrite the content of files with zeros before removing folders.
+    ///
+    /// See [`Folder::shred_and_remove`] for details.
+    #[inline]
+    pub fn shred(&mut self, value: bool) -> &mut Self {
+        self.removal.shred = value;
+        self
+    }
+
+    /// Check that folders have not been replaced before removing them.
+    ///
+    /// The identity of a folder, such as the device and inode numbers on Unix,
+    /// is recorded at creation, and the folder is not removed if the path
+    /// refers to another entry later on, which is then reported as an error.
+    /// Files belonging to a [`Session`](crate::Session) are checked the same
+    /// way. It is enabled by default and can be disabled for file systems
+    /// without stable identities.
+    #[inline]
+    pub fn verify_identity(&mut self, value: bool) -> &mut Self {
+        self.verify_identity = value;
+        self
+    }
+
+    /// Refuse to create entries in insecure parents.
+    ///
+    /// On Unix, the parent has to be owned by the current user or the
+    /// superuser, and if it is writable by everyone, it has to have the sticky
+    /// bit set, as `/tmp` does, so that other users cannot remove or replace
+    /// the entries. Otherwise, an error explaining the problem is returned.
+    /// Without the `libc` feature, the current user is unknown, and the parent
+    /// has to be owned by the superuser. It has no effect on other platforms.
+    #[inline]
+    pub fn require_secure_parent(&mut self, value: bool) -> &mut Self {
+        self.secure_parent = value;
+        self
+    }
+
+    /// Refuse to create entries on file systems with less than `bytes`
+    /// available.
+    ///
+    /// The space is checked once the parent is selected, and an error of kind
+    /// [`StorageFull`](std::io::ErrorKind::StorageFull) naming the parent and
+    /// both amounts is returned if it falls short. With several parents, the
+    /// next one is tried. See [`free_space`](crate::free_space) for details.
+    #[inline]
+    pub fn require_free_space(&mut self, bytes: u64) -> &mut Self {
+        self.free_space = Some(bytes);
+        self
+    }
+
+    /// Grant access to folders only to the current user.
+    ///
+    /// On Windows, folders get an access control list with full control for
+    /// the current user and the system, which is not inherited from the parent
+    /// and applies to the content too. A failure to apply it fails the
+    /// creation, and the folder is removed. It has no effect on other
+    /// platforms, where folders are private by default.
+    #[cfg(feature = "acl")]
+    #[inline]
+    pub fn restrict_to_current_user(&mut self, value: bool) -> &mut Self {
+        self.restrict = value;
+        self
+    }
+
+    /// Set the owner and the group of folders.
+    ///
+    /// On Unix, the change is applied to a descriptor of the folder opened
+    /// right after creation, so that the path cannot be swapped in between,
+    /// which usually requires privileges. A failure to apply it fails the
+    /// creation, and the folder is removed. Together with
+    /// [`permissions`](Builder::permissions), it allows for handing folders
+    /// over to other users. It has no effect on other platforms.
+    #[cfg(feature = "owner")]
+    #[inline]
+    pub fn owner(&mut self, user: u32, group: u32) -> &mut Self {
+        self.owner = Some((user, group));
+        self
+    }
+
+    /// Check that folders still belong to the same user before removing them.
+    ///
+    /// The owner of a folder is recorded at creation, and the folder is not
+    /// removed if it has changed later on, which is then reported as an error.
+    /// Files belonging to a [`Session`](crate::Session) are checked the same
+    /// way. It is enabled by default and has an effect only on Unix. It can be
+    /// disabled when ownership changes legitimately, such as when a service
+    /// running as root hands folders over to other users.
+    #[inline]
+    pub fn verify_owner(&mut self, value: bool) -> &mut Self {
+        self.verify_owner = value;
+        self
+    }
+
+    /// Remove folders when the process exits even if they are still in scope.
+    ///
+    /// Folders are recorded in a global registry until they are removed, kept,
+    /// or disposed, and with the `libc` feature, the registry is cleared by a
+    /// handler run at exit, such as after calling [`std::process::exit`], where
+    /// destructors do not run. If panics abort the process, the registry is
+    /// cleared by a panic hook as well, which chains to the previously
+    /// installed one. Nothing can be done when the process is killed abruptly,
+    /// such as via `SIGKILL`. The default is false.
+    #[inline]
+    pub fn register_for_exit(&mut self, value: bool) -> &mut Self {
+        self.register_for_exit = value;
+        self
+    }
+
+    /// Add folders and files to a session.
+    ///
+    /// See [`Session`](crate::Session) for details.
+    #[inline]
+    pub fn session(&mut self, session: &crate::Session) -> &mut Self {
+        self.session = Some(crate::session::Handle::of(session));
+        self
+    }
+
+    /// Create, rename, and remove folders via a specific file system.
+    ///
+    /// [`Backend`], [`permissions`](Builder::permissions),
+    /// [`canonicalize`](Builder::canonicalize), and
+    /// [`require_free_space`](Builder::require_free_space) have no effect on
+    /// such folders, and files are not affected at all. See [`Filesystem`] for
+    /// details.
+    #[inline]
+    pub fn filesystem(&mut self, value: Arc<dyn Filesystem>) -> &mut Self {
+        self.filesystem = Some(value);
+        self
+    }
+
+    /// Synchronize the parent folder with the disk after creating, renaming,
+    /// and removing folders.
+    ///
+    /// It makes the changes durable in case of a crash at the expense of
+    /// slower operations. The default is false.
+    #[inline]
+    pub fn sync(&mut self, value: bool) -> &mut Self {
+        self.sync = value;
+        self
+    }
+
+    /// Set the permissions of entries.
+    ///
+    /// On Unix, the permissions are applied at creation, subject to the umask,
+    /// and folders get mode 0700 and files mode 0600 if no permissions are
+    /// given. Elsewhere, they are applied right after creation.
+    #[inline]
+    pub fn permissions(&mut self, permissions: Permissions) -> &mut Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// Create a temporary folder.
+    #[inline]
+    pub fn folder(&self) -> crate::Result<Folder> {
+        Folder::create(self).map_err(|error| self.error(Operation::CreateFolder, error))
+    }
+
+    /// Create a temporary file.
+    #[inline]
+    pub fn file(&self) -> crate::Result<File> {
+        File::create(self).map_err(|error| self.error(Operation::CreateFile, error))
+    }
+
+    /// Create a custom temporary entry.
+    ///
+    /// The closure is called with candidate paths and is supposed to create
+    /// an entry there, such as a socket or a named pipe. It is called again
+    /// with another path if it fails with `AlreadyExists`, and any other error
+    /// is returned immediately. The path is removed when the resulting object
+    /// goes out of scope.
+    pub fn make<F, T>(&self, create: F) -> crate::Result<Entry<T>>
+    where
+        F: FnMut(&Path) -> Result<T>,
+    {
+        let (path, value) = crate::name::create(self, create)
+            .map_err(|error| self.error(Operation::CreateEntry, error))?;
+        Ok(Entry {
+            value,
+            path: TempPath::from_path(path),
+        })
+    }
+
+    /// Reserve a vacant path without creating anything.
+    ///
+    /// Candidate paths are checked until one is found where nothing exists.
+    /// See [`ReservedPath`] for details.
+    pub fn reserve(&self) -> crate::Result<ReservedPath> {
+        let (path, _) = crate::name::create(self, |path| match std::fs::symlink_metadata(path) {
+            Ok(_) => Err(std::io::Error::from(std::io::ErrorKind::AlreadyExists)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error),
+        })
+        .map_err(|error| self.error(Operation::ReservePath, error))?;
+        Ok(ReservedPath::new(path))
+    }
+
+    fn error(&self, operation: Operation, error: std::io::Error) -> crate::Error {
+        // With several parents, the underlying error names all of them.
+        let parent = match self.parents.first() {
+            Some(parent) => parent.clone(),
+            _ => crate::env::default_parent(),
+        };
+        crate::Error::new(operation, parent, error)
+    }
+}
+
+/// A custom temporary entry.
+///
+/// The value is dropped before the path is removed.
+#[derive(Debug)]
+pub struct Entry<T> {
+    value: T,
+    path: TempPath,
+}
+
+impl<T> Entry<T> {
+    /// Return the path to the entry.
+    #[inline]
+    pub fn path(&self) -> &TempPath {
+        &self.path
+    }
+
+    /// Return the value and the path separately.
+    #[inline]
+    pub fn into_parts(self) -> (T, TempPath) {
+        (self.value, self.path)
+    }
+}
+
+impl<T> std::ops::Deref for Entry<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> std::ops::DerefMut for Entry<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl Default for Builder {
+    #[inline]
+    fn default() -> Self {
+        Builder {
+            parents: Vec::new(),
+            prefix: OsString::new(),
+            suffix: String::new(),
+            random_len: 12,
+            max_name_len: 255,
+            truncate_prefix: false,
+            name_style: NameStyle::default(),
+            retries: 1 << 16,
+            create_parent: false,
+            sanitize_prefix: false,
+            canonicalize: false,
+            timestamped: false,
+            keep_on_panic: false,
+            removal: crate::remove::Policy::default(),
+            verify_identity: true,
+            verify_owner: true,
+            secure_parent: false,
+            free_space: None,
+            #[cfg(feature = "acl")]
+            restrict: false,
+            #[cfg(feature = "owner")]
+            owner: None,
+            register_for_exit: false,
+            session: None,
+            filesystem: None,
+            sync: false,
+            seed: None,
+            on_retry: None,
+            backend: Backend::default(),
+            verbatim: false,
+            temporary_hint: false,
+            inheritable: false,
+            delete_on_close: false,
+            #[cfg(feature = "async")]
+            panic_on_blocking_drop: false,
+            permissions: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Builder;
+
+    #[test]
+    fn folder() {
+        let parent = crate::Folder::new("foo").unwrap();
+        let mut builder = Builder::new();
+        builder
+            .parent(&parent)
+            .prefix("job")
+            .suffix(".work")
+            .random_len(16);
+        let one = builder.folder().unwrap();
+        let other = builder.folder().unwrap();
+        assert_ne!(one.path(), other.path());
+        for folder in [&one, &other] {
+            assert_eq!(folder.parent(), Some(parent.path()));
+            let name = folder.file_name().unwrap().to_str().unwrap();
+            assert!(name.starts_with("job.") && name.ends_with(".work"));
+            assert_eq!(name.len(), 4 + 16 + 5);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn canonicalize() {
+        let root = crate::Folder::new("foo").unwrap();
+        let root = std::fs::canonicalize(&root).unwrap();
+        std::fs::create_dir(root.join("bar")).unwrap();
+        std::os::unix::fs::symlink(root.join("bar"), root.join("baz")).unwrap();
+        let folder = Builder::new()
+            .parent(root.join("baz"))
+            .canonicalize(true)
+            .folder()
+            .unwrap();
+        assert_eq!(folder.parent(), Some(root.join("bar").as_path()));
+        assert_eq!(std::fs::canonicalize(&folder).unwrap(), folder.path());
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn create_parent() {
+        let root = crate::Folder::new("foo").unwrap();
+        let parent = root.join("bar").join("baz");
+        let mut builder = Builder::new();
+        builder.parent(&parent);
+        assert!(builder.folder().is_err());
+        let folder = builder.create_parent(true).folder().unwrap();
+        assert_eq!(folder.parent(), Some(parent.as_path()));
+        drop(folder);
+        assert!(parent.is_dir());
+    }
+
+    #[test]
+    fn parents() {
+        use std::io::ErrorKind;
+
+        let root = crate::Folder::new("foo").unwrap();
+        let mut builder = Builder::new();
+        builder.parents([root.join("bar"), root.join("baz"), root.to_path_buf()]);
+        let folder = builder.folder().unwrap();
+        assert_eq!(folder.parent(), Some(root.path()));
+
+        builder.parents([root.join("bar"), root.join("baz")]);
+        let error = builder.folder().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        let message = error.to_string();
+        assert!(message.contains(root.join("bar").to_str().unwrap()));
+        assert!(message.contains(root.join("baz").to_str().unwrap()));
+
+        builder
+            .parents([root.to_path_buf(), root.join("bar")])
+            .retries(0);
+        let error = builder.folder().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn make() {
+        use std::io::{Error, ErrorKind};
+
+        let mut count = 0;
+        let entry = Builder::new()
+            .make(|path| {
+                count += 1;
+                if count < 3 {
+                    return Err(Error::from(ErrorKind::AlreadyExists));
+                }
+                std::fs::write(path, "Hi there!").map(|_| 42)
+            })
+            .unwrap();
+        assert_eq!((*entry, count), (42, 3));
+        let path = entry.path().to_path_buf();
+        assert!(path.is_file());
+        drop(entry);
+        assert!(!path.exists());
+
+        let error = Builder::new()
+            .make(|_| Err::<(), _>(Error::from(ErrorKind::PermissionDenied)))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file() {
+        use std::fs::{self, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        let file = Builder::new()
+            .prefix("foo")
+            .permissions(Permissions::from_mode(0o400))
+            .file()
+            .unwrap();
+        let permissions = fs::metadata(file.path()).unwrap().permissions();
+        assert_eq!(permissions.mode() & 0o777, 0o400);
+    }
+}
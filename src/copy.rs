@@ -0,0 +1,68 @@
+use std::fs;
+use std::io::{Error, Result};
+use std::path::Path;
+
+/// Copy the content of `source` into `target` recursively.
+///
+/// Symbolic links are recreated as opposed to being followed, and permissions
+/// are preserved. The standard library relies on `copy_file_range` and
+/// similar facilities where available, which makes copying cheap on file
+/// systems supporting reflinks.
+pub fn content(source: &Path, target: &Path) -> Result<()> {
+    for entry in fs::read_dir(source).map_err(|error| context(error, source))? {
+        let entry = entry.map_err(|error| context(error, source))?;
+        let path = entry.path();
+        let destination = target.join(entry.file_name());
+        entry
+            .file_type()
+            .and_then(|file_type| {
+                if file_type.is_symlink() {
+                    symlink(&path, &destination)
+                } else if file_type.is_dir() {
+                    fs::create_dir(&destination)?;
+                    content(&path, &destination)?;
+                    fs::set_permissions(&destination, entry.metadata()?.permissions())
+                } else {
+                    fs::copy(&path, &destination).map(|_| ())
+                }
+            })
+            .map_err(|error| context(error, &path))?;
+    }
+    Ok(())
+}
+
+fn context(error: Error, path: &Path) -> Error {
+    // Errors from nested entries already name them.
+    if error.get_ref().is_some() {
+        return error;
+    }
+    Error::new(
+        error.kind(),
+        format!("failed to copy {}: {}", path.display(), error),
+    )
+}
+
+#[cfg(unix)]
+fn symlink(source: &Path, target: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, target)
+}
+
+#[cfg(windows)]
+fn symlink(source: &Path, target: &Path) -> Result<()> {
+    use std::os::windows::fs::{symlink_dir, symlink_file, FileTypeExt};
+
+    let link = fs::read_link(source)?;
+    if fs::symlink_metadata(source)?.file_type().is_symlink_dir() {
+        symlink_dir(link, target)
+    } else {
+        symlink_file(link, target)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(source: &Path, _: &Path) -> Result<()> {
+    Err(Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("cannot recreate symbolic link {}", source.display()),
+    ))
+}
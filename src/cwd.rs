@@ -0,0 +1,105 @@
+use std::env;
+use std::io::{Error, Result};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::Folder;
+
+/// A guard restoring the current folder when it goes out of scope.
+///
+/// The current folder is global to the process, and changing it affects all
+/// threads, including tests run in parallel. Nested guards restore the
+/// respective previous folders provided that they are dropped in the reverse
+/// order of creation, which is the case for guards bound to local variables.
+/// Dropping the guard ignores errors; use [`restore`](CwdGuard::restore) to
+/// handle them explicitly.
+#[must_use = "the current folder is restored as soon as the guard is dropped"]
+pub struct CwdGuard<'l> {
+    previous: PathBuf,
+    restored: bool,
+    phantom: PhantomData<&'l Folder>,
+}
+
+impl CwdGuard<'_> {
+    pub(crate) fn enter(path: &Path) -> Result<CwdGuard<'_>> {
+        let previous = env::current_dir()?;
+        env::set_current_dir(path).map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!("failed to enter {}: {}", path.display(), error),
+            )
+        })?;
+        Ok(CwdGuard {
+            previous,
+            restored: false,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Return the path to the folder that will be restored.
+    #[inline]
+    pub fn previous(&self) -> &Path {
+        &self.previous
+    }
+
+    /// Restore the previous current folder.
+    #[inline]
+    pub fn restore(mut self) -> Result<()> {
+        self.cleanup()
+    }
+
+    fn cleanup(&mut self) -> Result<()> {
+        if self.restored {
+            return Ok(());
+        }
+        self.restored = true;
+        env::set_current_dir(&self.previous).map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!("failed to restore {}: {}", self.previous.display(), error),
+            )
+        })
+    }
+}
+
+impl Drop for CwdGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = self.cleanup();
+    }
+}
+
+#[cfg(test)]
+pub static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(test)]
+mod tests {
+    use crate::Folder;
+    use std::{env, fs};
+
+    #[test]
+    fn enter() {
+        let _lock = super::LOCK
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let current = env::current_dir().unwrap();
+        let one = Folder::new("foo").unwrap();
+        let other = one.child("bar").unwrap();
+        {
+            let _one = one.enter().unwrap();
+            let path = fs::canonicalize(&one).unwrap();
+            assert_eq!(env::current_dir().unwrap(), path);
+            let guard = other.enter().unwrap();
+            assert_eq!(guard.previous(), path);
+            assert_eq!(
+                env::current_dir().unwrap(),
+                fs::canonicalize(&other).unwrap()
+            );
+        }
+        assert_eq!(env::current_dir().unwrap(), current);
+
+        let guard = one.enter().unwrap();
+        guard.restore().unwrap();
+        assert_eq!(env::current_dir().unwrap(), current);
+    }
+}
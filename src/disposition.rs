@@ -0,0 +1,96 @@
+use std::ffi::c_void;
+use std::fs::{File, OpenOptions};
+use std::io::{Error, Result};
+use std::os::windows::fs::OpenOptionsExt;
+use std::os::windows::io::AsRawHandle;
+use std::path::Path;
+
+const DELETE: u32 = 0x00010000;
+const FILE_SHARE_READ: u32 = 0x1;
+const FILE_SHARE_WRITE: u32 = 0x2;
+const FILE_SHARE_DELETE: u32 = 0x4;
+const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x02000000;
+const FILE_FLAG_DELETE_ON_CLOSE: u32 = 0x04000000;
+const FILE_FLAG_OPEN_REPARSE_POINT: u32 = 0x00200000;
+const FILE_DISPOSITION_INFO_EX: i32 = 21;
+const FILE_DISPOSITION_FLAG_DELETE: u32 = 0x1;
+const FILE_DISPOSITION_FLAG_POSIX_SEMANTICS: u32 = 0x2;
+const FILE_DISPOSITION_FLAG_ON_CLOSE: u32 = 0x8;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn SetFileInformationByHandle(
+        handle: *mut c_void,
+        class: i32,
+        information: *const c_void,
+        size: u32,
+    ) -> i32;
+}
+
+/// A handle to a folder that makes the system remove the folder, provided it
+/// is empty, once the last handle to it is closed.
+#[derive(Debug)]
+pub struct Handle {
+    file: File,
+}
+
+impl Handle {
+    /// Open a folder with delete-on-close semantics.
+    pub fn open(path: &Path) -> Result<Handle> {
+        let file = OpenOptions::new()
+            .access_mode(DELETE)
+            .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_DELETE_ON_CLOSE)
+            .open(path)?;
+        Ok(Handle { file })
+    }
+
+    /// Set or clear the removal of the folder when the handle is closed.
+    pub fn set(&self, value: bool) -> Result<()> {
+        let mut flags = FILE_DISPOSITION_FLAG_ON_CLOSE;
+        if value {
+            flags |= FILE_DISPOSITION_FLAG_DELETE;
+        }
+        apply(&self.file, flags)
+    }
+}
+
+/// Remove a file without following symbolic links.
+///
+/// The file is unlinked with POSIX semantics, which removes the name right
+/// away even if other handles to the file are still open, provided they allow
+/// for deletion. Systems before Windows 10 version 1607 and file systems other
+/// than NTFS do not support it, and any failure to do so results in the usual
+/// removal, whose error is the one returned.
+pub fn remove(path: &Path) -> Result<()> {
+    let unlinked = OpenOptions::new()
+        .access_mode(DELETE)
+        .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT)
+        .open(path)
+        .and_then(|file| {
+            apply(
+                &file,
+                FILE_DISPOSITION_FLAG_DELETE | FILE_DISPOSITION_FLAG_POSIX_SEMANTICS,
+            )
+        });
+    match unlinked {
+        Ok(_) => Ok(()),
+        _ => std::fs::remove_file(path),
+    }
+}
+
+fn apply(file: &File, flags: u32) -> Result<()> {
+    let result = unsafe {
+        SetFileInformationByHandle(
+            file.as_raw_handle(),
+            FILE_DISPOSITION_INFO_EX,
+            (&flags as *const u32).cast(),
+            std::mem::size_of::<u32>() as u32,
+        )
+    };
+    if result == 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,370 @@
+use std::ffi::OsString;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+/// The variable preventing temporary folders from being removed.
+pub const KEEP: &str = "TEMPORARY_KEEP";
+
+/// The variable overriding the default temporary folder.
+pub const DIR: &str = "TEMPORARY_DIR";
+
+/// The variables pointing programs at the temporary folder.
+#[cfg(windows)]
+pub const TEMP_VARIABLES: &[&str] = &["TMP", "TEMP", DIR];
+#[cfg(not(windows))]
+pub const TEMP_VARIABLES: &[&str] = &["TMPDIR", DIR];
+
+/// Check if temporary folders should be kept, which is read once.
+pub fn keep() -> bool {
+    static KEEP: OnceLock<bool> = OnceLock::new();
+    *KEEP.get_or_init(|| parse_keep(std::env::var_os(self::KEEP)))
+}
+
+static PARENT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Set the folder to create temporary entries in by default.
+///
+/// It takes precedence over `TEMPORARY_DIR` and the temporary folder of the
+/// system and applies to the whole process, including entries created by
+/// other libraries, unless they specify a parent explicitly. The folder is
+/// created along with its parents if it does not exist, and a relative path
+/// is resolved against the current working directory right away.
+pub fn set_default_parent<T: AsRef<Path>>(path: T) -> Result<()> {
+    let path = path.as_ref();
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    std::fs::create_dir_all(&path).map_err(|error| {
+        Error::new(
+            error.kind(),
+            format!("failed to create {}: {}", path.display(), error),
+        )
+    })?;
+    if !std::fs::metadata(&path)?.is_dir() {
+        return Err(Error::new(
+            ErrorKind::NotADirectory,
+            format!("{} is not a folder", path.display()),
+        ));
+    }
+    *PARENT.write().unwrap_or_else(|error| error.into_inner()) = Some(path);
+    Ok(())
+}
+
+/// Go back to the default folder given by the environment.
+///
+/// It undoes [`set_default_parent`].
+pub fn reset_default_parent() {
+    *PARENT.write().unwrap_or_else(|error| error.into_inner()) = None;
+}
+
+/// Return the folder to create temporary entries in by default.
+///
+/// It is the folder given to [`set_default_parent`], `TEMPORARY_DIR`, or the
+/// temporary folder of the system, whichever is available first.
+pub fn default_parent() -> PathBuf {
+    if let Some(path) = &*PARENT.read().unwrap_or_else(|error| error.into_inner()) {
+        return path.clone();
+    }
+    static DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+    DIR.get_or_init(|| parse_dir(std::env::var_os(self::DIR)))
+        .clone()
+        .unwrap_or_else(system_temp_dir)
+}
+
+/// Return the runtime folder of the user if it is usable.
+///
+/// `XDG_RUNTIME_DIR` is used when it is set to an absolute path of a folder
+/// owned by the current user with mode 0700, as required by the XDG Base
+/// Directory Specification.
+#[cfg(unix)]
+pub fn runtime_dir() -> Option<PathBuf> {
+    let value = std::env::var_os("XDG_RUNTIME_DIR")?;
+    let result = check_runtime_dir(value);
+    #[cfg(feature = "log")]
+    if let Err(error) = &result {
+        log::warn!("ignoring XDG_RUNTIME_DIR: {}", error);
+    }
+    result.ok()
+}
+
+#[cfg(unix)]
+fn check_runtime_dir(value: OsString) -> Result<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let path = PathBuf::from(value);
+    if !path.is_absolute() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} is not an absolute path", path.display()),
+        ));
+    }
+    let metadata = std::fs::metadata(&path).map_err(|error| {
+        Error::new(
+            error.kind(),
+            format!("failed to read {}: {}", path.display(), error),
+        )
+    })?;
+    if !metadata.is_dir() {
+        return Err(Error::new(
+            ErrorKind::NotADirectory,
+            format!("{} is not a folder", path.display()),
+        ));
+    }
+    if Some(metadata.uid()) != crate::identity::user() {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is not owned by the current user", path.display()),
+        ));
+    }
+    if metadata.mode() & 0o777 != 0o700 {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "{} has mode {:o} instead of 700",
+                path.display(),
+                metadata.mode() & 0o777,
+            ),
+        ));
+    }
+    Ok(path)
+}
+
+/// Explain that the default temporary folder cannot be used.
+pub fn unusable(path: &Path, error: Error) -> Error {
+    Error::new(
+        error.kind(),
+        format!(
+            "the default temporary folder {} is not usable ({}); \
+             set {} to a writable folder",
+            path.display(),
+            error,
+            DIR,
+        ),
+    )
+}
+
+#[cfg(not(any(target_os = "wasi", windows)))]
+#[inline]
+fn system_temp_dir() -> PathBuf {
+    std::env::temp_dir()
+}
+
+// There is no temporary folder on WASI unless one is preopened, and an empty
+// path is returned if `TMPDIR` is not set.
+#[cfg(target_os = "wasi")]
+fn system_temp_dir() -> PathBuf {
+    std::env::var_os("TMPDIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+#[cfg(windows)]
+fn system_temp_dir() -> PathBuf {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(windows::temp_dir).clone()
+}
+
+fn parse_keep(value: Option<OsString>) -> bool {
+    match value.as_ref().and_then(|value| value.to_str()) {
+        Some(value) => value == "1" || value.eq_ignore_ascii_case("true"),
+        None => false,
+    }
+}
+
+fn parse_dir(value: Option<OsString>) -> Option<PathBuf> {
+    value.filter(|value| !value.is_empty()).map(PathBuf::from)
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::{c_void, OsString};
+    use std::os::windows::ffi::OsStringExt;
+    use std::path::{Path, PathBuf};
+
+    type GetTempPath = unsafe extern "system" fn(u32, *mut u16) -> u32;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetModuleHandleW(name: *const u16) -> *mut c_void;
+        fn GetProcAddress(module: *mut c_void, name: *const u8) -> *mut c_void;
+        fn GetTempPathW(length: u32, buffer: *mut u16) -> u32;
+        fn GetWindowsDirectoryW(buffer: *mut u16, length: u32) -> u32;
+    }
+
+    /// Find the first usable temporary folder.
+    ///
+    /// `GetTempPath2W`, which gives services running as `SYSTEM` a folder of
+    /// their own, is preferred when available. The variables and the folders
+    /// consulted by `GetTempPathW` are tried in turn, and the first candidate
+    /// is returned if none is usable so that errors name it.
+    pub fn temp_dir() -> PathBuf {
+        let mut candidates = Vec::new();
+        if let Some(function) = get_temp_path_2() {
+            candidates.extend(read(|length, buffer| unsafe { function(length, buffer) }));
+        }
+        candidates.extend(read(|length, buffer| unsafe {
+            GetTempPathW(length, buffer)
+        }));
+        for name in ["TMP", "TEMP"] {
+            candidates.extend(
+                std::env::var_os(name)
+                    .filter(|value| !value.is_empty())
+                    .map(PathBuf::from),
+            );
+        }
+        if let Some(profile) = std::env::var_os("USERPROFILE").filter(|value| !value.is_empty()) {
+            candidates.push(
+                Path::new(&profile)
+                    .join("AppData")
+                    .join("Local")
+                    .join("Temp"),
+            );
+        }
+        if let Some(windows) =
+            read(|length, buffer| unsafe { GetWindowsDirectoryW(buffer, length) })
+        {
+            candidates.push(windows.join("Temp"));
+        }
+        match candidates.iter().find(|path| usable(path)) {
+            Some(path) => path.clone(),
+            None => candidates
+                .into_iter()
+                .next()
+                .unwrap_or_else(std::env::temp_dir),
+        }
+    }
+
+    /// Check that a folder exists and that entries can be created in it.
+    fn usable(path: &Path) -> bool {
+        if !path.is_absolute() || !path.is_dir() {
+            return false;
+        }
+        let mut builder = crate::Builder::new();
+        builder.parent(path).prefix(".probe").retries(16);
+        match crate::name::create(&builder, |path| std::fs::create_dir(path)) {
+            Ok((path, _)) => {
+                let _ = std::fs::remove_dir(path);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn get_temp_path_2() -> Option<GetTempPath> {
+        let name = "kernel32.dll\0".encode_utf16().collect::<Vec<_>>();
+        let module = unsafe { GetModuleHandleW(name.as_ptr()) };
+        if module.is_null() {
+            return None;
+        }
+        let function = unsafe { GetProcAddress(module, c"GetTempPath2W".as_ptr().cast()) };
+        if function.is_null() {
+            return None;
+        }
+        Some(unsafe { std::mem::transmute::<*mut c_void, GetTempPath>(function) })
+    }
+
+    fn read<F: Fn(u32, *mut u16) -> u32>(f: F) -> Option<PathBuf> {
+        let mut buffer = vec![0u16; 261];
+        loop {
+            let length = f(buffer.len() as u32, buffer.as_mut_ptr()) as usize;
+            if length == 0 {
+                return None;
+            }
+            if length < buffer.len() {
+                buffer.truncate(length);
+                return Some(OsString::from_wide(&buffer).into());
+            }
+            buffer.resize(length, 0);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #[test]
+        fn temp_dir() {
+            let path = super::temp_dir();
+            assert!(super::usable(&path));
+            assert!(!super::usable(&path.join("nonexistent")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn set_default_parent() {
+        use std::env;
+
+        use crate::Folder;
+
+        const CHILD: &str = "TEMPORARY_TEST_DEFAULT_PARENT";
+
+        // The default parent is changed in a separate process as it is shared
+        // by the tests run in parallel.
+        if env::var_os(CHILD).is_none() {
+            let status = std::process::Command::new(env::current_exe().unwrap())
+                .args(["env::tests::set_default_parent", "--exact"])
+                .env(CHILD, "1")
+                .status()
+                .unwrap();
+            assert!(status.success());
+            return;
+        }
+        let root = Folder::new("foo").unwrap();
+        let path = root.join("bar").join("baz");
+        super::set_default_parent(&path).unwrap();
+        let folder = Folder::new("qux");
+        assert_eq!(super::default_parent(), path);
+        super::reset_default_parent();
+        assert_ne!(super::default_parent(), path);
+        assert_eq!(folder.unwrap().parent().unwrap(), path);
+
+        std::fs::write(root.join("qux"), "Hi there!").unwrap();
+        let error = super::set_default_parent(root.join("qux")).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+    }
+
+    #[cfg(all(unix, feature = "libc"))]
+    #[test]
+    fn check_runtime_dir() {
+        use std::fs::{self, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        use crate::Folder;
+
+        let root = Folder::new("foo").unwrap();
+        let path = root.join("bar");
+        fs::create_dir(&path).unwrap();
+        fs::set_permissions(&path, Permissions::from_mode(0o700)).unwrap();
+        assert_eq!(super::check_runtime_dir(path.clone().into()).unwrap(), path);
+        fs::set_permissions(&path, Permissions::from_mode(0o755)).unwrap();
+        assert!(super::check_runtime_dir(path.into()).is_err());
+        assert!(super::check_runtime_dir("bar".into()).is_err());
+        assert!(super::check_runtime_dir(root.join("baz").into()).is_err());
+    }
+
+    #[test]
+    fn parse_keep() {
+        for (value, expected) in [
+            (None, false),
+            (Some(""), false),
+            (Some("0"), false),
+            (Some("false"), false),
+            (Some("1"), true),
+            (Some("true"), true),
+            (Some("TRUE"), true),
+        ] {
+            assert_eq!(super::parse_keep(value.map(Into::into)), expected);
+        }
+    }
+
+    #[test]
+    fn parse_dir() {
+        assert_eq!(super::parse_dir(None), None);
+        assert_eq!(super::parse_dir(Some("".into())), None);
+        assert_eq!(super::parse_dir(Some("foo".into())), Some("foo".into()));
+    }
+}
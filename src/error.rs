@@ -0,0 +1,290 @@
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// A result with the error of the crate.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An error of an operation on temporary entries.
+///
+/// It carries the operation that failed and the path involved along with the
+/// underlying error. It converts into [`std::io::Error`] with the same kind
+/// and message.
+///
+/// It is returned by the functions creating or adopting temporary entries,
+/// such as [`Builder::folder`](crate::Builder::folder),
+/// [`Folder::new`](crate::Folder::new), [`Folder::adopt`](crate::Folder::adopt),
+/// [`File::new`](crate::File::new), [`reserve`](crate::reserve),
+/// [`TempTree::build`](crate::TempTree::build),
+/// [`LockFolder::acquire`](crate::LockFolder::acquire),
+/// [`FolderPool::new`](crate::FolderPool::new), and
+/// [`AtomicWriteFile::new`](crate::AtomicWriteFile::new). Failures to fill
+/// entries with content right after their creation, as in
+/// [`Folder::with_files`](crate::Folder::with_files), are reported in the same
+/// way. So are failures to remove, rename, and clear temporary entries, as in
+/// [`Folder::remove`](crate::Folder::remove),
+/// [`Folder::rename_into`](crate::Folder::rename_into),
+/// [`Folder::clear`](crate::Folder::clear), and
+/// [`File::remove`](crate::File::remove), where the error is also available
+/// via [`RemoveError`](crate::RemoveError) and
+/// [`RenameError`](crate::RenameError) along with the folder. Other operations
+/// on existing entries, such as writing files into a folder, return
+/// [`std::io::Error`] with the paths involved in the message.
+///
+/// Problems with the parent itself are checked for before creating anything
+/// and reported with [`ErrorKind::NotFound`] if the parent does not exist,
+/// [`ErrorKind::NotADirectory`] if it is not a folder, and
+/// [`ErrorKind::PermissionDenied`] if it cannot be accessed or written to.
+#[derive(Debug)]
+pub struct Error {
+    operation: Operation,
+    path: PathBuf,
+    error: std::io::Error,
+}
+
+/// An operation on temporary entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// Creating a folder in a parent.
+    CreateFolder,
+    /// Creating a file in a parent.
+    CreateFile,
+    /// Creating a custom entry in a parent.
+    CreateEntry,
+    /// Reserving a vacant path in a parent.
+    ReservePath,
+    /// Taking over the responsibility of removing an existing folder.
+    AdoptFolder,
+    /// Filling a newly created folder with content.
+    PopulateFolder,
+    /// Filling a newly created file with content.
+    PopulateFile,
+    /// Acquiring a folder shared between processes.
+    AcquireFolder,
+    /// Removing a temporary entry.
+    Remove,
+    /// Renaming a temporary entry.
+    Rename,
+    /// Removing the content of a temporary folder.
+    Clear,
+}
+
+impl Error {
+    #[inline]
+    pub(crate) fn new<T: Into<PathBuf>>(
+        operation: Operation,
+        path: T,
+        error: std::io::Error,
+    ) -> Error {
+        Error {
+            operation,
+            path: path.into(),
+            error,
+        }
+    }
+
+    /// Return the operation that failed.
+    #[inline]
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// Return the path involved, such as the parent of an entry to create, the
+    /// entry to remove, or the target of a rename.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return the kind of the underlying error.
+    #[inline]
+    pub fn kind(&self) -> ErrorKind {
+        self.error.kind()
+    }
+
+    /// Return the underlying error.
+    #[inline]
+    pub fn io(&self) -> &std::io::Error {
+        &self.error
+    }
+
+    /// Convert into an I/O error with the same kind and message.
+    #[inline]
+    pub fn into_io(self) -> std::io::Error {
+        self.into()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let operation = match self.operation {
+            Operation::CreateFolder => "create temporary folder in",
+            Operation::CreateFile => "create temporary file in",
+            Operation::CreateEntry => "create temporary entry in",
+            Operation::ReservePath => "reserve temporary path in",
+            Operation::AdoptFolder => "adopt folder",
+            Operation::PopulateFolder => "populate temporary folder",
+            Operation::PopulateFile => "populate temporary file",
+            Operation::AcquireFolder => "acquire shared folder",
+            Operation::Remove => "remove temporary entry",
+            Operation::Rename => "rename temporary entry into",
+            Operation::Clear => "clear temporary folder",
+        };
+        write!(
+            formatter,
+            "failed to {} {}: {}",
+            operation,
+            self.path.display(),
+            self.error,
+        )
+    }
+}
+
+impl std::error::Error for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<Error> for std::io::Error {
+    #[inline]
+    fn from(error: Error) -> std::io::Error {
+        std::io::Error::new(error.kind(), error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::Operation;
+    use crate::{Builder, File, Folder};
+
+    #[test]
+    fn create() {
+        let root = Folder::new("foo").unwrap();
+        let parent = root.join("bar");
+
+        let error = Folder::with_parent(&parent, "baz").unwrap_err();
+        assert_eq!(error.operation(), Operation::CreateFolder);
+        assert_eq!(error.path(), parent);
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        let message = format!(
+            "failed to create temporary folder in {}: ",
+            parent.display()
+        );
+        assert!(error.to_string().starts_with(&message));
+
+        let error = File::with_parent(&parent, "baz").unwrap_err();
+        assert_eq!(error.operation(), Operation::CreateFile);
+        let error = Builder::new()
+            .parent(&parent)
+            .make(|path| std::fs::create_dir(path))
+            .unwrap_err();
+        assert_eq!(error.operation(), Operation::CreateEntry);
+
+        let error = error.into_io();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(error
+            .to_string()
+            .starts_with("failed to create temporary entry in"));
+    }
+
+    #[test]
+    fn populate() {
+        let error = Folder::with_files("foo", &[("../bar.txt", b"")]).unwrap_err();
+        assert_eq!(error.operation(), Operation::PopulateFolder);
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(!error.path().exists());
+
+        let root = Folder::new("foo").unwrap();
+        let error = Folder::adopt(root.join("bar")).unwrap_err();
+        assert_eq!(error.operation(), Operation::AdoptFolder);
+        assert_eq!(error.path(), root.join("bar"));
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn existing() {
+        use std::fs;
+
+        let root = Folder::new("foo").unwrap();
+        let folder = Folder::with_parent(&root, "bar").unwrap();
+        let path = folder.to_path_buf();
+        // The original folder is kept aside for its identity not to be reused.
+        fs::rename(&path, root.join("baz")).unwrap();
+        fs::create_dir(&path).unwrap();
+        let error = folder.remove().unwrap_err();
+        assert_eq!(error.error.operation(), Operation::Remove);
+        assert_eq!(error.error.path(), path);
+        let folder = error.folder;
+
+        fs::remove_dir(&path).unwrap();
+        let error = folder.clear().unwrap_err();
+        assert_eq!(error.operation(), Operation::Clear);
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+
+        let target = root.join("qux").join("qux");
+        let error = folder.rename_into(&target).unwrap_err();
+        assert_eq!(error.error.operation(), Operation::Rename);
+        assert_eq!(error.error.path(), target);
+        assert_eq!(error.target(), target);
+        let mut folder = error.folder;
+        folder.keep();
+
+        let file = File::with_parent(&root, "bar").unwrap();
+        fs::remove_file(file.path()).unwrap();
+        let error = file.remove().unwrap_err();
+        assert_eq!(error.operation(), Operation::Remove);
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+
+        let error = crate::LockFolder::acquire(&root, "..").unwrap_err();
+        assert_eq!(error.operation(), Operation::AcquireFolder);
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn parent() {
+        let root = Folder::new("foo").unwrap();
+
+        let parent = root.join("bar");
+        let error = Folder::with_parent(&parent, "baz").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        let message = format!("parent path {} does not exist", parent.display());
+        assert!(error.to_string().ends_with(&message));
+
+        let parent = root.join("bar.txt");
+        std::fs::write(&parent, "Hi there!").unwrap();
+        let error = Folder::with_parent(&parent, "baz").unwrap_err();
+        assert_eq!(error.path(), parent);
+        assert_eq!(error.kind(), ErrorKind::NotADirectory);
+        let message = format!(
+            "parent path {} exists but is not a directory",
+            parent.display()
+        );
+        assert!(error.to_string().ends_with(&message));
+        let error = File::with_parent(&parent, "baz").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotADirectory);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parent_denied() {
+        use std::fs::{self, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        // Permissions do not apply to the superuser.
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+        let root = Folder::new("foo").unwrap();
+        fs::set_permissions(&root, Permissions::from_mode(0o500)).unwrap();
+        let error = Folder::with_parent(&root, "bar").unwrap_err();
+        fs::set_permissions(&root, Permissions::from_mode(0o700)).unwrap();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+        let message = format!("permission denied on parent path {}", root.display());
+        assert!(error.to_string().contains(&message));
+    }
+}
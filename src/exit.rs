@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, Once};
+
+use crate::identity::Expected;
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    next: 0,
+    paths: BTreeMap::new(),
+});
+static INSTALL: Once = Once::new();
+
+#[cfg(test)]
+pub static LOCK: Mutex<()> = Mutex::new(());
+
+struct Registry {
+    next: u64,
+    paths: BTreeMap<u64, (PathBuf, Expected)>,
+}
+
+/// A record of a folder to be removed when the process exits.
+///
+/// The record is removed from the registry when the object goes out of scope.
+#[derive(Debug)]
+pub struct Registration {
+    id: u64,
+}
+
+impl Registration {
+    /// Register a folder, which is removed only if it is still as expected.
+    pub fn new(path: &Path, expected: Expected) -> Registration {
+        INSTALL.call_once(install);
+        let id = update(|registry| {
+            let id = registry.next;
+            registry.next += 1;
+            registry.paths.insert(id, (path.to_path_buf(), expected));
+            id
+        });
+        Registration { id }
+    }
+}
+
+/// Register a folder for the rest of the life of the process.
+#[inline]
+pub fn register(path: &Path, expected: Expected) {
+    std::mem::forget(Registration::new(path, expected));
+}
+
+impl Drop for Registration {
+    #[inline]
+    fn drop(&mut self) {
+        update(|registry| registry.paths.remove(&self.id));
+    }
+}
+
+/// Remove all registered folders.
+#[cfg_attr(
+    not(any(all(any(unix, windows), feature = "libc"), panic = "abort")),
+    allow(dead_code)
+)]
+pub fn clear() {
+    let paths = update(|registry| std::mem::take(&mut registry.paths));
+    for (path, expected) in paths.values() {
+        if crate::env::keep() {
+            eprintln!("keeping the temporary folder {}", path.display());
+            continue;
+        }
+        let policy = crate::remove::Policy::default();
+        let result = match expected.verify(path) {
+            Ok(Ok(_)) => crate::remove::folder(path, &policy),
+            Ok(Err(error)) | Err(error) => Err(error),
+        };
+        match result {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+                crate::hook::report(path, &error);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn install() {
+    #[cfg(all(any(unix, windows), feature = "libc"))]
+    unsafe {
+        libc::atexit(exit);
+    }
+    // With unwinding, folders are removed as they go out of scope.
+    #[cfg(panic = "abort")]
+    {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |information| {
+            previous(information);
+            clear();
+        }));
+    }
+}
+
+#[cfg(all(any(unix, windows), feature = "libc"))]
+extern "C" fn exit() {
+    clear();
+}
+
+fn update<F: FnOnce(&mut Registry) -> T, T>(f: F) -> T {
+    // A signal handler waiting for the registry must not interrupt its holder.
+    #[cfg(feature = "signals")]
+    return crate::signal::masked(|| f(&mut lock()));
+    #[cfg(not(feature = "signals"))]
+    f(&mut lock())
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Builder;
+
+    fn registered(path: &std::path::Path) -> bool {
+        super::lock().paths.values().any(|(other, _)| other == path)
+    }
+
+    #[test]
+    fn register() {
+        let _lock = super::LOCK.lock().unwrap();
+        let mut folder = Builder::new()
+            .prefix("foo")
+            .register_for_exit(true)
+            .folder()
+            .unwrap();
+        let path = folder.to_path_buf();
+        assert!(registered(&path));
+        folder.keep();
+        assert!(!registered(&path));
+        folder.unkeep();
+        assert!(registered(&path));
+        drop(folder);
+        assert!(!registered(&path));
+        assert!(!path.exists());
+
+        let folder = Builder::new()
+            .prefix("bar")
+            .register_for_exit(true)
+            .folder()
+            .unwrap();
+        std::fs::write(folder.join("bar.txt"), "Hi there!").unwrap();
+        let path = folder.to_path_buf();
+        super::clear();
+        assert!(!path.exists());
+        folder.remove().unwrap();
+    }
+
+    #[test]
+    fn replaced() {
+        use std::fs;
+
+        let _lock = super::LOCK.lock().unwrap();
+        let root = crate::Folder::new("foo").unwrap();
+        let mut folder = Builder::new()
+            .parent(&root)
+            .prefix("bar")
+            .register_for_exit(true)
+            .folder()
+            .unwrap();
+        let path = folder.to_path_buf();
+        // The original folder is kept aside for its identity not to be reused.
+        fs::rename(&path, root.join("baz")).unwrap();
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("qux.txt"), "Hi there!").unwrap();
+        super::clear();
+        assert!(path.join("qux.txt").exists());
+        folder.keep();
+    }
+}
@@ -0,0 +1,84 @@
+use std::ffi::OsStr;
+use std::path::Path;
+
+use crate::{File, Folder};
+
+/// An extension for creating temporary entries inside folders.
+///
+/// ```rust
+/// use temporary::TempPathExt;
+///
+/// let folder = std::env::temp_dir().temp_folder("foo").unwrap();
+/// let file = folder.temp_file("bar").unwrap();
+/// assert_eq!(file.parent(), Some(folder.path()));
+/// ```
+pub trait TempPathExt {
+    /// Create a temporary folder inside the folder.
+    ///
+    /// It is the same as [`Folder::with_parent`].
+    fn temp_folder<T: AsRef<OsStr>>(&self, prefix: T) -> crate::Result<Folder>;
+
+    /// Create a temporary file inside the folder.
+    ///
+    /// It is the same as [`File::with_parent`].
+    fn temp_file<T: AsRef<OsStr>>(&self, prefix: T) -> crate::Result<File>;
+}
+
+impl TempPathExt for Path {
+    #[inline]
+    fn temp_folder<T: AsRef<OsStr>>(&self, prefix: T) -> crate::Result<Folder> {
+        Folder::with_parent(self, prefix)
+    }
+
+    #[inline]
+    fn temp_file<T: AsRef<OsStr>>(&self, prefix: T) -> crate::Result<File> {
+        File::with_parent(self, prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TempPathExt;
+    use crate::Folder;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn temp_folder() {
+        let root = Folder::new("foo").unwrap();
+        let folder = root.path().temp_folder("bar").unwrap();
+        assert_eq!(folder.parent(), Some(root.path()));
+        assert!(folder
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("bar."));
+        let path = PathBuf::from(root.path());
+        let other = path.temp_folder("baz").unwrap();
+        assert_eq!(other.parent(), Some(root.path()));
+        let path = other.to_path_buf();
+        drop(other);
+        assert!(!path.exists());
+
+        let _lock = crate::cwd::LOCK
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let folder = Path::new("").temp_folder("bar").unwrap();
+        assert_eq!(
+            folder.parent(),
+            Some(std::env::current_dir().unwrap().as_path())
+        );
+    }
+
+    #[test]
+    fn temp_file() {
+        let root = Folder::new("foo").unwrap();
+        let file = root.path().temp_file("bar").unwrap();
+        assert_eq!(file.parent(), Some(root.path()));
+        let path = file.to_path_buf();
+        assert!(path.is_file());
+        drop(file);
+        assert!(!path.exists());
+        assert!(root.join("baz").temp_file("bar").is_err());
+    }
+}
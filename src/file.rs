@@ -0,0 +1,981 @@
+use std::ffi::OsStr;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs};
+
+use crate::{Builder, Operation, TempPath};
+
+/// A temporary file.
+pub struct File {
+    path: PathBuf,
+    file: Option<fs::File>,
+    removed: bool,
+    anonymous: bool,
+    owned: bool,
+    inheritable: bool,
+    // The record is kept aside to keep the object small.
+    session: Option<Box<crate::session::Membership>>,
+}
+
+impl File {
+    /// Create a temporary file.
+    ///
+    /// The file will have a name starting from `prefix`, and it will be
+    /// automatically removed when the object goes out of scope.
+    #[inline]
+    pub fn new<T: AsRef<OsStr>>(prefix: T) -> crate::Result<File> {
+        Builder::new().prefix(prefix).file()
+    }
+
+    /// Create a temporary file in a specific folder.
+    ///
+    /// The file will have a name starting from `prefix`, and it will be
+    /// automatically removed when the object goes out of scope.
+    #[inline]
+    pub fn with_parent<T: AsRef<Path>, U: AsRef<OsStr>>(
+        parent: T,
+        prefix: U,
+    ) -> crate::Result<File> {
+        File::with_affixes(parent, prefix, "")
+    }
+
+    /// Create a temporary file with a specific suffix in a specific folder.
+    ///
+    /// The file will have a name starting from `prefix` and ending with
+    /// `suffix`, such as `.tar.gz`, and it will be automatically removed when
+    /// the object goes out of scope. The suffix may not contain path
+    /// separators.
+    #[inline]
+    pub fn with_affixes<T: AsRef<Path>, U: AsRef<OsStr>>(
+        parent: T,
+        prefix: U,
+        suffix: &str,
+    ) -> crate::Result<File> {
+        Builder::new()
+            .parent(parent)
+            .prefix(prefix)
+            .suffix(suffix)
+            .file()
+    }
+
+    pub(crate) fn create(builder: &Builder) -> Result<File> {
+        let _span = crate::trace::creating("file", &builder.prefix);
+        let mut options = fs::OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            let mode = builder.permissions.as_ref().map(|value| value.mode());
+            options.mode(mode.unwrap_or(0o600));
+        }
+        #[cfg(windows)]
+        if builder.temporary_hint {
+            use std::os::windows::fs::OpenOptionsExt;
+            options.attributes(platform::FILE_ATTRIBUTE_TEMPORARY);
+        }
+        let mut attempts = 0;
+        let (path, file) = crate::name::create(builder, |path| {
+            attempts += 1;
+            options.open(path)
+        })?;
+        crate::trace::created("file", &path, attempts);
+        let mut file = File {
+            path,
+            file: Some(file),
+            removed: false,
+            anonymous: false,
+            owned: false,
+            inheritable: builder.inheritable,
+            session: None,
+        };
+        // The standard library opens files with `O_CLOEXEC` on Unix and with
+        // non-inheritable handles on Windows.
+        if file.inheritable {
+            set_inheritable(file.handle())?;
+        }
+        if let Some(session) = &builder.session {
+            let mut expected = crate::identity::Expected::default();
+            if builder.verify_identity {
+                // Without an identity, the check is skipped.
+                let identity = crate::identity::Identity::of_file(file.handle());
+                expected.identity = identity.ok().flatten();
+            }
+            #[cfg(unix)]
+            if builder.verify_owner {
+                use std::os::unix::fs::MetadataExt;
+                expected.owner = file.handle().metadata().ok().map(|metadata| metadata.uid());
+            }
+            let kind = crate::session::Kind::File;
+            let policy = Default::default();
+            file.session = Some(Box::new(session.join(&file.path, kind, expected, policy)));
+        }
+        #[cfg(not(unix))]
+        if let Some(permissions) = &builder.permissions {
+            fs::set_permissions(&file.path, permissions.clone())?;
+        }
+        Ok(file)
+    }
+
+    /// Create a temporary file with specific content.
+    ///
+    /// The content is written and synchronized with the disk, and the object
+    /// is positioned at the beginning of the file.
+    #[inline]
+    pub fn with_contents<T: AsRef<OsStr>>(prefix: T, contents: &[u8]) -> crate::Result<File> {
+        File::with_reader(prefix, contents)
+    }
+
+    /// Create a temporary file with the content of a reader.
+    ///
+    /// The content is streamed without being buffered in memory as a whole;
+    /// otherwise, the behavior is the same as the one of
+    /// [`with_contents`](File::with_contents).
+    pub fn with_reader<T: AsRef<OsStr>, R: Read>(prefix: T, mut reader: R) -> crate::Result<File> {
+        let mut file = File::new(prefix)?;
+        let result = std::io::copy(&mut reader, &mut file)
+            .and_then(|_| file.handle().sync_all())
+            .and_then(|_| file.seek(SeekFrom::Start(0)));
+        if let Err(error) = result {
+            return Err(crate::Error::new(
+                Operation::PopulateFile,
+                &file.path,
+                error,
+            ));
+        }
+        Ok(file)
+    }
+
+    /// Create an anonymous temporary file in the default temporary folder.
+    ///
+    /// See [`anonymous`](File::anonymous) for details.
+    #[inline]
+    pub fn unnamed() -> crate::Result<File> {
+        File::anonymous(crate::env::default_parent())
+    }
+
+    /// Create an anonymous temporary file in a specific folder.
+    ///
+    /// The file has no visible name, and its content disappears once the
+    /// object goes out of scope, even if the process is killed. On Linux with
+    /// the `libc` feature, the file is created with `O_TMPFILE`; on Windows, it
+    /// is opened with `FILE_FLAG_DELETE_ON_CLOSE`. Otherwise, a named file is
+    /// created and immediately unlinked. Since the file does not have a name in
+    /// general, the path of the object is the path to `parent`.
+    pub fn anonymous<T: AsRef<Path>>(parent: T) -> crate::Result<File> {
+        let parent = parent.as_ref();
+        File::create_anonymous(parent)
+            .map_err(|error| crate::Error::new(Operation::CreateFile, parent, error))
+    }
+
+    fn create_anonymous(parent: &Path) -> Result<File> {
+        if let Some(file) = platform::anonymous(parent)? {
+            return Ok(file);
+        }
+        let mut options = fs::OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let (path, file) = crate::name::create(crate::Builder::new().parent(parent), |path| {
+            options.open(path)
+        })?;
+        crate::interrupt::retry(|| fs::remove_file(&path))?;
+        Ok(File {
+            path: parent.to_path_buf(),
+            file: Some(file),
+            removed: true,
+            anonymous: false,
+            owned: false,
+            inheritable: false,
+            session: None,
+        })
+    }
+
+    /// Take over the responsibility of removing an open file.
+    #[cfg(feature = "tempfile-interop")]
+    #[inline]
+    pub(crate) fn armed(path: PathBuf, file: fs::File) -> File {
+        File {
+            path,
+            file: Some(file),
+            removed: false,
+            anonymous: false,
+            owned: false,
+            inheritable: false,
+            session: None,
+        }
+    }
+
+    /// Dispose the object without removing the actual file and return its
+    /// parts.
+    #[cfg(feature = "tempfile-interop")]
+    pub(crate) fn into_parts(mut self) -> Result<(PathBuf, fs::File)> {
+        self.check_persistable()?;
+        self.removed = true;
+        let file = self.file.take().expect("the file should be open");
+        Ok((std::mem::take(&mut self.path), file))
+    }
+
+    pub(crate) fn create_named(path: PathBuf) -> Result<File> {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(File {
+            path,
+            file: Some(file),
+            removed: false,
+            anonymous: false,
+            owned: false,
+            inheritable: false,
+            session: None,
+        })
+    }
+
+    #[inline]
+    pub(crate) fn into_owned(mut self) -> File {
+        self.owned = true;
+        self
+    }
+
+    /// Check if the file is left for the enclosing temporary folder to remove.
+    ///
+    /// Such files are not removed when the object goes out of scope.
+    #[inline]
+    pub fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    /// Check if the file was created using the anonymous facilities of the
+    /// operating system as opposed to the create-then-unlink fallback.
+    #[inline]
+    pub fn is_anonymous(&self) -> bool {
+        self.anonymous
+    }
+
+    /// Return the path to the file.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.as_ref()
+    }
+
+    /// Return the path to the file and dispose the object without removing
+    /// the actual file.
+    #[inline]
+    pub fn into_path(mut self) -> PathBuf {
+        self.removed = true;
+        std::mem::take(&mut self.path)
+    }
+
+    /// Return the path to the file as an object responsible for removing it
+    /// when it goes out of scope.
+    #[inline]
+    pub fn into_temp_path(mut self) -> TempPath {
+        let removed = std::mem::replace(&mut self.removed, true);
+        let path = std::mem::take(&mut self.path);
+        if removed {
+            TempPath::disarmed(path)
+        } else {
+            TempPath::from_path(path)
+        }
+    }
+
+    /// Open the file anew.
+    ///
+    /// The returned handle has its own position, and it is inheritable by
+    /// child processes only if the file was created with
+    /// [`Builder::inheritable`]. On Unix, it is verified that the path still
+    /// refers to the same file. Anonymous files are reopened
+    /// via `/proc/self/fd` on Linux, and the create-then-unlink fallback files
+    /// cannot be reopened.
+    pub fn reopen(&self) -> Result<fs::File> {
+        let file = self.handle();
+        let path = platform::reopen_path(self, file)?;
+        let other = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(|error| reopen_error(error, &self.path))?;
+        check_same(file, &other).map_err(|error| reopen_error(error, &self.path))?;
+        if self.inheritable {
+            set_inheritable(&other).map_err(|error| reopen_error(error, &self.path))?;
+        }
+        Ok(other)
+    }
+
+    /// Synchronize the content and metadata with the disk.
+    #[inline]
+    pub fn sync_all(&self) -> Result<()> {
+        self.handle().sync_all()
+    }
+
+    /// Truncate or extend the file to a specific size.
+    #[inline]
+    pub fn set_len(&self, size: u64) -> Result<()> {
+        self.handle().set_len(size)
+    }
+
+    /// Map the file into memory for reading.
+    ///
+    /// The file cannot be removed or persisted while the mapping is alive.
+    #[cfg(feature = "mmap")]
+    #[inline]
+    pub fn map(&self) -> Result<crate::map::Map<'_>> {
+        crate::map::Map::new(self.handle())
+    }
+
+    /// Map the file into memory for reading and writing.
+    ///
+    /// The file cannot be removed or persisted while the mapping is alive, and
+    /// the changes are flushed when the mapping goes out of scope.
+    #[cfg(feature = "mmap")]
+    #[inline]
+    pub fn map_mut(&mut self) -> Result<crate::map::MapMut<'_>> {
+        crate::map::MapMut::new(self.handle())
+    }
+
+    /// Move the file to `target` and dispose the object without removing the
+    /// actual file.
+    ///
+    /// An existing file at `target` is replaced. In case of failure, the object
+    /// is handed back as a part of the error.
+    pub fn persist<T: AsRef<Path>>(
+        mut self,
+        target: T,
+    ) -> std::result::Result<fs::File, PersistError> {
+        let target = target.as_ref();
+        // On Windows, the standard library relies on `MoveFileEx` with
+        // `MOVEFILE_REPLACE_EXISTING`.
+        if let Err(error) = self
+            .check_persistable()
+            .and_then(|_| crate::interrupt::retry(|| fs::rename(&self.path, target)))
+        {
+            return Err(PersistError::new(error, self, target));
+        }
+        self.removed = true;
+        Ok(self.file.take().expect("the file should be open"))
+    }
+
+    /// Move the file to `target`, copying it if needed, and dispose the object
+    /// without removing the actual file.
+    ///
+    /// It behaves as [`persist`](File::persist) unless the rename fails due to
+    /// `target` being on another file system. In that case, the content is
+    /// copied to a temporary file in the folder of `target`, which then
+    /// replaces `target` atomically, so that an interrupted copy does not leave
+    /// a partial target behind. The original file is removed afterwards.
+    pub fn persist_or_copy<T: AsRef<Path>>(
+        mut self,
+        target: T,
+    ) -> std::result::Result<fs::File, PersistError> {
+        let target = target.as_ref();
+        match self
+            .check_persistable()
+            .and_then(|_| crate::interrupt::retry(|| fs::rename(&self.path, target)))
+        {
+            Ok(_) => {
+                self.removed = true;
+                return Ok(self.file.take().expect("the file should be open"));
+            }
+            Err(error) if error.kind() == ErrorKind::CrossesDevices => {}
+            Err(error) => return Err(PersistError::new(error, self, target)),
+        }
+        match self.copy(target) {
+            Ok(file) => Ok(file),
+            Err(error) => Err(PersistError::new(error, self, target)),
+        }
+    }
+
+    /// Move the file to `target` unless it already exists and dispose the
+    /// object without removing the actual file.
+    ///
+    /// An existing file at `target` results in an error of kind
+    /// `AlreadyExists`. In case of failure, the object is handed back as a
+    /// part of the error.
+    pub fn persist_noclobber<T: AsRef<Path>>(
+        mut self,
+        target: T,
+    ) -> std::result::Result<fs::File, PersistError> {
+        let target = target.as_ref();
+        if let Err(error) = self
+            .check_persistable()
+            .and_then(|_| fs::hard_link(&self.path, target))
+        {
+            return Err(PersistError::new(error, self, target));
+        }
+        self.removed = true;
+        // The file is already in place, and a stale name is not worth failing.
+        let _ = fs::remove_file(&self.path);
+        Ok(self.file.take().expect("the file should be open"))
+    }
+
+    /// Remove the file.
+    #[inline]
+    pub fn remove(mut self) -> crate::Result<()> {
+        self.cleanup()
+            .map_err(|error| crate::Error::new(Operation::Remove, &self.path, error))
+    }
+
+    fn cleanup(&mut self) -> Result<()> {
+        if self.removed || self.owned || self.session.as_ref().is_some_and(|session| session.kept())
+        {
+            return Ok(());
+        }
+        self.removed = true;
+
+        // The handle has to be closed first on some platforms.
+        self.file.take();
+        let timer = crate::trace::Timer::start();
+        crate::interrupt::retry(|| fs::remove_file(&self.path))?;
+        crate::trace::removed("file", &self.path, timer);
+        Ok(())
+    }
+
+    fn check_persistable(&self) -> Result<()> {
+        if self.removed {
+            return Err(Error::new(ErrorKind::InvalidInput, "the file is anonymous"));
+        }
+        Ok(())
+    }
+
+    fn copy(&mut self, target: &Path) -> Result<fs::File> {
+        let parent = match target.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut other = File::with_parent(parent, "")?;
+        self.seek(SeekFrom::Start(0))?;
+        std::io::copy(self, &mut other)?;
+        other.sync_all()?;
+        other.persist(target).map_err(|error| error.error)
+    }
+
+    #[inline]
+    fn handle(&self) -> &fs::File {
+        self.file.as_ref().expect("the file should be open")
+    }
+
+    #[inline]
+    fn handle_mut(&mut self) -> &mut fs::File {
+        self.file.as_mut().expect("the file should be open")
+    }
+}
+
+/// An error of persisting a temporary file.
+///
+/// The file is handed back and is removed when it goes out of scope unless it
+/// is persisted again or disposed otherwise.
+#[derive(Debug)]
+pub struct PersistError {
+    /// The underlying error.
+    pub error: Error,
+    /// The file that failed to be persisted.
+    pub file: File,
+    target: PathBuf,
+}
+
+impl PersistError {
+    #[inline]
+    fn new(error: Error, file: File, target: &Path) -> Self {
+        PersistError {
+            error,
+            file,
+            target: target.to_path_buf(),
+        }
+    }
+
+    /// Return the path the file failed to be persisted as.
+    #[inline]
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "failed to persist {} as {}: {}",
+            self.file.path.display(),
+            self.target.display(),
+            self.error,
+        )
+    }
+}
+
+impl std::error::Error for PersistError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<PersistError> for Error {
+    #[inline]
+    fn from(error: PersistError) -> Error {
+        Error::new(error.error.kind(), error.to_string())
+    }
+}
+
+fn named_path(file: &File) -> Result<PathBuf> {
+    if file.removed {
+        return Err(reopen_error(
+            Error::new(ErrorKind::NotFound, "the file is unlinked"),
+            &file.path,
+        ));
+    }
+    Ok(file.path.clone())
+}
+
+fn reopen_error(error: Error, path: &Path) -> Error {
+    Error::new(
+        error.kind(),
+        format!("failed to reopen {}: {}", path.display(), error),
+    )
+}
+
+#[cfg(unix)]
+fn check_same(file: &fs::File, other: &fs::File) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let (one, other) = (file.metadata()?, other.metadata()?);
+    if one.dev() != other.dev() || one.ino() != other.ino() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            "the path refers to a different file",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+#[inline]
+fn check_same(_: &fs::File, _: &fs::File) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(all(unix, feature = "libc"))]
+fn set_inheritable(file: &fs::File) -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let descriptor = file.as_raw_fd();
+    let flags = unsafe { libc::fcntl(descriptor, libc::F_GETFD) };
+    if flags == -1
+        || unsafe { libc::fcntl(descriptor, libc::F_SETFD, flags & !libc::FD_CLOEXEC) } == -1
+    {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn set_inheritable(file: &fs::File) -> Result<()> {
+    use std::ffi::c_void;
+    use std::os::windows::io::AsRawHandle;
+
+    const HANDLE_FLAG_INHERIT: u32 = 0x1;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetHandleInformation(handle: *mut c_void, mask: u32, flags: u32) -> i32;
+    }
+
+    let handle = file.as_raw_handle();
+    if unsafe { SetHandleInformation(handle, HANDLE_FLAG_INHERIT, HANDLE_FLAG_INHERIT) } == 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(feature = "libc")))]
+#[inline]
+fn set_inheritable(_: &fs::File) -> Result<()> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "inheritable files require the `libc` feature",
+    ))
+}
+
+#[cfg(not(any(unix, windows)))]
+#[inline]
+fn set_inheritable(_: &fs::File) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", feature = "libc"))]
+mod platform {
+    use std::fs;
+    use std::io::Result;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::path::{Path, PathBuf};
+
+    pub fn anonymous(parent: &Path) -> Result<Option<super::File>> {
+        let result = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .mode(0o600)
+            .custom_flags(libc::O_TMPFILE)
+            .open(parent);
+        match result {
+            Ok(file) => Ok(Some(super::File {
+                path: parent.to_path_buf(),
+                file: Some(file),
+                removed: true,
+                anonymous: true,
+                owned: false,
+                inheritable: false,
+                session: None,
+            })),
+            // The kernel or the file system does not support the flag.
+            Err(error)
+                if matches!(
+                    error.raw_os_error(),
+                    Some(libc::EOPNOTSUPP | libc::EISDIR | libc::EINVAL)
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn reopen_path(file: &super::File, handle: &fs::File) -> Result<PathBuf> {
+        use std::os::unix::io::AsRawFd;
+
+        if file.anonymous {
+            return Ok(PathBuf::from(format!(
+                "/proc/self/fd/{}",
+                handle.as_raw_fd()
+            )));
+        }
+        super::named_path(file)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::fs;
+    use std::io::Result;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::path::{Path, PathBuf};
+
+    pub const FILE_ATTRIBUTE_TEMPORARY: u32 = 0x100;
+    const FILE_FLAG_DELETE_ON_CLOSE: u32 = 0x04000000;
+
+    pub fn anonymous(parent: &Path) -> Result<Option<super::File>> {
+        let (path, file) = crate::name::create(crate::Builder::new().parent(parent), |path| {
+            fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .attributes(FILE_ATTRIBUTE_TEMPORARY)
+                .custom_flags(FILE_FLAG_DELETE_ON_CLOSE)
+                .open(path)
+        })?;
+        Ok(Some(super::File {
+            path,
+            file: Some(file),
+            removed: true,
+            anonymous: true,
+            owned: false,
+            inheritable: false,
+            session: None,
+        }))
+    }
+
+    #[inline]
+    pub fn reopen_path(file: &super::File, _: &fs::File) -> Result<PathBuf> {
+        // Files opened with `FILE_FLAG_DELETE_ON_CLOSE` keep their names.
+        if file.anonymous {
+            return Ok(file.path.clone());
+        }
+        super::named_path(file)
+    }
+}
+
+#[cfg(not(any(all(target_os = "linux", feature = "libc"), windows)))]
+mod platform {
+    use std::fs;
+    use std::io::Result;
+    use std::path::{Path, PathBuf};
+
+    #[inline]
+    pub fn anonymous(_: &Path) -> Result<Option<super::File>> {
+        Ok(None)
+    }
+
+    #[inline]
+    pub fn reopen_path(file: &super::File, _: &fs::File) -> Result<PathBuf> {
+        super::named_path(file)
+    }
+}
+
+impl AsRef<Path> for File {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Debug for File {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.path.fmt(formatter)
+    }
+}
+
+impl Deref for File {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for File {
+    #[inline]
+    fn drop(&mut self) {
+        if let Err(error) = self.cleanup() {
+            crate::hook::report(&self.path, &error);
+        }
+    }
+}
+
+impl Read for File {
+    #[inline]
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        self.handle_mut().read(buffer)
+    }
+}
+
+impl Seek for File {
+    #[inline]
+    fn seek(&mut self, position: SeekFrom) -> Result<u64> {
+        self.handle_mut().seek(position)
+    }
+}
+
+impl Write for File {
+    #[inline]
+    fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+        self.handle_mut().write(buffer)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        self.handle_mut().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::File;
+    use std::fs;
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn with_contents() {
+        let mut file = File::with_contents("foo", b"Hi there!").unwrap();
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer).unwrap();
+        assert_eq!(buffer, "Hi there!");
+
+        let mut file = File::with_reader("foo", std::io::repeat(42).take(1 << 20)).unwrap();
+        assert_eq!(file.seek(SeekFrom::End(0)).unwrap(), 1 << 20);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn map() {
+        let mut file = File::new("foo").unwrap();
+        file.set_len(4).unwrap();
+        file.map_mut().unwrap().copy_from_slice(b"Hi!\n");
+        assert_eq!(&*file.map().unwrap(), b"Hi!\n");
+        assert_eq!(fs::read(file.path()).unwrap(), b"Hi!\n");
+    }
+
+    #[test]
+    fn new() {
+        let path = {
+            let file = File::new("foo").unwrap();
+            assert!(fs::metadata(file.path()).unwrap().is_file());
+            file.path().to_path_buf()
+        };
+        assert!(fs::metadata(path).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let previous = unsafe { libc::umask(0) };
+        let file = File::new("foo");
+        unsafe { libc::umask(previous) };
+        let mode = fs::metadata(file.unwrap().path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o7777, 0o600);
+    }
+
+    #[test]
+    fn anonymous() {
+        let mut file = File::unnamed().unwrap();
+        assert_eq!(
+            file.is_anonymous(),
+            cfg!(any(all(target_os = "linux", feature = "libc"), windows))
+        );
+        file.write_all(b"Hi there!").unwrap();
+        file.seek(SeekFrom::Start(3)).unwrap();
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer).unwrap();
+        assert_eq!(buffer, "there!");
+    }
+
+    #[test]
+    fn temporary_hint() {
+        let mut file = crate::Builder::new()
+            .prefix("foo")
+            .temporary_hint(true)
+            .file()
+            .unwrap();
+        file.write_all(b"Hi there!").unwrap();
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::MetadataExt;
+
+            let attributes = fs::metadata(file.path()).unwrap().file_attributes();
+            assert_ne!(attributes & super::platform::FILE_ATTRIBUTE_TEMPORARY, 0);
+        }
+        let folder = crate::Folder::new("foo").unwrap();
+        file.persist(folder.join("foo.txt")).unwrap();
+        assert_eq!(fs::read(folder.join("foo.txt")).unwrap(), b"Hi there!");
+    }
+
+    #[test]
+    fn with_affixes() {
+        let file = File::with_affixes(std::env::temp_dir(), "foo", ".tar.gz").unwrap();
+        let name = file.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("foo.") && name.ends_with(".tar.gz"));
+    }
+
+    #[test]
+    fn persist() {
+        let folder = crate::Folder::new("foo").unwrap();
+        let target = folder.join("foo.txt");
+        fs::write(&target, "Hello!").unwrap();
+
+        let mut file = File::new("foo").unwrap();
+        file.write_all(b"Hi there!").unwrap();
+        let path = file.path().to_path_buf();
+        file.persist(&target).unwrap();
+        assert!(fs::metadata(path).is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "Hi there!");
+
+        let file = File::with_contents("foo", b"Hello!").unwrap();
+        file.persist_or_copy(&target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "Hello!");
+    }
+
+    #[test]
+    fn persist_noclobber() {
+        use std::io::ErrorKind;
+
+        let folder = crate::Folder::new("foo").unwrap();
+        let target = folder.join("foo.txt");
+        fs::write(&target, "Hello!").unwrap();
+
+        let file = File::new("foo").unwrap();
+        let path = file.path().to_path_buf();
+        let error = file.persist_noclobber(&target).unwrap_err();
+        assert_eq!(error.error.kind(), ErrorKind::AlreadyExists);
+        assert!(error.to_string().contains(path.to_str().unwrap()));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "Hello!");
+        let other = folder.join("bar.txt");
+        error.file.persist_noclobber(&other).unwrap();
+        assert!(fs::metadata(path).is_err());
+        assert!(fs::metadata(other).is_ok());
+
+        let file = File::new("foo").unwrap();
+        fs::remove_file(&target).unwrap();
+        file.persist_noclobber(&target).unwrap();
+        assert!(fs::metadata(&target).is_ok());
+    }
+
+    #[test]
+    fn reopen() {
+        for mut file in [File::new("foo").unwrap(), File::unnamed().unwrap()] {
+            file.write_all(b"Hi there!").unwrap();
+            if file.path().is_dir() && !file.is_anonymous() {
+                assert!(file.reopen().is_err());
+                continue;
+            }
+            let mut other = file.reopen().unwrap();
+            let mut buffer = String::new();
+            other.read_to_string(&mut buffer).unwrap();
+            assert_eq!(buffer, "Hi there!");
+        }
+
+        let file = File::new("foo").unwrap();
+        let path = file.path().to_path_buf();
+        fs::remove_file(&path).unwrap();
+        fs::write(&path, "Hello!").unwrap();
+        assert!(file.reopen().is_err());
+    }
+
+    #[cfg(all(unix, feature = "libc"))]
+    #[test]
+    fn inheritable() {
+        use std::os::unix::io::AsRawFd;
+
+        let inherited = |file: &fs::File| {
+            let flags = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETFD) };
+            assert_ne!(flags, -1);
+            flags & libc::FD_CLOEXEC == 0
+        };
+        let file = File::new("foo").unwrap();
+        assert!(!inherited(file.handle()));
+        assert!(!inherited(&file.reopen().unwrap()));
+        let file = crate::Builder::new()
+            .prefix("foo")
+            .inheritable(true)
+            .file()
+            .unwrap();
+        assert!(inherited(file.handle()));
+        assert!(inherited(&file.reopen().unwrap()));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn inheritable() {
+        use std::process::{Command, Stdio};
+
+        // A child holding the handle would prevent the removal.
+        let file = File::new("foo").unwrap();
+        let mut child = Command::new("cmd")
+            .args(["/C", "ping -n 3 127.0.0.1"])
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        let path = file.path().to_path_buf();
+        file.remove().unwrap();
+        assert!(fs::metadata(path).is_err());
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn read_write() {
+        let mut file = File::new("bar").unwrap();
+        file.write_all(b"Hi there!").unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer).unwrap();
+        assert_eq!(buffer, "Hi there!");
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), "Hi there!");
+    }
+}
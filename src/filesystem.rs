@@ -0,0 +1,431 @@
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::panic::RefUnwindSafe;
+use std::path::{Path, PathBuf};
+
+/// A file system hosting temporary folders.
+///
+/// It covers the operations that temporary folders perform on their parents
+/// and on themselves, and
+/// it can be given to [`Builder::filesystem`](crate::Builder::filesystem) in
+/// order to create folders through a virtual file system or to inject failures
+/// in tests. Without it, the standard library is used directly, which is what
+/// [`StdFilesystem`] does as well.
+///
+/// ```rust
+/// use std::io::{Error, ErrorKind, Result};
+/// use std::path::{Path, PathBuf};
+/// use std::sync::Arc;
+///
+/// use temporary::{Builder, Filesystem, StdFilesystem};
+///
+/// #[derive(Debug)]
+/// struct Full;
+///
+/// impl Filesystem for Full {
+///     fn create_dir(&self, _: &Path) -> Result<()> {
+///         Err(Error::from(ErrorKind::StorageFull))
+///     }
+///
+///     fn remove_dir_all(&self, path: &Path) -> Result<()> {
+///         StdFilesystem.remove_dir_all(path)
+///     }
+///
+///     fn remove_file(&self, path: &Path) -> Result<()> {
+///         StdFilesystem.remove_file(path)
+///     }
+///
+///     fn rename(&self, source: &Path, target: &Path) -> Result<()> {
+///         StdFilesystem.rename(source, target)
+///     }
+///
+///     fn metadata(&self, path: &Path) -> Result<std::fs::Metadata> {
+///         StdFilesystem.metadata(path)
+///     }
+///
+///     fn symlink_metadata(&self, path: &Path) -> Result<std::fs::Metadata> {
+///         StdFilesystem.symlink_metadata(path)
+///     }
+///
+///     fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+///         StdFilesystem.read_dir(path)
+///     }
+/// }
+///
+/// let error = Builder::new().filesystem(Arc::new(Full)).folder().unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::StorageFull);
+/// ```
+///
+/// Paths are generated by the crate as usual. The file system is used for
+/// inspecting, creating, and checking the parent as well as for creating,
+/// renaming, and removing folders. It also backs
+/// [`Folder::metadata`](crate::Folder::metadata),
+/// [`is_empty`](crate::Folder::is_empty), [`size`](crate::Folder::size),
+/// [`count`](crate::Folder::count), [`clear`](crate::Folder::clear),
+/// [`move_file_in`](crate::Folder::move_file_in), and
+/// [`move_dir_in`](crate::Folder::move_dir_in) as well as `disk_usage` on
+/// Unix, and entries are not copied when moved across file systems.
+/// [`canonicalize`](crate::Builder::canonicalize) and
+/// [`require_free_space`](crate::Builder::require_free_space) have no effect,
+/// and [`Folder::entries`](crate::Folder::entries) fails with `Unsupported`,
+/// as they need the real file system. Other operations on the content of
+/// folders, such as reading and writing files, as well as the checks of the
+/// identity and the owner of folders before removal rely on the standard
+/// library, and the checks are skipped for folders on other file systems.
+pub trait Filesystem: fmt::Debug + RefUnwindSafe + Send + Sync {
+    /// Create a folder, failing with `AlreadyExists` if the path is taken.
+    ///
+    /// Names are tried again on `AlreadyExists` as usual, and any other error
+    /// fails the creation.
+    fn create_dir(&self, path: &Path) -> Result<()>;
+
+    /// Remove a folder with its content without following symbolic links.
+    fn remove_dir_all(&self, path: &Path) -> Result<()>;
+
+    /// Remove a file or a symbolic link.
+    fn remove_file(&self, path: &Path) -> Result<()>;
+
+    /// Move an entry without replacing an existing one.
+    fn rename(&self, source: &Path, target: &Path) -> Result<()>;
+
+    /// Read the metadata of an entry.
+    fn metadata(&self, path: &Path) -> Result<fs::Metadata>;
+
+    /// Read the metadata of an entry without following symbolic links.
+    fn symlink_metadata(&self, path: &Path) -> Result<fs::Metadata>;
+
+    /// List the paths to the entries of a folder.
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>>;
+}
+
+/// The file system of the standard library.
+///
+/// Folders are private on Unix and are removed the same way as the ones
+/// created without a file system given.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdFilesystem;
+
+impl Filesystem for StdFilesystem {
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        #[allow(unused_mut)]
+        let mut options = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            options.mode(0o700);
+        }
+        options.create(path)
+    }
+
+    #[inline]
+    fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        crate::remove::folder(path, &crate::remove::Policy::default())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        let file_type = fs::symlink_metadata(path)?.file_type();
+        if file_type.is_dir() {
+            return Err(Error::from(ErrorKind::IsADirectory));
+        }
+        crate::remove::entry(path, file_type)
+    }
+
+    #[inline]
+    fn rename(&self, source: &Path, target: &Path) -> Result<()> {
+        crate::folder::rename_noreplace(source, target)
+    }
+
+    #[inline]
+    fn metadata(&self, path: &Path) -> Result<fs::Metadata> {
+        fs::metadata(path)
+    }
+
+    #[inline]
+    fn symlink_metadata(&self, path: &Path) -> Result<fs::Metadata> {
+        fs::symlink_metadata(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::io::{Error, ErrorKind, Result};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::{Filesystem, StdFilesystem};
+    use crate::{Builder, Folder};
+
+    // A file system failing the first few calls of each operation.
+    #[derive(Debug)]
+    struct Flaky {
+        kind: ErrorKind,
+        creations: AtomicUsize,
+        removals: AtomicUsize,
+    }
+
+    impl Flaky {
+        fn new(kind: ErrorKind, creations: usize, removals: usize) -> Arc<Flaky> {
+            Arc::new(Flaky {
+                kind,
+                creations: AtomicUsize::new(creations),
+                removals: AtomicUsize::new(removals),
+            })
+        }
+
+        fn fail(&self, counter: &AtomicUsize) -> Result<()> {
+            match counter.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                count.checked_sub(1)
+            }) {
+                Ok(_) => Err(Error::from(self.kind)),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    impl Filesystem for Flaky {
+        fn create_dir(&self, path: &Path) -> Result<()> {
+            self.fail(&self.creations)?;
+            StdFilesystem.create_dir(path)
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> Result<()> {
+            self.fail(&self.removals)?;
+            StdFilesystem.remove_dir_all(path)
+        }
+
+        fn remove_file(&self, path: &Path) -> Result<()> {
+            StdFilesystem.remove_file(path)
+        }
+
+        fn rename(&self, source: &Path, target: &Path) -> Result<()> {
+            StdFilesystem.rename(source, target)
+        }
+
+        fn metadata(&self, path: &Path) -> Result<fs::Metadata> {
+            StdFilesystem.metadata(path)
+        }
+
+        fn symlink_metadata(&self, path: &Path) -> Result<fs::Metadata> {
+            StdFilesystem.symlink_metadata(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+            StdFilesystem.read_dir(path)
+        }
+    }
+
+    // A file system keeping entries in memory and describing each of them by
+    // the metadata of a real folder or file.
+    #[derive(Debug)]
+    struct Virtual {
+        backing: Folder,
+        entries: Mutex<BTreeMap<PathBuf, bool>>,
+    }
+
+    impl Virtual {
+        fn new(root: &Path) -> Arc<Virtual> {
+            let backing = Folder::with_parent(root, "bar").unwrap();
+            backing.write("file", "Hi there!").unwrap();
+            let entries = BTreeMap::from([(root.to_path_buf(), true)]);
+            Arc::new(Virtual {
+                backing,
+                entries: Mutex::new(entries),
+            })
+        }
+
+        fn insert(&self, path: &Path, folder: bool) {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(path.to_path_buf(), folder);
+        }
+
+        fn contains(&self, path: &Path) -> bool {
+            self.entries.lock().unwrap().contains_key(path)
+        }
+    }
+
+    impl Filesystem for Virtual {
+        fn create_dir(&self, path: &Path) -> Result<()> {
+            let mut entries = self.entries.lock().unwrap();
+            if entries.contains_key(path) {
+                return Err(Error::from(ErrorKind::AlreadyExists));
+            }
+            entries.insert(path.to_path_buf(), true);
+            Ok(())
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> Result<()> {
+            let mut entries = self.entries.lock().unwrap();
+            entries.retain(|entry, _| !entry.starts_with(path));
+            Ok(())
+        }
+
+        fn remove_file(&self, path: &Path) -> Result<()> {
+            match self.entries.lock().unwrap().remove(path) {
+                Some(false) => Ok(()),
+                _ => Err(Error::from(ErrorKind::NotFound)),
+            }
+        }
+
+        fn rename(&self, source: &Path, target: &Path) -> Result<()> {
+            let mut entries = self.entries.lock().unwrap();
+            if entries.contains_key(target) {
+                return Err(Error::from(ErrorKind::AlreadyExists));
+            }
+            let moved = entries
+                .keys()
+                .filter(|entry| entry.starts_with(source))
+                .cloned()
+                .collect::<Vec<_>>();
+            if moved.is_empty() {
+                return Err(Error::from(ErrorKind::NotFound));
+            }
+            for entry in moved {
+                let folder = entries.remove(&entry).unwrap();
+                let path = target.join(entry.strip_prefix(source).unwrap());
+                entries.insert(path, folder);
+            }
+            Ok(())
+        }
+
+        fn metadata(&self, path: &Path) -> Result<fs::Metadata> {
+            match self.entries.lock().unwrap().get(path) {
+                Some(true) => fs::metadata(&self.backing),
+                Some(false) => fs::metadata(self.backing.join("file")),
+                _ => Err(Error::from(ErrorKind::NotFound)),
+            }
+        }
+
+        fn symlink_metadata(&self, path: &Path) -> Result<fs::Metadata> {
+            self.metadata(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+            self.metadata(path)?;
+            let entries = self.entries.lock().unwrap();
+            Ok(entries
+                .keys()
+                .filter(|entry| entry.parent() == Some(path))
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[test]
+    fn create() {
+        let parent = Folder::new("foo").unwrap();
+        let filesystem = Flaky::new(ErrorKind::AlreadyExists, 3, 0);
+        let folder = Builder::new()
+            .parent(&parent)
+            .filesystem(filesystem)
+            .folder()
+            .unwrap();
+        assert_eq!(folder.creation_attempts(), 4);
+        assert!(folder.metadata().unwrap().is_dir());
+
+        let filesystem = Flaky::new(ErrorKind::StorageFull, 1, 0);
+        let error = Builder::new()
+            .parent(&parent)
+            .filesystem(filesystem)
+            .folder()
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::StorageFull);
+        assert_eq!(parent.entries().unwrap().count(), 1);
+    }
+
+    #[test]
+    fn remove() {
+        let filesystem = Flaky::new(ErrorKind::PermissionDenied, 0, 1);
+        let folder = Builder::new()
+            .prefix("foo")
+            .filesystem(filesystem)
+            .folder()
+            .unwrap();
+        folder.write("bar.txt", "Hi there!").unwrap();
+        let path = folder.to_path_buf();
+        let error = folder.remove().unwrap_err();
+        assert_eq!(error.error.kind(), ErrorKind::PermissionDenied);
+        assert!(path.exists());
+        error.folder.remove().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn parent() {
+        let root = Folder::new("foo").unwrap();
+        let filesystem = Virtual::new(&root);
+        let parent = root.join("baz").join("qux");
+        let folder = Builder::new()
+            .parent(&parent)
+            .create_parent(true)
+            .canonicalize(true)
+            .require_free_space(1)
+            .require_secure_parent(true)
+            .filesystem(filesystem.clone())
+            .folder()
+            .unwrap();
+        assert!(!root.join("baz").exists());
+        assert!(!folder.exists());
+        assert_eq!(folder.parent(), Some(&*parent));
+        assert!(filesystem.contains(&parent));
+        assert!(folder.is_empty().unwrap());
+        let error = folder.entries().err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!filesystem.contains(&path));
+        assert!(filesystem.contains(&parent));
+        assert_eq!(root.entries().unwrap().count(), 1);
+    }
+
+    #[test]
+    fn content() {
+        let root = Folder::new("foo").unwrap();
+        let filesystem = Virtual::new(&root);
+        let folder = Builder::new()
+            .parent(&root)
+            .filesystem(filesystem.clone())
+            .folder()
+            .unwrap();
+        filesystem.insert(&folder.join("foo.txt"), false);
+        filesystem.insert(&folder.join("bar"), true);
+        filesystem.insert(&folder.join("bar").join("baz.txt"), false);
+        assert_eq!(folder.size().unwrap(), 18);
+        assert_eq!(folder.count().unwrap(), (2, 1));
+        #[cfg(unix)]
+        assert!(folder.disk_usage().is_ok());
+
+        filesystem.insert(&root.join("qux.txt"), false);
+        let path = folder
+            .move_file_in(root.join("qux.txt"), "qux.txt")
+            .unwrap();
+        assert!(filesystem.contains(&path));
+        assert!(!filesystem.contains(&root.join("qux.txt")));
+        filesystem.insert(&root.join("qux"), true);
+        let path = folder.move_dir_in(root.join("qux"), "qux").unwrap();
+        assert!(filesystem.contains(&path));
+
+        folder.clear().unwrap();
+        assert!(folder.is_empty().unwrap());
+        assert!(filesystem.contains(&folder));
+
+        let target = root.join("target");
+        filesystem.insert(&target, true);
+        let source = folder.to_path_buf();
+        assert_eq!(folder.rename_into_overwrite(&target).unwrap(), target);
+        assert!(filesystem.contains(&target));
+        assert!(!filesystem.contains(&source));
+        assert_eq!(root.entries().unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,3636 @@
+use std::ffi::{OsStr, OsString};
+use std::io::{Error, ErrorKind, Result, Write};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{env, fmt, fs};
+
+use crate::background::RemovalOutcome;
+use crate::identity::{Expected, Identity};
+use crate::remove::RemovalReport;
+use crate::{
+    AliasGuard, BackgroundRemoval, Builder, CwdGuard, File, Filesystem, Manifest, Operation,
+    QuotaExceeded, QuotaStatus, SharedFolder, TempDirGuard, TempPath,
+};
+
+/// A temporary folder.
+///
+/// The object is `Send` and `Sync`, and the folder is removed at most once even
+/// if the object is shared between threads; see [`shared`](Folder::shared).
+pub struct Folder {
+    path: PathBuf,
+    removed: AtomicBool,
+    kept: AtomicBool,
+    options: Box<Options>,
+}
+
+// Settings needed rarely are kept aside to keep the object small.
+#[derive(Debug, Default)]
+pub(crate) struct Options {
+    prefix: OsString,
+    keep_on_panic: bool,
+    removal: crate::remove::Policy,
+    expected: Expected,
+    exit: bool,
+    sync: bool,
+    attempts: u32,
+    registration: Mutex<Option<crate::exit::Registration>>,
+    session: Option<crate::session::Membership>,
+    #[cfg(feature = "async")]
+    pub(crate) panic_on_blocking_drop: bool,
+    preserve: Mutex<Option<PathBuf>>,
+    frozen: Mutex<Option<Vec<(PathBuf, fs::Permissions)>>>,
+    quota: Option<u64>,
+    plain_command: bool,
+    leak: Option<crate::leak::Tracker>,
+    filesystem: Option<Arc<dyn Filesystem>>,
+    #[cfg(feature = "archive")]
+    archive: Option<PathBuf>,
+    #[cfg(windows)]
+    handle: Mutex<Option<crate::disposition::Handle>>,
+}
+
+impl Folder {
+    /// Create a temporary folder.
+    ///
+    /// The folder will have a name starting from `prefix`, and it will be
+    /// automatically removed when the object goes out of scope.
+    #[inline]
+    pub fn new<T: AsRef<OsStr>>(prefix: T) -> crate::Result<Folder> {
+        Builder::new().prefix(prefix).folder()
+    }
+
+    /// Create a temporary folder in the runtime folder of the user.
+    ///
+    /// On Unix, `XDG_RUNTIME_DIR` is used if it points at a folder owned by
+    /// the current user with mode 0700, which is suitable for sockets and
+    /// other ephemeral data that should not outlive the session. Otherwise, on
+    /// other platforms, or without the `libc` feature, it is the same as
+    /// [`new`](Folder::new). With the
+    /// `log` feature, an unusable `XDG_RUNTIME_DIR` is reported as a warning.
+    pub fn runtime<T: AsRef<OsStr>>(prefix: T) -> crate::Result<Folder> {
+        #[cfg(unix)]
+        if let Some(parent) = crate::env::runtime_dir() {
+            return Folder::with_parent(parent, prefix);
+        }
+        Folder::new(prefix)
+    }
+
+    /// Create a temporary folder in memory if possible.
+    ///
+    /// On Linux with the `libc` feature, `/dev/shm`, `/run/user/$UID`, and the
+    /// default temporary folder are tried in order, and the first one that is
+    /// on `tmpfs` or `ramfs` and accepts the folder is used. Otherwise, it is
+    /// the same as [`new`](Folder::new). Use
+    /// [`is_ram_backed`](Folder::is_ram_backed) to find out where the folder
+    /// ended up.
+    #[inline]
+    pub fn in_memory<T: AsRef<OsStr>>(prefix: T) -> crate::Result<Folder> {
+        crate::memory::create(prefix.as_ref(), crate::memory::candidates())
+    }
+
+    /// Create a temporary folder in a specific folder.
+    ///
+    /// The folder will have a name starting from `prefix`, and it will be
+    /// automatically removed when the object goes out of scope.
+    #[inline]
+    pub fn with_parent<T: AsRef<Path>, U: AsRef<OsStr>>(
+        parent: T,
+        prefix: U,
+    ) -> crate::Result<Folder> {
+        Builder::new().parent(parent).prefix(prefix).folder()
+    }
+
+    /// Create a temporary folder in the first usable of several folders.
+    ///
+    /// See [`Builder::parents`] for details.
+    #[inline]
+    pub fn with_parents<I, T>(parents: I, prefix: T) -> crate::Result<Folder>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+        T: AsRef<OsStr>,
+    {
+        Builder::new().parents(parents).prefix(prefix).folder()
+    }
+
+    /// Create a temporary folder with an exact name in a specific folder.
+    ///
+    /// There is no random part and no second attempt. If `parent` already
+    /// contains an entry called `name`, the error is `AlreadyExists`, and the
+    /// entry is left alone. Otherwise, the folder is handled like any other
+    /// temporary folder, including the checks before removal. The name has to
+    /// be a valid file name, and a relative parent is resolved against the
+    /// current folder right away.
+    pub fn named<T: AsRef<Path>>(parent: T, name: &str) -> crate::Result<Folder> {
+        let parent = parent.as_ref();
+        let context = |error| crate::Error::new(Operation::CreateFolder, parent, error);
+        crate::name::check(name.as_ref(), "name").map_err(context)?;
+        let path = std::path::absolute(parent).map_err(context)?.join(name);
+        #[allow(unused_mut)]
+        let mut options = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            options.mode(0o700);
+        }
+        options.create(&path).map_err(context)?;
+        Ok(Folder::armed(path))
+    }
+
+    /// Take over the responsibility of removing an existing folder.
+    ///
+    /// The folder is handled like any other temporary folder from then on,
+    /// including the checks before removal, for which its identity is recorded
+    /// right away. The path has to be a folder and not a symbolic link to one;
+    /// see [`adopt_symlink_target`](Folder::adopt_symlink_target) for the
+    /// latter. Relative paths are resolved against the current folder.
+    pub fn adopt<T: AsRef<Path>>(path: T) -> crate::Result<Folder> {
+        let path = path.as_ref();
+        let context = |error| crate::Error::new(Operation::AdoptFolder, path, error);
+        let metadata = fs::symlink_metadata(path).map_err(context)?;
+        if !metadata.is_dir() {
+            let error = Error::new(ErrorKind::NotADirectory, "the path is not a folder");
+            return Err(context(error));
+        }
+        Ok(Folder::armed(std::path::absolute(path).map_err(context)?))
+    }
+
+    /// Take over the responsibility of removing the folder a symbolic link
+    /// points at.
+    ///
+    /// The link is resolved once, and the target is adopted as with
+    /// [`adopt`](Folder::adopt), so that the target is what gets removed
+    /// while the link itself is left alone.
+    pub fn adopt_symlink_target<T: AsRef<Path>>(path: T) -> crate::Result<Folder> {
+        let path = path.as_ref();
+        let target = fs::canonicalize(path)
+            .map_err(|error| crate::Error::new(Operation::AdoptFolder, path, error))?;
+        Folder::adopt(target)
+    }
+
+    /// Create a temporary folder next to another path.
+    ///
+    /// The folder is created in the parent folder of `neighbor`, which should
+    /// be used when the folder is to be renamed into `neighbor` later on so
+    /// that both are on the same file system. The neighbor itself does not
+    /// have to exist, and relative paths are resolved against the current
+    /// folder.
+    pub fn beside<T: AsRef<Path>, U: AsRef<OsStr>>(
+        neighbor: T,
+        prefix: U,
+    ) -> crate::Result<Folder> {
+        let neighbor = neighbor.as_ref();
+        let context = |error| crate::Error::new(Operation::CreateFolder, neighbor, error);
+        let mut path = if neighbor.is_absolute() {
+            neighbor.to_path_buf()
+        } else {
+            env::current_dir().map_err(context)?.join(neighbor)
+        };
+        if path.file_name().is_none() {
+            path = fs::canonicalize(&path).map_err(context)?;
+        }
+        match path.parent() {
+            Some(parent) => Folder::with_parent(parent, prefix),
+            None => Err(context(Error::new(
+                ErrorKind::InvalidInput,
+                format!("the path {} has no parent", neighbor.display()),
+            ))),
+        }
+    }
+
+    /// Create a temporary folder and populate it with files.
+    ///
+    /// See [`populate`](Folder::populate) for details. If any of the files
+    /// cannot be created, the folder is removed before the error, which is
+    /// reported as [`Operation::PopulateFolder`], is returned.
+    ///
+    /// ```rust
+    /// use temporary::Folder;
+    ///
+    /// let folder = Folder::with_files("foo", &[
+    ///     ("foo.txt", b"Hi there!"),
+    ///     ("bar/baz.txt", b"Bye!"),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(folder.read_to_string("bar/baz.txt").unwrap(), "Bye!");
+    /// ```
+    pub fn with_files<T: AsRef<OsStr>>(
+        prefix: T,
+        files: &[(&str, &[u8])],
+    ) -> crate::Result<Folder> {
+        let folder = Folder::new(prefix)?;
+        if let Err(error) = folder.populate(files) {
+            let error = crate::Error::new(Operation::PopulateFolder, &folder.path, error);
+            let _ = folder.remove();
+            return Err(error);
+        }
+        Ok(folder)
+    }
+
+    /// Return the temporary folder of the process.
+    ///
+    /// The folder is created on the first call, and its name contains the
+    /// identifier of the process. Subsequent calls return the same folder
+    /// regardless of the prefix. The folder is removed when the process exits
+    /// normally. It requires the `libc` feature.
+    #[cfg(all(any(unix, windows), feature = "libc"))]
+    #[inline]
+    pub fn for_process<T: AsRef<OsStr>>(prefix: T) -> crate::Result<&'static Folder> {
+        crate::process::folder(prefix.as_ref())
+    }
+
+    /// Create a temporary folder with the content of another folder.
+    ///
+    /// See [`copy_from`](Folder::copy_from) for details. In case of failure,
+    /// the partially populated folder is removed.
+    pub fn from_template<T: AsRef<OsStr>, U: AsRef<Path>>(
+        prefix: T,
+        template: U,
+    ) -> crate::Result<Folder> {
+        let folder = Folder::new(prefix)?;
+        folder
+            .copy_from(template)
+            .map_err(|error| crate::Error::new(Operation::PopulateFolder, &folder.path, error))?;
+        Ok(folder)
+    }
+
+    /// Create a temporary folder with a specific suffix in a specific folder.
+    ///
+    /// The folder will have a name starting from `prefix` and ending with
+    /// `suffix`, such as `.workdir`, and it will be automatically removed when
+    /// the object goes out of scope. The suffix may not contain path
+    /// separators.
+    #[inline]
+    pub fn with_affixes<T: AsRef<Path>, U: AsRef<OsStr>>(
+        parent: T,
+        prefix: U,
+        suffix: &str,
+    ) -> crate::Result<Folder> {
+        Builder::new()
+            .parent(parent)
+            .prefix(prefix)
+            .suffix(suffix)
+            .folder()
+    }
+
+    /// Create a temporary folder with specific permissions in a specific
+    /// folder.
+    ///
+    /// The mode is applied by the creation itself, subject to the umask, and
+    /// other than that, the behavior is the same as the one of
+    /// [`with_parent`](Folder::with_parent).
+    #[cfg(unix)]
+    #[inline]
+    pub fn with_permissions<T: AsRef<Path>, U: AsRef<OsStr>>(
+        parent: T,
+        prefix: U,
+        mode: u32,
+    ) -> crate::Result<Folder> {
+        use std::os::unix::fs::PermissionsExt;
+
+        Builder::new()
+            .parent(parent)
+            .prefix(prefix)
+            .permissions(fs::Permissions::from_mode(mode))
+            .folder()
+    }
+
+    pub(crate) fn create(builder: &Builder) -> Result<Folder> {
+        let _span = crate::trace::creating("folder", &builder.prefix);
+        #[allow(unused_mut)]
+        let mut options = fs::DirBuilder::new();
+        // Folders are private by default as other users might share the parent.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+            let mode = builder
+                .permissions
+                .as_ref()
+                .map(|permissions| permissions.mode());
+            options.mode(mode.unwrap_or(0o700));
+        }
+        let mut attempts = 0;
+        #[cfg(all(unix, feature = "libc"))]
+        let mut mkdtemp = false;
+        let path = match (&builder.filesystem, builder.backend) {
+            (Some(filesystem), _) => {
+                crate::name::create(builder, |path| {
+                    attempts += 1;
+                    filesystem.create_dir(path)
+                })?
+                .0
+            }
+            #[cfg(all(unix, feature = "libc"))]
+            (_, crate::Backend::Native) => {
+                attempts = 1;
+                mkdtemp = true;
+                crate::name::mkdtemp(builder)?
+            }
+            _ => {
+                crate::name::create(builder, |path| {
+                    attempts += 1;
+                    options.create(path)
+                })?
+                .0
+            }
+        };
+        let mut folder = Folder {
+            path,
+            removed: AtomicBool::new(false),
+            kept: AtomicBool::new(false),
+            options: Box::new(Options {
+                prefix: builder.prefix.clone(),
+                keep_on_panic: builder.keep_on_panic,
+                removal: builder.removal,
+                expected: Expected::default(),
+                exit: builder.register_for_exit,
+                sync: builder.sync,
+                attempts,
+                registration: Mutex::new(None),
+                session: None,
+                #[cfg(feature = "async")]
+                panic_on_blocking_drop: builder.panic_on_blocking_drop,
+                preserve: Mutex::new(None),
+                frozen: Mutex::new(None),
+                quota: None,
+                plain_command: false,
+                leak: None,
+                filesystem: builder.filesystem.clone(),
+                #[cfg(feature = "archive")]
+                archive: None,
+                #[cfg(windows)]
+                handle: Mutex::new(None),
+            }),
+        };
+        // The path is converted once the folder is removed in case of failure.
+        if builder.verbatim {
+            folder.path = crate::verbatim::path(&folder.path)?;
+        }
+        // Folders created by `mkdtemp` are private, and other modes can only be
+        // applied afterwards, once the folder is removed in case of failure.
+        #[cfg(all(unix, feature = "libc"))]
+        if let (true, Some(permissions)) = (mkdtemp, &builder.permissions) {
+            fs::set_permissions(&folder.path, permissions.clone())?;
+        }
+        #[cfg(windows)]
+        if builder.delete_on_close {
+            let handle = crate::disposition::Handle::open(&folder.path)?;
+            folder.options.handle = Mutex::new(Some(handle));
+        }
+        // The owner is recorded below, so it has to be changed first.
+        #[cfg(feature = "owner")]
+        if let Some((user, group)) = builder.owner {
+            crate::owner::set(&folder.path, user, group)?;
+        }
+        // The checks rely on the standard library.
+        let native = builder.filesystem.is_none();
+        if builder.verify_identity && native {
+            // Without an identity, the check is skipped.
+            folder.options.expected.identity = Identity::of(&folder.path).ok().flatten();
+        }
+        #[cfg(unix)]
+        if builder.verify_owner && native {
+            use std::os::unix::fs::MetadataExt;
+            folder.options.expected.owner = fs::symlink_metadata(&folder.path)
+                .ok()
+                .map(|metadata| metadata.uid());
+        }
+        if builder.register_for_exit {
+            let registration =
+                crate::exit::Registration::new(&folder.path, folder.options.expected);
+            folder.options.registration = Mutex::new(Some(registration));
+        }
+        if let Some(session) = &builder.session {
+            let kind = crate::session::Kind::Folder;
+            let expected = folder.options.expected;
+            let policy = folder.options.removal;
+            let membership = session.join(&folder.path, kind, expected, policy);
+            folder.options.session = Some(membership);
+        }
+        folder.options.leak = crate::leak::Tracker::new(&folder.path);
+        #[cfg(not(unix))]
+        if let Some(permissions) = &builder.permissions {
+            fs::set_permissions(&folder.path, permissions.clone())?;
+        }
+        #[cfg(feature = "acl")]
+        if builder.restrict {
+            crate::acl::restrict(&folder.path)?;
+        }
+        if builder.sync {
+            folder.sync_parent()?;
+        }
+        crate::trace::created("folder", &folder.path, attempts);
+        Ok(folder)
+    }
+
+    /// Take over the responsibility of removing a folder known to exist.
+    pub(crate) fn armed(path: PathBuf) -> Folder {
+        let expected = Expected {
+            identity: Identity::of(&path).ok().flatten(),
+            #[cfg(unix)]
+            owner: {
+                use std::os::unix::fs::MetadataExt;
+                fs::symlink_metadata(&path)
+                    .ok()
+                    .map(|metadata| metadata.uid())
+            },
+        };
+        let leak = crate::leak::Tracker::new(&path);
+        Folder {
+            path,
+            removed: AtomicBool::new(false),
+            kept: AtomicBool::new(false),
+            options: Box::new(Options {
+                expected,
+                leak,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Return the path to the folder.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.as_ref()
+    }
+
+    /// Return the path to the folder and dispose the object without removing
+    /// the actual folder.
+    #[inline]
+    pub fn into_path(mut self) -> PathBuf {
+        self.delete_on_close(false);
+        self.track(crate::leak::LeakStatus::Kept);
+        *self.removed.get_mut() = true;
+        std::mem::take(&mut self.path)
+    }
+
+    /// Return the path to the folder as a UTF-8 path.
+    ///
+    /// The generated part of the name is ASCII, and an error is returned only
+    /// if the parent or the prefix is not valid UTF-8.
+    #[cfg(feature = "camino")]
+    pub fn utf8_path(&self) -> Result<&camino::Utf8Path> {
+        camino::Utf8Path::from_path(&self.path).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "the path {} is not valid UTF-8 due to its parent or prefix",
+                    self.path.display(),
+                ),
+            )
+        })
+    }
+
+    /// Return the path to the folder as a UTF-8 path and dispose the object
+    /// without removing the actual folder.
+    ///
+    /// See [`utf8_path`](Folder::utf8_path) for details. In case of failure,
+    /// the folder is removed as usual.
+    #[cfg(feature = "camino")]
+    pub fn into_utf8_path(self) -> Result<camino::Utf8PathBuf> {
+        self.utf8_path()?;
+        let path = self.into_path();
+        Ok(camino::Utf8PathBuf::try_from(path).expect("the path should be valid UTF-8"))
+    }
+
+    /// Leak the path to the folder and dispose the object without removing the
+    /// actual folder.
+    ///
+    /// The memory of the path is deliberately leaked, and the folder stays on
+    /// disk after the process exits. It is useful for folders that are needed
+    /// for the whole lifetime of the process.
+    #[inline]
+    pub fn leak(self) -> &'static Path {
+        Box::leak(self.into_path().into_boxed_path())
+    }
+
+    /// Return the path to the folder as an object responsible for removing it
+    /// when it goes out of scope.
+    #[inline]
+    pub fn into_temp_path(mut self) -> TempPath {
+        self.delete_on_close(false);
+        self.untrack();
+        *self.removed.get_mut() = true;
+        TempPath::from_path(std::mem::take(&mut self.path))
+    }
+
+    /// Return the number of candidate names tried when creating the folder.
+    ///
+    /// It is one unless some names were taken. With the native backend, it is
+    /// always one.
+    #[inline]
+    pub fn creation_attempts(&self) -> u32 {
+        self.options.attempts
+    }
+
+    /// Check if the folder has been removed or disposed by the object.
+    #[inline]
+    pub fn is_removed(&self) -> bool {
+        self.removed.load(Ordering::Acquire)
+    }
+
+    /// Check if the folder resides on a file system kept in memory.
+    ///
+    /// It is detected on Linux with the `libc` feature, and `false` is returned
+    /// otherwise.
+    #[inline]
+    pub fn is_ram_backed(&self) -> bool {
+        crate::memory::is_ram_backed(&self.path)
+    }
+
+    /// Check if the folder exists on disk.
+    #[inline]
+    pub fn exists(&self) -> bool {
+        fs::metadata(&self.path).is_ok()
+    }
+
+    /// Check that the folder still exists and is a folder.
+    ///
+    /// It is useful for detecting folders removed by external cleaners.
+    pub fn revalidate(&self) -> Result<()> {
+        match fs::symlink_metadata(&self.path) {
+            Ok(metadata) if metadata.is_dir() => Ok(()),
+            Ok(_) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{} is no longer a folder", self.path.display()),
+            )),
+            Err(error) => Err(Error::new(
+                error.kind(),
+                format!("{} is no longer available: {}", self.path.display(), error),
+            )),
+        }
+    }
+
+    /// Run a closure with the path to the folder and keep the folder only if
+    /// the closure succeeds.
+    ///
+    /// In case of success, the object is disposed without removing the actual
+    /// folder, and the path is returned along with the value of the closure.
+    /// Otherwise, including a panic, the folder is removed.
+    pub fn persist_if<F, T, E>(self, f: F) -> std::result::Result<(T, PathBuf), E>
+    where
+        F: FnOnce(&Path) -> std::result::Result<T, E>,
+    {
+        let value = f(&self.path)?;
+        Ok((value, self.into_path()))
+    }
+
+    /// Prevent the folder from being removed when the object goes out of
+    /// scope.
+    ///
+    /// It is the non-consuming counterpart of
+    /// [`into_path`](Folder::into_path), which can be undone using
+    /// [`unkeep`](Folder::unkeep).
+    #[inline]
+    pub fn keep(&mut self) {
+        self.keep_shared();
+    }
+
+    /// Undo the effect of [`keep`](Folder::keep).
+    #[inline]
+    pub fn unkeep(&mut self) {
+        if *self.kept.get_mut() && !*self.removed.get_mut() {
+            self.delete_on_close(true);
+        }
+        *self.kept.get_mut() = false;
+        let registration = self.options.registration.get_mut();
+        let registration = registration.unwrap_or_else(|error| error.into_inner());
+        if self.options.exit && registration.is_none() && !*self.removed.get_mut() {
+            let expected = self.options.expected;
+            *registration = Some(crate::exit::Registration::new(&self.path, expected));
+        }
+        if let (Some(session), false) = (&self.options.session, *self.removed.get_mut()) {
+            session.rejoin();
+        }
+        if !*self.removed.get_mut() {
+            self.track(crate::leak::LeakStatus::Alive);
+        }
+    }
+
+    /// Prevent the folder from being removed when the object goes out of
+    /// scope while the thread is panicking.
+    ///
+    /// It is useful for inspecting the folder after a failed assertion in a
+    /// test. The path to the folder is then printed to the standard error.
+    #[inline]
+    pub fn keep_on_panic(&mut self, value: bool) {
+        self.options.keep_on_panic = value;
+    }
+
+    /// Preserve the content of the folder at `target` before removing it.
+    ///
+    /// During the removal, the folder is moved to `target` or copied there if
+    /// `target` is on another file system. An existing entry at `target` is
+    /// never replaced; instead, a numeric suffix, such as `.1`, is appended to
+    /// the name. Failures to preserve are reported by
+    /// [`remove`](Folder::remove) and are ignored when the object goes out of
+    /// scope; the folder is removed regardless.
+    #[inline]
+    pub fn preserve_to<T: AsRef<Path>>(&mut self, target: T) {
+        *self.preserve() = Some(target.as_ref().to_path_buf());
+    }
+
+    /// Cancel the effect of [`preserve_to`](Folder::preserve_to).
+    #[inline]
+    pub fn clear_preserve(&mut self) {
+        *self.preserve() = None;
+    }
+
+    /// Share the folder between several owners.
+    ///
+    /// The folder is removed once the last clone of the resulting object goes
+    /// out of scope unless it has been removed explicitly before.
+    #[inline]
+    pub fn shared(self) -> SharedFolder {
+        SharedFolder::from(self)
+    }
+
+    /// Share the folder between several owners.
+    ///
+    /// It is the same as [`shared`](Folder::shared).
+    #[inline]
+    pub fn into_shared(self) -> SharedFolder {
+        SharedFolder::from(self)
+    }
+
+    /// Write the content of the folder to a gzip-compressed tarball.
+    ///
+    /// Entries are stored in lexicographic order with relative paths and
+    /// without timestamps or owners, so that the same content results in the
+    /// same archive, and symbolic links are stored as links. The archive may
+    /// not be inside the folder, and an existing file is replaced. The size of
+    /// the archive is returned.
+    #[cfg(feature = "archive")]
+    #[inline]
+    pub fn archive_to<T: AsRef<Path>>(&self, target: T) -> Result<u64> {
+        crate::archive::write(&self.path, target.as_ref())
+    }
+
+    /// Archive the folder to `target` when the object goes out of scope while
+    /// the thread is panicking.
+    ///
+    /// It is useful for collecting the content of folders of failed tests in
+    /// continuous integration. The archive is written as in
+    /// [`archive_to`](Folder::archive_to) before the folder is removed or
+    /// kept, and failures are reported to the cleanup error handler.
+    #[cfg(feature = "archive")]
+    #[inline]
+    pub fn archive_on_drop<T: AsRef<Path>>(&mut self, target: T) {
+        self.options.archive = Some(target.as_ref().to_path_buf());
+    }
+
+    /// Make a link at `link` point at the folder.
+    ///
+    /// It is useful for having a predictable path, such as `/tmp/job-latest`,
+    /// leading to the most recent folder. An existing link is replaced, and
+    /// the link is created next to `link` and renamed into place, so that the
+    /// replacement is atomic on Unix; existing entries other than links are
+    /// never replaced. On Windows, a symbolic link is created if the process
+    /// has the privilege to do so and a junction otherwise. The link is removed
+    /// when the resulting guard goes out of scope unless it has been replaced
+    /// to point elsewhere.
+    #[inline]
+    pub fn alias<T: AsRef<Path>>(&self, link: T) -> Result<AliasGuard<'_>> {
+        crate::alias::create(self, link.as_ref())
+    }
+
+    /// Make the folder the current folder of the process.
+    ///
+    /// The previous current folder is restored when the resulting guard goes
+    /// out of scope. Since the current folder is global to the process, this
+    /// is not suitable for tests run in parallel; see [`CwdGuard`] for
+    /// details.
+    #[inline]
+    pub fn enter(&self) -> Result<CwdGuard<'_>> {
+        CwdGuard::enter(&self.path)
+    }
+
+    /// Point the temporary folder of the process at the folder.
+    ///
+    /// The previous values of the variables are restored when the resulting
+    /// guard goes out of scope; see [`TempDirGuard`] for the variables.
+    ///
+    /// # Safety
+    ///
+    /// The environment is global to the process, and no other thread may read
+    /// or write it from the call until the guard is dropped, including via C
+    /// code such as `getenv`. Doing so is undefined behavior on some
+    /// platforms. In particular, this is not suitable for tests run in
+    /// parallel in the same process.
+    #[inline]
+    pub unsafe fn set_as_temp_dir(&self) -> TempDirGuard<'_> {
+        // SAFETY: The caller upholds the same conditions.
+        unsafe { TempDirGuard::set(&self.path) }
+    }
+
+    /// Prepare a command running inside the folder.
+    ///
+    /// The current folder of the command is the folder, and so is the
+    /// temporary folder of the command, which is communicated via `TMPDIR` on
+    /// Unix and other platforms and via `TMP` and `TEMP` on Windows, as well as
+    /// via `TEMPORARY_DIR` for children relying on this crate. The variables
+    /// can be left intact using [`set_command_env`](Folder::set_command_env).
+    pub fn command<T: AsRef<OsStr>>(&self, program: T) -> std::process::Command {
+        let mut command = std::process::Command::new(program);
+        command.current_dir(&self.path);
+        if !self.options.plain_command {
+            for name in crate::env::TEMP_VARIABLES {
+                command.env(name, &self.path);
+            }
+        }
+        command
+    }
+
+    /// Prepare a command running inside a specific folder inside the folder.
+    ///
+    /// It is the same as [`command`](Folder::command) except for the current
+    /// folder of the command, which is given relative to the folder; the
+    /// temporary folder of the command is still the folder itself. The path
+    /// may not escape the folder, and it has to exist by the time the command
+    /// is spawned.
+    pub fn command_in<T: AsRef<Path>, U: AsRef<OsStr>>(
+        &self,
+        relative: T,
+        program: U,
+    ) -> Result<std::process::Command> {
+        let path = self.inside(relative.as_ref())?;
+        let mut command = self.command(program);
+        command.current_dir(path);
+        Ok(command)
+    }
+
+    /// Set whether commands prepared via [`command`](Folder::command) have
+    /// their temporary folders pointed at the folder.
+    ///
+    /// The default is true.
+    #[inline]
+    pub fn set_command_env(&mut self, value: bool) {
+        self.options.plain_command = !value;
+    }
+
+    /// Create a temporary folder inside the folder.
+    ///
+    /// The resulting object borrows the folder, which ensures that the child
+    /// is removed before the parent.
+    #[inline]
+    pub fn child<T: AsRef<OsStr>>(&self, prefix: T) -> crate::Result<ChildFolder<'_>> {
+        Ok(ChildFolder {
+            folder: Folder::with_parent(&self.path, prefix)?,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Create numbered folders inside the folder.
+    ///
+    /// The folders are named `0`, `1`, and so on up to `count - 1`, and they
+    /// are left for the folder to remove. See
+    /// [`children_with`](Folder::children_with) for details.
+    #[inline]
+    pub fn children(&self, count: usize) -> Result<Vec<PathBuf>> {
+        self.children_with(count, |index| index.to_string())
+    }
+
+    /// Create folders named by a closure inside the folder.
+    ///
+    /// The closure is called with indices from zero to `count - 1`. Either all
+    /// the folders are created, or, in case of failure, the ones created by
+    /// the call are removed, and the error names the failing index.
+    pub fn children_with<F, T>(&self, count: usize, mut name: F) -> Result<Vec<PathBuf>>
+    where
+        F: FnMut(usize) -> T,
+        T: AsRef<OsStr>,
+    {
+        let mut paths = Vec::with_capacity(count);
+        for index in 0..count {
+            let name = name(index);
+            let result = crate::name::check(name.as_ref(), "name")
+                .map(|_| self.path.join(name.as_ref()))
+                .and_then(|path| fs::create_dir(&path).map(|_| path));
+            match result {
+                Ok(path) => paths.push(path),
+                Err(error) => {
+                    for path in paths.iter().rev() {
+                        let _ = fs::remove_dir(path);
+                    }
+                    return Err(Error::new(
+                        error.kind(),
+                        format!(
+                            "failed to create child {} in {}: {}",
+                            index,
+                            self.path.display(),
+                            error,
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Create a temporary file inside the folder.
+    ///
+    /// The file is left for the folder to remove, and it is not removed when
+    /// the resulting object goes out of scope.
+    #[inline]
+    pub fn file<T: AsRef<OsStr>>(&self, prefix: T) -> crate::Result<File> {
+        Ok(File::with_parent(&self.path, prefix)?.into_owned())
+    }
+
+    /// Create a file with a specific name inside the folder.
+    ///
+    /// The file should not exist, and the name may not contain path separators.
+    /// As with [`file`](Folder::file), the file is left for the folder to
+    /// remove.
+    pub fn named_file<T: AsRef<OsStr>>(&self, name: T) -> crate::Result<File> {
+        let name = name.as_ref();
+        let context = |error| crate::Error::new(Operation::CreateFile, &self.path, error);
+        crate::name::check(name, "name").map_err(context)?;
+        let file = File::create_named(self.path.join(name)).map_err(context)?;
+        Ok(file.into_owned())
+    }
+
+    /// Write a file inside the folder.
+    ///
+    /// The path is relative to the folder and may not be absolute or contain
+    /// `..`. Intermediate folders are created as needed, and an existing file
+    /// is replaced. The path to the file is returned, and errors name it.
+    pub fn write<T: AsRef<Path>, U: AsRef<[u8]>>(
+        &self,
+        relative: T,
+        contents: U,
+    ) -> Result<PathBuf> {
+        let path = self.inside(relative.as_ref())?;
+        let context = |error: Error| {
+            Error::new(
+                error.kind(),
+                format!("failed to write {}: {}", path.display(), error),
+            )
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(context)?;
+        }
+        fs::write(&path, contents).map_err(context)?;
+        Ok(path)
+    }
+
+    /// Read a file inside the folder.
+    ///
+    /// The path is subject to the same restrictions as the one of
+    /// [`write`](Folder::write), and errors name the file.
+    pub fn read<T: AsRef<Path>>(&self, relative: T) -> Result<Vec<u8>> {
+        let path = self.inside(relative.as_ref())?;
+        fs::read(&path).map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!("failed to read {}: {}", path.display(), error),
+            )
+        })
+    }
+
+    /// Read a file inside the folder as a string.
+    ///
+    /// See [`read`](Folder::read) for details.
+    pub fn read_to_string<T: AsRef<Path>>(&self, relative: T) -> Result<String> {
+        let path = self.inside(relative.as_ref())?;
+        fs::read_to_string(&path).map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!("failed to read {}: {}", path.display(), error),
+            )
+        })
+    }
+
+    /// Create an empty file inside the folder or update the modification time
+    /// of an existing one.
+    ///
+    /// The path is subject to the same restrictions as the one of
+    /// [`write`](Folder::write), and intermediate folders are created as
+    /// needed. The path to the file is returned, and errors name it.
+    pub fn touch<T: AsRef<Path>>(&self, relative: T) -> Result<PathBuf> {
+        let path = self.inside(relative.as_ref())?;
+        let context = |error: Error| {
+            Error::new(
+                error.kind(),
+                format!("failed to touch {}: {}", path.display(), error),
+            )
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(context)?;
+        }
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(std::time::SystemTime::now()))
+            .map_err(context)?;
+        Ok(path)
+    }
+
+    /// Create a symbolic link inside the folder.
+    ///
+    /// The path to the link is subject to the same restrictions as the one of
+    /// [`write`](Folder::write), and intermediate folders are created as
+    /// needed. The target is stored as given, may be relative to the link, and
+    /// does not have to exist. On Windows, a link to a folder is created if the
+    /// target is a folder at that moment, and the lack of the privilege needed
+    /// for creating links results in an error of kind `PermissionDenied`. Links
+    /// are removed along with the folder without being followed. The path to
+    /// the link is returned.
+    pub fn symlink<T: AsRef<Path>, U: AsRef<Path>>(&self, target: T, link: U) -> Result<PathBuf> {
+        let path = self.inside(link.as_ref())?;
+        let context = |error: Error| {
+            Error::new(
+                error.kind(),
+                format!("failed to create {}: {}", path.display(), error),
+            )
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(context)?;
+        }
+        crate::link::symlink(target.as_ref(), &path).map_err(context)?;
+        Ok(path)
+    }
+
+    /// Create a named pipe inside the folder.
+    ///
+    /// The path is subject to the same restrictions as the one of
+    /// [`write`](Folder::write), and intermediate folders are created as
+    /// needed. The mode is subject to the umask. The path to the pipe is
+    /// returned.
+    #[cfg(all(unix, feature = "fifo"))]
+    pub fn fifo<T: AsRef<Path>>(&self, relative: T, mode: u32) -> Result<PathBuf> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = self.inside(relative.as_ref())?;
+        let context = |error: Error| {
+            Error::new(
+                error.kind(),
+                format!("failed to create {}: {}", path.display(), error),
+            )
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(context)?;
+        }
+        let name = std::ffi::CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| context(Error::from(ErrorKind::InvalidInput)))?;
+        if unsafe { libc::mkfifo(name.as_ptr(), mode as libc::mode_t) } != 0 {
+            return Err(context(Error::last_os_error()));
+        }
+        Ok(path)
+    }
+
+    /// Create several files inside the folder.
+    ///
+    /// Each path is subject to the same restrictions as the one of
+    /// [`write`](Folder::write), and intermediate folders are created as
+    /// needed. Existing files are not replaced, so duplicate paths result in an
+    /// error, and the files created before a failure are left in place.
+    pub fn populate(&self, files: &[(&str, &[u8])]) -> Result<()> {
+        for (relative, contents) in files {
+            let path = self.inside(Path::new(relative))?;
+            let context = |error: Error| {
+                Error::new(
+                    error.kind(),
+                    format!("failed to write {}: {}", path.display(), error),
+                )
+            };
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(context)?;
+            }
+            fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(contents))
+                .map_err(context)?;
+        }
+        Ok(())
+    }
+
+    fn inside(&self, relative: &Path) -> Result<PathBuf> {
+        use std::path::Component;
+
+        let mut path = self.path.clone();
+        for component in relative.components() {
+            match component {
+                Component::Normal(name) => path.push(name),
+                Component::CurDir => {}
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "the path {} is not relative to the folder or escapes it",
+                            relative.display(),
+                        ),
+                    ))
+                }
+            }
+        }
+        if path == self.path {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("the path {:?} does not name an entry", relative),
+            ));
+        }
+        Ok(path)
+    }
+
+    /// Copy the content of another folder into the folder recursively.
+    ///
+    /// Empty folders are preserved, and so are permissions. Symbolic links are
+    /// recreated as opposed to being followed, which is not supported on
+    /// platforms other than Unix and Windows. The error names the entry that
+    /// failed to be copied.
+    #[inline]
+    pub fn copy_from<T: AsRef<Path>>(&self, template: T) -> Result<()> {
+        crate::copy::content(template.as_ref(), &self.path)
+    }
+
+    /// Create a temporary folder with a copy of the content of the folder.
+    ///
+    /// The new folder is created next to the folder with the same prefix, and
+    /// it is independent of the folder. The content is copied as described in
+    /// [`copy_from`](Folder::copy_from), and so are the permissions of the
+    /// folder itself.
+    pub fn duplicate(&self) -> crate::Result<Folder> {
+        let mut builder = Builder::new();
+        builder.prefix(&self.options.prefix).sanitize_prefix(true);
+        if let Some(parent) = self.path.parent() {
+            builder.parent(parent);
+        }
+        let folder = builder.folder()?;
+        folder
+            .copy_from(&self.path)
+            .and_then(|_| self.metadata())
+            .and_then(|metadata| fs::set_permissions(&folder.path, metadata.permissions()))
+            .map_err(|error| crate::Error::new(Operation::PopulateFolder, &folder.path, error))?;
+        Ok(folder)
+    }
+
+    /// Watch for changes inside the folder and its descendants.
+    #[cfg(feature = "watch")]
+    #[inline]
+    pub fn watch(&self) -> Result<crate::Watcher<'_>> {
+        crate::Watcher::new(self)
+    }
+
+    /// Wait until an entry appears at a path relative to the folder.
+    ///
+    /// The wait is driven by notifications from the system with occasional
+    /// checks. An error of kind `TimedOut` is returned if the entry does not
+    /// appear within `timeout`.
+    #[cfg(feature = "watch")]
+    #[inline]
+    pub fn wait_for<T: AsRef<Path>>(&self, path: T, timeout: std::time::Duration) -> Result<()> {
+        crate::watch::wait_for(self, path.as_ref(), timeout)
+    }
+
+    /// Iterate over the entries of the folder.
+    ///
+    /// Errors name the folder. An error of kind `Unsupported` is returned for
+    /// folders on a custom [`Filesystem`].
+    pub fn entries(&self) -> Result<impl Iterator<Item = Result<fs::DirEntry>> + '_> {
+        if self.options.filesystem.is_some() {
+            return Err(self.context(Error::new(
+                ErrorKind::Unsupported,
+                "listing entries requires the standard file system",
+            )));
+        }
+        let entries = fs::read_dir(&self.path).map_err(|error| self.context(error))?;
+        Ok(entries.map(|entry| entry.map_err(|error| self.context(error))))
+    }
+
+    /// Check if the folder is empty.
+    ///
+    /// Only the first entry is read. Errors name the folder.
+    pub fn is_empty(&self) -> Result<bool> {
+        if let Some(filesystem) = &self.options.filesystem {
+            let entries = filesystem
+                .read_dir(&self.path)
+                .map_err(|error| self.context(error))?;
+            return Ok(entries.is_empty());
+        }
+        Ok(self.entries()?.next().transpose()?.is_none())
+    }
+
+    /// Return the metadata of the folder.
+    ///
+    /// Errors name the folder.
+    #[inline]
+    pub fn metadata(&self) -> Result<fs::Metadata> {
+        match &self.options.filesystem {
+            Some(filesystem) => filesystem.metadata(&self.path),
+            _ => fs::metadata(&self.path),
+        }
+        .map_err(|error| self.context(error))
+    }
+
+    /// Compute the apparent size of the content of the folder in bytes.
+    ///
+    /// Symbolic links are not followed, and entries disappearing during the
+    /// computation are skipped.
+    pub fn size(&self) -> Result<u64> {
+        let mut size = 0;
+        self.walk(|_, metadata| {
+            if !metadata.is_dir() {
+                size += metadata.len();
+            }
+        })?;
+        Ok(size)
+    }
+
+    fn walk<F: FnMut(&Path, &fs::Metadata)>(&self, visit: F) -> Result<()> {
+        match &self.options.filesystem {
+            Some(filesystem) => crate::walk::each_in(&**filesystem, &self.path, visit),
+            _ => crate::walk::each(&self.path, visit),
+        }
+    }
+
+    /// Limit the apparent size of the content of the folder in bytes.
+    ///
+    /// The quota is advisory: it is not enforced by the file system, and it is
+    /// only checked by [`check_quota`](Folder::check_quota) and
+    /// [`write_guarded`](Folder::write_guarded).
+    #[inline]
+    pub fn set_quota(&mut self, bytes: u64) {
+        self.options.quota = Some(bytes);
+    }
+
+    /// Cancel the effect of [`set_quota`](Folder::set_quota).
+    #[inline]
+    pub fn clear_quota(&mut self) {
+        self.options.quota = None;
+    }
+
+    /// Compute the usage of the folder relative to its quota.
+    ///
+    /// The usage is computed as in [`size`](Folder::size).
+    #[inline]
+    pub fn check_quota(&self) -> Result<QuotaStatus> {
+        Ok(QuotaStatus {
+            used: self.size()?,
+            limit: self.options.quota,
+        })
+    }
+
+    /// Move a file into the folder under a specific name.
+    ///
+    /// The file is renamed if possible. If `source` is on another file system,
+    /// it is copied, synchronized with the disk, and then removed, and a copy
+    /// failing midway is removed before the error is returned. An existing
+    /// entry called `name` results in an error of kind `AlreadyExists`. The
+    /// name may not contain path separators. On custom file systems, the file
+    /// is only renamed.
+    pub fn move_file_in<T: AsRef<Path>>(&self, source: T, name: &str) -> Result<PathBuf> {
+        let source = source.as_ref();
+        let target = self.target(source, name, false)?;
+        match self.rename_in(source, &target) {
+            Err(error) if self.copies(&error) => move_file(source, &target)?,
+            result => result.map_err(|error| moving(source, &target, error))?,
+        }
+        Ok(target)
+    }
+
+    /// Move a folder into the folder under a specific name.
+    ///
+    /// It is the same as [`move_file_in`](Folder::move_file_in) except that
+    /// the content is copied recursively if `source` is on another file system,
+    /// without synchronizing it with the disk. If the copy succeeds but
+    /// `source` fails to be removed, the copy is left in place.
+    pub fn move_dir_in<T: AsRef<Path>>(&self, source: T, name: &str) -> Result<PathBuf> {
+        let source = source.as_ref();
+        let target = self.target(source, name, true)?;
+        match self.rename_in(source, &target) {
+            Err(error) if self.copies(&error) => move_dir(source, &target)?,
+            result => result.map_err(|error| moving(source, &target, error))?,
+        }
+        Ok(target)
+    }
+
+    fn rename_in(&self, source: &Path, target: &Path) -> Result<()> {
+        match &self.options.filesystem {
+            Some(filesystem) => filesystem.rename(source, target),
+            _ => rename_noreplace(source, target),
+        }
+    }
+
+    // Entries are copied across the standard file systems only.
+    fn copies(&self, error: &Error) -> bool {
+        error.kind() == ErrorKind::CrossesDevices && self.options.filesystem.is_none()
+    }
+
+    fn target(&self, source: &Path, name: &str, folder: bool) -> Result<PathBuf> {
+        crate::name::check(name.as_ref(), "name")?;
+        let metadata = match &self.options.filesystem {
+            Some(filesystem) => filesystem.symlink_metadata(source),
+            _ => fs::symlink_metadata(source),
+        };
+        let metadata = metadata.map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!("failed to move {}: {}", source.display(), error),
+            )
+        })?;
+        match (metadata.is_dir(), folder) {
+            (true, false) => Err(Error::new(
+                ErrorKind::IsADirectory,
+                format!("failed to move {}, which is a folder", source.display()),
+            )),
+            (false, true) => Err(Error::new(
+                ErrorKind::NotADirectory,
+                format!("failed to move {}, which is not a folder", source.display()),
+            )),
+            _ => Ok(self.path.join(name)),
+        }
+    }
+
+    /// Write a file with a specific name inside the folder unless it would
+    /// exceed the quota.
+    ///
+    /// An existing file is replaced, and its current size is not counted
+    /// against the quota. If the quota would be exceeded, nothing is written,
+    /// and an error of kind [`ErrorKind::QuotaExceeded`] carrying
+    /// [`QuotaExceeded`] is returned. The name may not contain path
+    /// separators. The check and the write are not atomic with respect to
+    /// other writers.
+    pub fn write_guarded<T: AsRef<OsStr>>(&self, name: T, contents: &[u8]) -> Result<PathBuf> {
+        let name = name.as_ref();
+        crate::name::check(name, "name")?;
+        let path = self.path.join(name);
+        if self.options.quota.is_some() {
+            let mut status = self.check_quota()?;
+            let existing = match fs::symlink_metadata(&path) {
+                Ok(metadata) if !metadata.is_dir() => metadata.len(),
+                _ => 0,
+            };
+            status.used = status.used.saturating_sub(existing);
+            let requested = contents.len() as u64;
+            if status
+                .remaining()
+                .is_some_and(|remaining| remaining < requested)
+            {
+                return Err(QuotaExceeded::new(path, status, requested).into());
+            }
+        }
+        fs::write(&path, contents).map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!("failed to write {}: {}", path.display(), error),
+            )
+        })?;
+        Ok(path)
+    }
+
+    /// Compute the space allocated for the content of the folder in bytes.
+    ///
+    /// It is the same as [`size`](Folder::size) except that allocated blocks
+    /// are counted as opposed to the apparent size.
+    #[cfg(unix)]
+    pub fn disk_usage(&self) -> Result<u64> {
+        use std::os::unix::fs::MetadataExt;
+
+        let mut size = 0;
+        self.walk(|_, metadata| size += metadata.blocks() * 512)?;
+        Ok(size)
+    }
+
+    /// Count the files and folders inside the folder.
+    ///
+    /// Symbolic links are counted as files and are not followed.
+    pub fn count(&self) -> Result<(u64, u64)> {
+        let (mut files, mut folders) = (0, 0);
+        self.walk(|_, metadata| {
+            if metadata.is_dir() {
+                folders += 1;
+            } else {
+                files += 1;
+            }
+        })?;
+        Ok((files, folders))
+    }
+
+    /// Make the folder and its content read-only.
+    ///
+    /// The write permissions of the folder and of all the entries inside are
+    /// removed, and the original permissions are restored via
+    /// [`thaw`](Folder::thaw) or before the folder is removed. Symbolic links
+    /// are not followed. On Windows, the read-only attribute does not prevent
+    /// entries from being added to folders. Freezing a frozen folder has no
+    /// effect, and so do entries added afterwards by privileged users.
+    pub fn freeze(&mut self) -> Result<()> {
+        let frozen = self.options.frozen.get_mut();
+        if frozen.unwrap_or_else(|error| error.into_inner()).is_some() {
+            return Ok(());
+        }
+        let metadata = fs::symlink_metadata(&self.path).map_err(|error| self.context(error))?;
+        let mut entries = vec![(self.path.clone(), metadata.permissions())];
+        crate::walk::each(&self.path, |path, metadata| {
+            if !metadata.file_type().is_symlink() {
+                entries.push((path.to_path_buf(), metadata.permissions()));
+            }
+        })
+        .map_err(|error| self.context(error))?;
+        for (index, (path, permissions)) in entries.iter().enumerate() {
+            let mut readonly = permissions.clone();
+            readonly.set_readonly(true);
+            if let Err(error) = fs::set_permissions(path, readonly) {
+                let _ = thaw(&entries[..index]);
+                return Err(Error::new(
+                    error.kind(),
+                    format!("failed to freeze {}: {}", path.display(), error),
+                ));
+            }
+        }
+        let frozen = self.options.frozen.get_mut();
+        *frozen.unwrap_or_else(|error| error.into_inner()) = Some(entries);
+        Ok(())
+    }
+
+    /// Undo the effect of [`freeze`](Folder::freeze).
+    ///
+    /// The original permissions are restored even if some of them fail to be,
+    /// and the first failure is returned.
+    #[inline]
+    pub fn thaw(&mut self) -> Result<()> {
+        self.thaw_shared()
+    }
+
+    /// Record the content of the folder for comparing it later.
+    ///
+    /// See [`Manifest`] for details.
+    #[inline]
+    pub fn manifest(&self) -> Result<Manifest> {
+        Manifest::capture(&self.path)
+    }
+
+    /// Remove the content of the folder but not the folder itself.
+    ///
+    /// Symbolic links are removed as opposed to their targets. The entries are
+    /// removed according to the same settings as the folder itself, such as
+    /// [`Builder::force_remove`](crate::Builder::force_remove) and
+    /// [`Builder::shred`](crate::Builder::shred). The removal continues past
+    /// failing entries, and the resulting error lists them.
+    pub fn clear(&self) -> crate::Result<()> {
+        let context = |error| crate::Error::new(Operation::Clear, &self.path, error);
+        let mut failures = Vec::new();
+        if let Some(filesystem) = &self.options.filesystem {
+            for path in filesystem.read_dir(&self.path).map_err(context)? {
+                if let Err(error) = filesystem.symlink_metadata(&path).and_then(|metadata| {
+                    match metadata.is_dir() {
+                        true => filesystem.remove_dir_all(&path),
+                        _ => filesystem.remove_file(&path),
+                    }
+                }) {
+                    failures.push((path, error));
+                }
+            }
+        } else {
+            for entry in fs::read_dir(&self.path).map_err(context)? {
+                let entry = entry.map_err(context)?;
+                let path = entry.path();
+                if let Err(error) = entry.file_type().and_then(|file_type| {
+                    crate::remove::entry_with(&path, file_type, &self.options.removal)
+                }) {
+                    failures.push((path, error));
+                }
+            }
+        }
+        let kind = match failures.first() {
+            Some((_, error)) => error.kind(),
+            None => return Ok(()),
+        };
+        let failures = failures
+            .iter()
+            .map(|(path, error)| format!("{}: {}", path.display(), error))
+            .collect::<Vec<_>>();
+        Err(context(Error::new(kind, failures.join("; "))))
+    }
+
+    /// Move the folder to `target` and dispose the object without removing the
+    /// actual folder.
+    ///
+    /// An existing entry at `target` results in an error of kind
+    /// `AlreadyExists`, and so does a missing parent of `target` in an error of
+    /// kind `NotFound`. Moving across file systems is not supported. In case of
+    /// failure, the object is handed back as a part of the error. If
+    /// synchronization is requested via [`Builder::sync`](crate::Builder::sync)
+    /// and fails, the folder has already been moved, and the object in the
+    /// error refers to `target` and does not remove it.
+    pub fn rename_into<T: AsRef<Path>>(
+        self,
+        target: T,
+    ) -> std::result::Result<PathBuf, RenameError> {
+        let target = target.as_ref();
+        let filesystem = self.options.filesystem.as_deref();
+        let result = check_parent(target, filesystem).and_then(|_| match filesystem {
+            Some(filesystem) => filesystem.rename(&self.path, target),
+            _ => rename_noreplace(&self.path, target),
+        });
+        self.finish_rename(result, target)
+    }
+
+    /// Move the folder to `target`, replacing an empty folder, and dispose the
+    /// object without removing the actual folder.
+    ///
+    /// It is the same as [`rename_into`](Folder::rename_into) except that an
+    /// existing empty folder at `target` is replaced. On Windows and on custom
+    /// file systems, the replacement is not atomic.
+    pub fn rename_into_overwrite<T: AsRef<Path>>(
+        self,
+        target: T,
+    ) -> std::result::Result<PathBuf, RenameError> {
+        let target = target.as_ref();
+        let filesystem = self.options.filesystem.as_deref();
+        let result = check_parent(target, filesystem).and_then(|_| match filesystem {
+            Some(filesystem) => rename_overwrite_in(filesystem, &self.path, target),
+            _ => rename_overwrite(&self.path, target),
+        });
+        self.finish_rename(result, target)
+    }
+
+    fn finish_rename(
+        mut self,
+        result: Result<()>,
+        target: &Path,
+    ) -> std::result::Result<PathBuf, RenameError> {
+        if result.is_ok() {
+            self.delete_on_close(false);
+            self.track(crate::leak::LeakStatus::Kept);
+        }
+        match result {
+            Ok(_) if self.options.sync => {
+                *self.removed.get_mut() = true;
+                let source = std::mem::replace(&mut self.path, target.to_path_buf());
+                match crate::sync::parent(&source).and_then(|_| self.sync_parent()) {
+                    Ok(_) => Ok(std::mem::take(&mut self.path)),
+                    Err(error) => Err(RenameError {
+                        error: crate::Error::new(Operation::Rename, target, error),
+                        folder: self,
+                    }),
+                }
+            }
+            Ok(_) => {
+                *self.removed.get_mut() = true;
+                Ok(target.to_path_buf())
+            }
+            Err(error) => Err(RenameError {
+                error: crate::Error::new(Operation::Rename, target, error),
+                folder: self,
+            }),
+        }
+    }
+
+    /// Synchronize the folder containing the folder with the disk.
+    ///
+    /// It makes the creation or a rename of the folder durable in case of a
+    /// crash. It is done automatically if requested via
+    /// [`Builder::sync`](crate::Builder::sync).
+    #[inline]
+    pub fn sync_parent(&self) -> Result<()> {
+        crate::sync::parent(&self.path)
+    }
+
+    /// Remove the folder.
+    ///
+    /// In case of failure, the folder is handed back via [`RemoveError`], so
+    /// that the removal can be retried, and it is attempted again when the
+    /// object goes out of scope.
+    #[inline]
+    pub fn remove(self) -> std::result::Result<(), RemoveError> {
+        match self.cleanup() {
+            Ok(()) => Ok(()),
+            Err(error) => Err(RemoveError {
+                error: crate::Error::new(Operation::Remove, &self.path, error),
+                folder: self,
+            }),
+        }
+    }
+
+    /// Remove the folder extending permissions as needed.
+    ///
+    /// On Unix, folders whose permissions prevent listing or removing their
+    /// entries, such as the ones with mode `000`, are given full permissions
+    /// for the owner. Only the folder and its content are modified, and
+    /// symbolic links are not followed. On other platforms, it is the same as
+    /// [`remove`](Folder::remove).
+    #[inline]
+    pub fn force_remove(mut self) -> std::result::Result<(), RemoveError> {
+        self.options.removal.force = true;
+        self.remove()
+    }
+
+    /// Remove the folder continuing past failures.
+    ///
+    /// Unlike [`remove`](Folder::remove), which stops at the first entry that
+    /// fails to be removed, it removes as much as possible and reports all
+    /// the entries that remain. The folder is not removed again when the
+    /// object goes out of scope.
+    #[inline]
+    pub fn try_remove(self) -> std::result::Result<(), RemovalReport> {
+        self.remove_reporting(crate::remove::exhaustive)
+    }
+
+    /// Remove the folder using several threads continuing past failures.
+    ///
+    /// The folder is walked breadth-first, the removal of the entries is
+    /// distributed among `threads` threads, and the folders are then removed
+    /// bottom-up. Zero threads stand for the available parallelism. Symbolic
+    /// links are not followed. Small folders, with fewer than about a thousand
+    /// entries, are removed on the calling thread as the spawning does not pay
+    /// off, and so is whatever remains after a failure. The failures are
+    /// reported as with [`try_remove`](Folder::try_remove).
+    pub fn remove_parallel(self, threads: usize) -> std::result::Result<(), RemovalReport> {
+        let threads = match threads {
+            0 => std::thread::available_parallelism().map_or(1, |value| value.get()),
+            threads => threads,
+        };
+        self.remove_reporting(|path, policy| crate::remove::parallel(path, policy, threads))
+    }
+
+    fn remove_reporting<F>(mut self, remove: F) -> std::result::Result<(), RemovalReport>
+    where
+        F: FnOnce(&Path, &crate::remove::Policy) -> RemovalReport,
+    {
+        let mut report = None;
+        let result = self.cleanup_with(|path, policy| {
+            let outcome = remove(path, policy);
+            match outcome.failed.first() {
+                Some((_, error)) => {
+                    let error = Error::new(error.kind(), outcome.to_string());
+                    report = Some(outcome);
+                    Err(error)
+                }
+                None => Ok(()),
+            }
+        });
+        *self.removed.get_mut() = true;
+        match (result, report) {
+            (Ok(_), _) => Ok(()),
+            (Err(_), Some(report)) => Err(report),
+            (Err(error), None) => Err(RemovalReport {
+                failed: vec![(self.path.clone(), error)],
+                removed: 0,
+                remains: fs::symlink_metadata(&self.path).is_ok(),
+            }),
+        }
+    }
+
+    /// Overwrite the content of the files in the folder with zeros and remove
+    /// the folder.
+    ///
+    /// Regular files are overwritten in chunks, synchronized with the disk,
+    /// and truncated before being removed, and symbolic links are not
+    /// followed. A failure to overwrite a file does not stop the removal of
+    /// the rest, and the error lists all such files. It is a best effort:
+    /// solid-state drives remap writes, and copy-on-write or journaling file
+    /// systems and snapshots might keep the original content elsewhere, so
+    /// the content cannot be guaranteed to be unrecoverable.
+    #[inline]
+    pub fn shred_and_remove(mut self) -> std::result::Result<(), RemoveError> {
+        self.options.removal.shred = true;
+        self.remove()
+    }
+
+    /// Remove the folder.
+    ///
+    /// It is the same as [`remove`](Folder::remove) and is the recommended way
+    /// of disposing folders in asynchronous code, where dropping a folder
+    /// removes it on the current thread unless the `async` feature is enabled.
+    #[inline]
+    pub fn close(self) -> std::result::Result<(), RemoveError> {
+        self.remove()
+    }
+
+    /// Remove the folder in a separate thread.
+    ///
+    /// It is useful for large folders whose removal would otherwise block the
+    /// calling thread. The removal is subject to the same conditions as the
+    /// one of [`remove`](Folder::remove).
+    #[inline]
+    pub fn remove_in_background(self) -> BackgroundRemoval {
+        BackgroundRemoval::spawn(self)
+    }
+
+    /// Remove the folder within a deadline.
+    ///
+    /// The removal runs on a separate thread, which does not keep the process
+    /// alive, and the call returns once it finishes or `timeout` passes,
+    /// whichever comes first. In the latter case, [`RemovalOutcome::TimedOut`]
+    /// is returned, and the removal is left to the thread, which retries it
+    /// once in case of failure, as the object would when going out of scope.
+    /// If the process exits in the meantime, the folder is removed by the exit
+    /// handler provided that it is registered for exit, see
+    /// [`Builder::register_for_exit`], and it can be removed later on by
+    /// [`Sweep`](crate::Sweep) otherwise, which recognizes partially removed
+    /// folders by their names.
+    ///
+    /// In case of failure within the deadline, the error is returned, and the
+    /// folder is not removed again but is left to the exit handler or
+    /// [`Sweep`](crate::Sweep) in the same way.
+    pub fn remove_with_timeout(self, timeout: std::time::Duration) -> Result<RemovalOutcome> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let folder = std::sync::Arc::new(self);
+        let other = folder.clone();
+        std::thread::Builder::new()
+            .name("temporary".into())
+            .spawn(move || {
+                let _ = sender.send(other.cleanup());
+            })?;
+        match receiver.recv_timeout(timeout) {
+            Ok(Ok(_)) => Ok(RemovalOutcome::Completed),
+            Ok(Err(error)) => {
+                folder.keep_shared();
+                if folder.options.exit {
+                    crate::exit::register(&folder.path, folder.options.expected);
+                }
+                Err(error)
+            }
+            // The thread holds the last reference once this one goes away.
+            Err(_) => Ok(RemovalOutcome::TimedOut {
+                path: folder.path.clone(),
+            }),
+        }
+    }
+
+    /// Remove the folder without consuming the object.
+    ///
+    /// Subsequent calls and the eventual drop do nothing once the removal
+    /// succeeds. In case of failure, the removal is attempted again when the
+    /// object goes out of scope.
+    #[inline]
+    pub fn remove_in_place(&mut self) -> Result<()> {
+        self.cleanup()
+    }
+
+    fn context(&self, error: Error) -> Error {
+        Error::new(error.kind(), format!("{}: {}", self.path.display(), error))
+    }
+
+    #[cfg(feature = "async")]
+    #[inline]
+    pub(crate) fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Move the responsibility of removing the folder to another object.
+    #[cfg(feature = "async")]
+    pub(crate) fn disarm(&mut self) -> Folder {
+        let folder = Folder {
+            path: std::mem::take(&mut self.path),
+            removed: AtomicBool::new(*self.removed.get_mut()),
+            kept: AtomicBool::new(*self.kept.get_mut()),
+            options: Box::new(Options {
+                keep_on_panic: false,
+                panic_on_blocking_drop: false,
+                ..*std::mem::take(&mut self.options)
+            }),
+        };
+        *self.removed.get_mut() = true;
+        folder
+    }
+
+    /// Set or clear the removal of the folder by the system when the handle
+    /// opened via [`Builder::delete_on_close`] is closed.
+    #[cfg(windows)]
+    fn delete_on_close(&self, value: bool) {
+        let handle = self.options.handle.lock();
+        if let Some(handle) = &*handle.unwrap_or_else(|error| error.into_inner()) {
+            if let Err(error) = handle.set(value) {
+                crate::hook::report(&self.path, &error);
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[inline]
+    fn delete_on_close(&self, _: bool) {}
+
+    /// Prevent the folder from being removed, which is what
+    /// [`keep`](Folder::keep) does, via a shared reference.
+    pub(crate) fn keep_shared(&self) {
+        self.delete_on_close(false);
+        self.kept.store(true, Ordering::Release);
+        let registration = self.options.registration.lock();
+        *registration.unwrap_or_else(|error| error.into_inner()) = None;
+        if let Some(session) = &self.options.session {
+            session.leave();
+        }
+        self.track(crate::leak::LeakStatus::Kept);
+    }
+
+    #[inline]
+    fn track(&self, status: crate::leak::LeakStatus) {
+        if let Some(leak) = &self.options.leak {
+            leak.set(status);
+        }
+    }
+
+    #[inline]
+    fn untrack(&self) {
+        if let Some(leak) = &self.options.leak {
+            leak.forget();
+        }
+    }
+
+    fn thaw_shared(&self) -> Result<()> {
+        let frozen = self.options.frozen.lock();
+        match frozen.unwrap_or_else(|error| error.into_inner()).take() {
+            Some(entries) => thaw(&entries),
+            _ => Ok(()),
+        }
+    }
+
+    fn preserve(&self) -> std::sync::MutexGuard<'_, Option<PathBuf>> {
+        let preserve = self.options.preserve.lock();
+        preserve.unwrap_or_else(|error| error.into_inner())
+    }
+
+    #[inline]
+    pub(crate) fn cleanup(&self) -> Result<()> {
+        self.cleanup_with(crate::remove::folder)
+    }
+
+    fn cleanup_with<F>(&self, remove: F) -> Result<()>
+    where
+        F: FnOnce(&Path, &crate::remove::Policy) -> Result<()>,
+    {
+        if self.kept.load(Ordering::Acquire) || self.is_removed() {
+            return Ok(());
+        }
+        if self
+            .options
+            .session
+            .as_ref()
+            .is_some_and(|session| session.kept())
+        {
+            self.delete_on_close(false);
+            self.track(crate::leak::LeakStatus::Kept);
+            return Ok(());
+        }
+        if crate::env::keep() {
+            eprintln!("keeping the temporary folder {}", self.path.display());
+            self.delete_on_close(false);
+            self.kept.store(true, Ordering::Release);
+            self.track(crate::leak::LeakStatus::Kept);
+            return Ok(());
+        }
+        // The removal is claimed up front so that it happens at most once, and
+        // the claim is released in case of failure for the removal to be
+        // retried.
+        if self.removed.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        match self.options.expected.verify(&self.path) {
+            Ok(Ok(_)) => {}
+            // The claim is kept for the path not to be removed later either.
+            Ok(Err(error)) => return Err(error),
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                self.untrack();
+                return Ok(());
+            }
+            // The check can be retried.
+            Err(error) => {
+                self.removed.store(false, Ordering::Release);
+                return Err(error);
+            }
+        }
+        // Failures to restore permissions surface as failures to remove.
+        let _ = self.thaw_shared();
+        let preserved = match self.preserve().take() {
+            Some(target) => {
+                self.delete_on_close(false);
+                preserve(&self.path, &target)
+            }
+            None => Ok(()),
+        };
+        let timer = crate::trace::Timer::start();
+        let result = match &self.options.filesystem {
+            Some(filesystem) => filesystem.remove_dir_all(&self.path),
+            _ => remove(&self.path, &self.options.removal),
+        };
+        match result {
+            Ok(_) => {
+                // The folder is gone for good only once the handle is closed.
+                #[cfg(windows)]
+                if let Ok(mut handle) = self.options.handle.lock() {
+                    handle.take();
+                }
+                self.untrack();
+                crate::trace::removed("folder", &self.path, timer)
+            }
+            // The folder has been removed externally, which is the goal anyway.
+            Err(error) if error.kind() == ErrorKind::NotFound => self.untrack(),
+            Err(error) => {
+                self.removed.store(false, Ordering::Release);
+                return Err(error);
+            }
+        }
+        if self.options.sync {
+            self.sync_parent()?;
+        }
+        preserved
+    }
+}
+
+/// Run a closure with a temporary folder and remove the folder afterwards.
+///
+/// The folder will have a name starting from `prefix`. It is removed once the
+/// closure returns or panics, and a failure to remove it is reported unless
+/// the closure fails.
+///
+/// ```rust
+/// let count = temporary::with_folder("foo", |path| {
+///     std::fs::write(path.join("foo.txt"), "Hi there!")?;
+///     Ok(std::fs::read_dir(path)?.count())
+/// })
+/// .unwrap();
+/// assert_eq!(count, 1);
+/// ```
+pub fn with_folder<T, U, F>(prefix: T, f: F) -> Result<U>
+where
+    T: AsRef<OsStr>,
+    F: FnOnce(&Path) -> Result<U>,
+{
+    let folder = Folder::new(prefix)?;
+    let value = f(&folder.path)?;
+    folder.remove()?;
+    Ok(value)
+}
+
+/// An error of renaming a temporary folder.
+///
+/// The folder is handed back and is removed when it goes out of scope unless it
+/// is renamed again or disposed otherwise.
+#[derive(Debug)]
+pub struct RenameError {
+    /// The underlying error with the path the folder failed to be renamed
+    /// into.
+    pub error: crate::Error,
+    /// The folder that failed to be renamed.
+    pub folder: Folder,
+}
+
+impl RenameError {
+    /// Return the path the folder failed to be renamed into.
+    #[inline]
+    pub fn target(&self) -> &Path {
+        self.error.path()
+    }
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "failed to rename {} into {}: {}",
+            self.folder.path.display(),
+            self.target().display(),
+            self.error.io(),
+        )
+    }
+}
+
+impl std::error::Error for RenameError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<RenameError> for Error {
+    #[inline]
+    fn from(error: RenameError) -> Error {
+        Error::new(error.error.kind(), error.to_string())
+    }
+}
+
+impl From<RenameError> for crate::Error {
+    #[inline]
+    fn from(error: RenameError) -> crate::Error {
+        error.error
+    }
+}
+
+/// An error of removing a temporary folder.
+///
+/// The folder is handed back and is removed when it goes out of scope unless it
+/// is removed again or disposed otherwise.
+#[derive(Debug)]
+pub struct RemoveError {
+    /// The underlying error with the path to the folder.
+    pub error: crate::Error,
+    /// The folder that failed to be removed.
+    pub folder: Folder,
+}
+
+impl fmt::Display for RemoveError {
+    #[inline]
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.error.io().fmt(formatter)
+    }
+}
+
+impl std::error::Error for RemoveError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<RemoveError> for Error {
+    #[inline]
+    fn from(error: RemoveError) -> Error {
+        Error::new(error.error.kind(), error.to_string())
+    }
+}
+
+impl From<RemoveError> for crate::Error {
+    #[inline]
+    fn from(error: RemoveError) -> crate::Error {
+        error.error
+    }
+}
+
+fn check_parent(target: &Path, filesystem: Option<&dyn Filesystem>) -> Result<()> {
+    let is_dir = |parent: &Path| match filesystem {
+        Some(filesystem) => filesystem
+            .metadata(parent)
+            .is_ok_and(|metadata| metadata.is_dir()),
+        _ => parent.is_dir(),
+    };
+    match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !is_dir(parent) => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("the parent folder {} does not exist", parent.display()),
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "libc"))]
+pub(crate) fn rename_noreplace(source: &Path, target: &Path) -> Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let (from, to) = (
+        CString::new(source.as_os_str().as_bytes())?,
+        CString::new(target.as_os_str().as_bytes())?,
+    );
+    let result = crate::interrupt::retry(|| {
+        let code = unsafe {
+            libc::renameat2(
+                libc::AT_FDCWD,
+                from.as_ptr(),
+                libc::AT_FDCWD,
+                to.as_ptr(),
+                libc::RENAME_NOREPLACE,
+            )
+        };
+        if code == 0 {
+            Ok(())
+        } else {
+            Err(Error::last_os_error())
+        }
+    });
+    let Err(error) = result else {
+        return Ok(());
+    };
+    match error.raw_os_error() {
+        // The kernel or the file system does not support the flag.
+        Some(libc::ENOSYS | libc::EINVAL) => rename_checked(source, target),
+        _ => Err(error),
+    }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "libc")))]
+#[inline]
+pub(crate) fn rename_noreplace(source: &Path, target: &Path) -> Result<()> {
+    rename_checked(source, target)
+}
+
+fn rename_checked(source: &Path, target: &Path) -> Result<()> {
+    if fs::symlink_metadata(target).is_ok() {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+    crate::interrupt::retry(|| fs::rename(source, target))
+}
+
+fn moving(source: &Path, target: &Path, error: Error) -> Error {
+    Error::new(
+        error.kind(),
+        format!(
+            "failed to move {} to {}: {}",
+            source.display(),
+            target.display(),
+            error,
+        ),
+    )
+}
+
+fn move_file(source: &Path, target: &Path) -> Result<()> {
+    let reading = |error: Error| {
+        Error::new(
+            error.kind(),
+            format!("failed to read {}: {}", source.display(), error),
+        )
+    };
+    let writing = |error: Error| {
+        Error::new(
+            error.kind(),
+            format!("failed to write {}: {}", target.display(), error),
+        )
+    };
+    let mut input = fs::File::open(source).map_err(reading)?;
+    let permissions = input.metadata().map_err(reading)?.permissions();
+    let mut output = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)
+        .map_err(writing)?;
+    let result = std::io::copy(&mut input, &mut output)
+        .map_err(|error| match error.kind() {
+            // The standard library cannot tell which side failed.
+            ErrorKind::StorageFull | ErrorKind::QuotaExceeded => writing(error),
+            _ => moving(source, target, error),
+        })
+        .and_then(|_| output.set_permissions(permissions).map_err(writing))
+        .and_then(|_| output.sync_all().map_err(writing))
+        .and_then(|_| {
+            crate::interrupt::retry(|| fs::remove_file(source)).map_err(|error| {
+                Error::new(
+                    error.kind(),
+                    format!("failed to remove {}: {}", source.display(), error),
+                )
+            })
+        });
+    if result.is_err() {
+        drop(output);
+        let _ = fs::remove_file(target);
+    }
+    result
+}
+
+fn move_dir(source: &Path, target: &Path) -> Result<()> {
+    let copied = crate::interrupt::retry(|| fs::create_dir(target))
+        .map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!("failed to create {}: {}", target.display(), error),
+            )
+        })
+        .and_then(|_| {
+            // The errors name the entries that failed to be copied.
+            crate::copy::content(source, target)?;
+            let permissions = fs::symlink_metadata(source)
+                .map_err(|error| moving(source, target, error))?
+                .permissions();
+            fs::set_permissions(target, permissions).map_err(|error| moving(source, target, error))
+        });
+    if let Err(error) = copied {
+        if error.kind() != ErrorKind::AlreadyExists {
+            let _ = crate::remove::folder(target, &crate::remove::Policy::default());
+        }
+        return Err(error);
+    }
+    crate::remove::folder(source, &crate::remove::Policy::default()).map_err(|error| {
+        Error::new(
+            error.kind(),
+            format!(
+                "failed to remove {} after copying it to {}: {}",
+                source.display(),
+                target.display(),
+                error,
+            ),
+        )
+    })
+}
+
+fn thaw(entries: &[(PathBuf, fs::Permissions)]) -> Result<()> {
+    let mut result = Ok(());
+    for (path, permissions) in entries.iter().rev() {
+        match fs::set_permissions(path, permissions.clone()) {
+            Err(error) if error.kind() != ErrorKind::NotFound && result.is_ok() => {
+                result = Err(Error::new(
+                    error.kind(),
+                    format!("failed to thaw {}: {}", path.display(), error),
+                ));
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+fn preserve(source: &Path, target: &Path) -> Result<()> {
+    let context = |error: Error| {
+        Error::new(
+            error.kind(),
+            format!(
+                "failed to preserve {} at {}: {}",
+                source.display(),
+                target.display(),
+                error,
+            ),
+        )
+    };
+    if let Some(parent) = target.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent).map_err(context)?;
+        }
+    }
+    // Creating the folder first reserves the name.
+    let mut path = target.to_path_buf();
+    let mut index = 0;
+    loop {
+        match crate::interrupt::retry(|| fs::create_dir(&path)) {
+            Ok(_) => break,
+            Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+                index += 1;
+                let mut name = target.as_os_str().to_os_string();
+                name.push(format!(".{}", index));
+                path = name.into();
+            }
+            Err(error) => return Err(context(error)),
+        }
+    }
+    match rename_overwrite(source, &path) {
+        Err(error) if error.kind() == ErrorKind::CrossesDevices => {
+            crate::copy::content(source, &path).map_err(context)
+        }
+        result => result.map_err(context),
+    }
+}
+
+fn rename_overwrite_in(filesystem: &dyn Filesystem, source: &Path, target: &Path) -> Result<()> {
+    match filesystem.read_dir(target) {
+        Ok(entries) if entries.is_empty() => filesystem.remove_dir_all(target)?,
+        Ok(_) => return Err(Error::from(ErrorKind::DirectoryNotEmpty)),
+        Err(error) if error.kind() == ErrorKind::NotFound => {}
+        Err(error) => return Err(error),
+    }
+    filesystem.rename(source, target)
+}
+
+fn rename_overwrite(source: &Path, target: &Path) -> Result<()> {
+    // On Windows, existing folders are not replaced by renaming.
+    #[cfg(windows)]
+    match fs::remove_dir(target) {
+        Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+        _ => {}
+    }
+    crate::interrupt::retry(|| fs::rename(source, target))
+}
+
+/// A temporary folder inside another temporary folder.
+#[derive(Debug)]
+pub struct ChildFolder<'l> {
+    folder: Folder,
+    phantom: PhantomData<&'l Folder>,
+}
+
+impl ChildFolder<'_> {
+    /// Remove the folder.
+    #[inline]
+    pub fn remove(self) -> Result<()> {
+        Ok(self.folder.remove()?)
+    }
+}
+
+impl AsRef<Path> for ChildFolder<'_> {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        &self.folder.path
+    }
+}
+
+impl Deref for ChildFolder<'_> {
+    type Target = Folder;
+
+    #[inline]
+    fn deref(&self) -> &Folder {
+        &self.folder
+    }
+}
+
+impl AsRef<Path> for Folder {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl TryFrom<PathBuf> for Folder {
+    type Error = crate::Error;
+
+    /// Take over the responsibility of removing an existing folder.
+    ///
+    /// It is [`Folder::adopt`].
+    #[inline]
+    fn try_from(path: PathBuf) -> crate::Result<Folder> {
+        Folder::adopt(path)
+    }
+}
+
+impl fmt::Debug for Folder {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.path.fmt(formatter)
+    }
+}
+
+impl Deref for Folder {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Folder {
+    #[inline]
+    fn drop(&mut self) {
+        let disposed = *self.removed.get_mut() || *self.kept.get_mut();
+        #[cfg(feature = "archive")]
+        if let Some(target) = self.options.archive.take() {
+            if !disposed && std::thread::panicking() {
+                if let Err(error) = self.archive_to(&target) {
+                    crate::hook::report(&self.path, &error);
+                }
+            }
+        }
+        if self.options.keep_on_panic && !disposed && std::thread::panicking() {
+            self.delete_on_close(false);
+            self.track(crate::leak::LeakStatus::Kept);
+            eprintln!(
+                "keeping the temporary folder {} with prefix {:?}",
+                self.path.display(),
+                self.options.prefix,
+            );
+            return;
+        }
+        #[cfg(feature = "async")]
+        if !disposed && crate::asynchronous::offload(self) {
+            return;
+        }
+        if let Err(error) = self.cleanup() {
+            crate::hook::report(&self.path, &error);
+            self.track(crate::leak::LeakStatus::Leaked);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Folder;
+    use std::path::Path;
+
+    #[test]
+    fn new() {
+        use std::fs;
+
+        let path = {
+            let folder = Folder::new("foo").unwrap();
+            assert!(fs::metadata(folder.path()).is_ok());
+            folder.path().to_path_buf()
+        };
+        assert!(fs::metadata(path).is_err());
+    }
+
+    #[test]
+    fn named() {
+        use std::fs;
+        use std::io::ErrorKind;
+
+        let parent = Folder::new("foo").unwrap();
+        let folder = Folder::named(&parent, "workdir").unwrap();
+        assert_eq!(folder.path(), parent.join("workdir"));
+        let error = Folder::named(&parent, "workdir").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+        assert!(folder.exists());
+        for name in ["", ".", "..", "bar/baz"] {
+            let error = Folder::named(&parent, name).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        }
+        folder.write("bar.txt", "Hi there!").unwrap();
+        drop(folder);
+        assert!(parent.is_empty().unwrap());
+
+        let mut folder = Folder::named(&parent, "workdir").unwrap();
+        folder.keep();
+        let path = folder.into_path();
+        assert!(path.is_dir());
+        fs::remove_dir(path).unwrap();
+
+        let _lock = crate::cwd::LOCK
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let folder = {
+            let _guard = parent.enter().unwrap();
+            Folder::named(".", "workdir").unwrap()
+        };
+        assert!(folder.path().is_absolute());
+        let canonical = fs::canonicalize(folder.parent().unwrap()).unwrap();
+        assert_eq!(canonical, fs::canonicalize(&parent).unwrap());
+        drop(folder);
+        assert!(parent.is_empty().unwrap());
+    }
+
+    #[test]
+    fn adopt() {
+        use std::fs;
+        use std::io::ErrorKind;
+
+        let parent = Folder::new("foo").unwrap();
+        let error = Folder::adopt(parent.join("bar")).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        parent.write("bar.txt", "Hi there!").unwrap();
+        let error = Folder::adopt(parent.join("bar.txt")).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotADirectory);
+
+        fs::create_dir(parent.join("bar")).unwrap();
+        parent.write("bar/baz.txt", "Bye!").unwrap();
+        let folder = Folder::try_from(parent.join("bar")).unwrap();
+        assert_eq!(folder.read_to_string("baz.txt").unwrap(), "Bye!");
+        drop(folder);
+        assert!(!parent.join("bar").exists());
+
+        #[cfg(unix)]
+        {
+            fs::create_dir(parent.join("baz")).unwrap();
+            std::os::unix::fs::symlink("baz", parent.join("qux")).unwrap();
+            let error = Folder::adopt(parent.join("qux")).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::NotADirectory);
+            let folder = Folder::adopt_symlink_target(parent.join("qux")).unwrap();
+            assert_eq!(folder.path(), fs::canonicalize(parent.join("baz")).unwrap());
+            drop(folder);
+            assert!(!parent.join("baz").exists());
+            assert!(fs::symlink_metadata(parent.join("qux")).is_ok());
+        }
+    }
+
+    #[test]
+    fn beside() {
+        let _lock = crate::cwd::LOCK
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let folder = Folder::new("foo").unwrap();
+        let other = Folder::beside(folder.join("bar"), "bar").unwrap();
+        assert_eq!(other.parent(), Some(folder.path()));
+        let other = Folder::beside(other.join(".."), "bar").unwrap();
+        assert_eq!(other.parent(), folder.parent());
+        {
+            let _guard = folder.enter().unwrap();
+            let other = Folder::beside("bar", "bar").unwrap();
+            assert_eq!(
+                other.parent(),
+                Some(std::env::current_dir().unwrap().as_path())
+            );
+        }
+        #[cfg(unix)]
+        assert!(Folder::beside("/", "bar").is_err());
+    }
+
+    #[test]
+    fn child() {
+        let folder = Folder::new("foo").unwrap();
+        let one = folder.child("bar").unwrap();
+        let other = one.child("baz").unwrap();
+        assert_eq!(one.parent(), Some(folder.path()));
+        assert_eq!(other.parent(), Some(one.path()));
+        let path = other.to_path_buf();
+        drop(other);
+        assert!(!path.exists() && one.exists());
+        let path = one.to_path_buf();
+        std::fs::create_dir(one.join("baz")).unwrap();
+        drop(one);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn duplicate() {
+        use std::fs;
+
+        let folder = Folder::new("foo").unwrap();
+        fs::create_dir(folder.join("bar")).unwrap();
+        fs::write(folder.join("bar").join("baz.txt"), "Hi there!").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("bar", folder.join("baz")).unwrap();
+        let other = folder.duplicate().unwrap();
+        assert_ne!(other.path(), folder.path());
+        assert_eq!(other.parent(), folder.parent());
+        assert!(other
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("foo."));
+        fs::write(other.join("bar").join("baz.txt"), "Bye!").unwrap();
+        let content = fs::read_to_string(folder.join("bar").join("baz.txt")).unwrap();
+        assert_eq!(content, "Hi there!");
+        #[cfg(unix)]
+        assert_eq!(fs::read_link(other.join("baz")).unwrap(), Path::new("bar"));
+        let path = other.to_path_buf();
+        drop(other);
+        assert!(!path.exists() && folder.exists());
+    }
+
+    #[test]
+    fn children() {
+        use std::io::ErrorKind;
+
+        let folder = Folder::new("foo").unwrap();
+        let paths = folder.children(3).unwrap();
+        assert_eq!(
+            paths,
+            [folder.join("0"), folder.join("1"), folder.join("2")]
+        );
+        assert!(paths.iter().all(|path| path.is_dir()));
+
+        std::fs::create_dir(folder.join("shard-0002")).unwrap();
+        let error = folder
+            .children_with(4, |index| format!("shard-{:04}", index))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+        assert!(error.to_string().contains("child 2"));
+        assert!(!folder.join("shard-0000").exists());
+        assert!(folder.join("shard-0002").exists());
+    }
+
+    #[test]
+    fn with_folder() {
+        use std::io::{Error, ErrorKind};
+
+        let mut path = None;
+        let error = super::with_folder("foo", |folder| {
+            path = Some(folder.to_path_buf());
+            Err::<(), _>(Error::from(ErrorKind::Other))
+        })
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Other);
+        assert!(!path.unwrap().exists());
+
+        let mut path = None;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            super::with_folder("foo", |folder| -> std::io::Result<()> {
+                path = Some(folder.to_path_buf());
+                panic!("Oh no!");
+            })
+        }));
+        assert!(result.is_err());
+        assert!(!path.unwrap().exists());
+
+        assert!(super::with_folder("foo", |folder| std::fs::remove_dir(folder)).is_ok());
+    }
+
+    #[test]
+    fn entries() {
+        use std::fs;
+
+        let folder = Folder::new("foo").unwrap();
+        assert!(folder.is_empty().unwrap());
+        assert!(folder.metadata().unwrap().is_dir());
+        fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+        assert!(!folder.is_empty().unwrap());
+        let names = folder
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["foo.txt"]);
+        fs::remove_dir_all(&folder).unwrap();
+        let error = folder.metadata().unwrap_err();
+        assert!(error.to_string().contains(folder.to_str().unwrap()));
+    }
+
+    #[test]
+    fn file() {
+        use std::io::Write;
+
+        let folder = Folder::new("foo").unwrap();
+        let mut file = folder.file("foo").unwrap();
+        file.write_all(b"Hi there!").unwrap();
+        assert!(file.is_owned());
+        assert_eq!(file.parent(), Some(folder.path()));
+        let path = file.to_path_buf();
+        drop(file);
+        assert!(path.exists());
+
+        let file = folder.named_file("bar.txt").unwrap();
+        assert_eq!(file.path(), folder.join("bar.txt"));
+        assert!(folder.named_file("bar.txt").is_err());
+        assert!(folder.named_file("../bar.txt").is_err());
+        drop(file);
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn clear() {
+        use std::fs;
+
+        let folder = Folder::new("foo").unwrap();
+        folder.clear().unwrap();
+        let other = Folder::new("bar").unwrap();
+        fs::write(other.join("bar.txt"), "Hi there!").unwrap();
+        fs::create_dir_all(folder.join("foo").join("bar")).unwrap();
+        fs::write(folder.join("foo").join("foo.txt"), "Hi there!").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(other.path(), folder.join("bar")).unwrap();
+        folder.clear().unwrap();
+        assert!(folder.is_dir());
+        assert_eq!(fs::read_dir(&folder).unwrap().count(), 0);
+        assert!(other.join("bar.txt").exists());
+
+        let folder = crate::Builder::new().shred(true).folder().unwrap();
+        let links = Folder::new("baz").unwrap();
+        fs::create_dir(folder.join("foo")).unwrap();
+        for path in [folder.join("foo.txt"), folder.join("foo").join("bar.txt")] {
+            fs::write(&path, "Hi there!").unwrap();
+            fs::hard_link(&path, links.join(path.file_name().unwrap())).unwrap();
+        }
+        folder.clear().unwrap();
+        assert_eq!(fs::read_dir(&folder).unwrap().count(), 0);
+        assert_eq!(fs::metadata(links.join("foo.txt")).unwrap().len(), 0);
+        assert_eq!(fs::metadata(links.join("bar.txt")).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn preserve_to() {
+        use std::fs;
+
+        let root = Folder::new("foo").unwrap();
+        let target = root.join("bar").join("baz");
+        for _ in 0..2 {
+            let mut folder = Folder::new("foo").unwrap();
+            fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+            folder.preserve_to(&target);
+            let path = folder.to_path_buf();
+            drop(folder);
+            assert!(!path.exists());
+        }
+        assert!(target.join("foo.txt").is_file());
+        assert!(root.join("bar").join("baz.1").join("foo.txt").is_file());
+
+        let mut folder = Folder::new("foo").unwrap();
+        folder.preserve_to(&target);
+        folder.clear_preserve();
+        folder.remove().unwrap();
+        assert!(!root.join("bar").join("baz.2").exists());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn delete_on_close() {
+        use crate::Builder;
+
+        let root = Folder::new("foo").unwrap();
+        let mut builder = Builder::new();
+        builder.parent(&root).prefix("bar").delete_on_close(true);
+
+        let folder = builder.folder().unwrap();
+        folder.write("bar/baz.txt", "Hi there!").unwrap();
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+
+        let mut folder = builder.folder().unwrap();
+        folder.keep();
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(path.exists());
+
+        let path = builder.folder().unwrap().into_path();
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn persist_if() {
+        let root = Folder::new("foo").unwrap();
+        let folder = Folder::with_parent(&root, "bar").unwrap();
+        let (value, path) = folder
+            .persist_if(|path| std::fs::write(path.join("bar.txt"), "Hi there!").map(|_| 42))
+            .unwrap();
+        assert_eq!(value, 42);
+        assert!(path.join("bar.txt").is_file());
+
+        let folder = Folder::with_parent(&root, "bar").unwrap();
+        let path = folder.to_path_buf();
+        assert_eq!(folder.persist_if(|_| Err::<(), _>(42)).unwrap_err(), 42);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn keep_on_panic() {
+        let mut paths = Vec::new();
+        for (prefix, value) in [("foo", false), ("bar", true)] {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let mut folder = crate::Builder::new()
+                    .prefix(prefix)
+                    .keep_on_panic(value)
+                    .folder()
+                    .unwrap();
+                folder.keep_on_panic(value);
+                paths.push(folder.to_path_buf());
+                panic!("Oh no!");
+            }));
+            assert!(result.is_err());
+        }
+        assert!(!paths[0].exists());
+        assert!(paths[1].exists());
+        std::fs::remove_dir(&paths[1]).unwrap();
+
+        let mut folder = Folder::new("foo").unwrap();
+        folder.keep_on_panic(true);
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn keep() {
+        let mut folder = Folder::new("foo").unwrap();
+        folder.keep();
+        folder.keep();
+        let path = folder.path().to_path_buf();
+        drop(folder);
+        assert!(path.exists());
+
+        let mut folder = Folder::with_parent(&path, "foo").unwrap();
+        folder.keep();
+        folder.unkeep();
+        let other = folder.path().to_path_buf();
+        drop(folder);
+        assert!(!other.exists());
+        std::fs::remove_dir(path).unwrap();
+    }
+
+    #[test]
+    fn rename_into() {
+        use std::fs;
+        use std::io::ErrorKind;
+
+        let root = Folder::new("foo").unwrap();
+        let folder = Folder::with_parent(&root, "bar").unwrap();
+        fs::write(folder.join("bar.txt"), "Hi there!").unwrap();
+        let error = folder
+            .rename_into(root.join("baz").join("qux"))
+            .unwrap_err();
+        assert_eq!(error.error.kind(), ErrorKind::NotFound);
+        fs::create_dir(root.join("baz")).unwrap();
+        let error = error.folder.rename_into(root.join("baz")).unwrap_err();
+        assert_eq!(error.error.kind(), ErrorKind::AlreadyExists);
+        assert!(error.to_string().contains("baz"));
+        let path = error
+            .folder
+            .rename_into_overwrite(root.join("baz"))
+            .unwrap();
+        assert!(path.join("bar.txt").exists());
+
+        let folder = Folder::with_parent(&root, "bar").unwrap();
+        let path = folder.rename_into(root.join("qux")).unwrap();
+        assert!(path.is_dir());
+    }
+
+    #[test]
+    fn revalidate() {
+        use std::fs;
+
+        let folder = Folder::new("foo").unwrap();
+        assert!(folder.exists() && !folder.is_removed());
+        folder.revalidate().unwrap();
+        fs::remove_dir(&folder).unwrap();
+        assert!(!folder.exists());
+        assert!(folder.revalidate().is_err());
+        fs::write(&folder, "Hi there!").unwrap();
+        assert!(folder.revalidate().is_err());
+        fs::remove_file(&folder).unwrap();
+        folder.remove().unwrap();
+    }
+
+    #[test]
+    fn leak() {
+        let path = Folder::new("foo").unwrap().leak();
+        assert!(path.is_dir());
+        std::fs::remove_dir(path).unwrap();
+    }
+
+    #[test]
+    fn remove_in_place() {
+        let mut folder = Folder::new("foo").unwrap();
+        folder.remove_in_place().unwrap();
+        assert!(!folder.exists());
+        std::fs::create_dir(&folder).unwrap();
+        folder.remove_in_place().unwrap();
+        let path = folder.path().to_path_buf();
+        drop(folder);
+        assert!(path.exists());
+        std::fs::remove_dir(path).unwrap();
+    }
+
+    #[test]
+    fn remove_missing() {
+        let folder = Folder::new("foo").unwrap();
+        std::fs::remove_dir(&folder).unwrap();
+        folder.remove().unwrap();
+
+        let mut folder = Folder::new("foo").unwrap();
+        std::fs::remove_dir(&folder).unwrap();
+        folder.remove_in_place().unwrap();
+        assert!(folder.is_removed());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remove_failed() {
+        use std::fs::{self, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        // Permissions do not apply to the superuser.
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+        let mut folder = Folder::new("foo").unwrap();
+        fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+        fs::set_permissions(&folder, Permissions::from_mode(0o500)).unwrap();
+        let error = folder.remove_in_place().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+        assert!(!folder.is_removed());
+        fs::set_permissions(&folder, Permissions::from_mode(0o700)).unwrap();
+        folder.remove_in_place().unwrap();
+        assert!(folder.is_removed() && !folder.exists());
+
+        let folder = Folder::new("foo").unwrap();
+        fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+        fs::set_permissions(&folder, Permissions::from_mode(0o500)).unwrap();
+        let error = folder.remove().unwrap_err();
+        assert_eq!(error.error.kind(), std::io::ErrorKind::PermissionDenied);
+        let folder = error.folder;
+        assert!(!folder.is_removed() && folder.exists());
+        let path = folder.to_path_buf();
+        folder.force_remove().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn readonly() {
+        use std::fs;
+
+        let folder = Folder::new("foo").unwrap();
+        fs::create_dir(folder.join("bar")).unwrap();
+        for path in [folder.join("foo.txt"), folder.join("bar").join("bar.txt")] {
+            fs::write(&path, "Hi there!").unwrap();
+            let mut permissions = fs::metadata(&path).unwrap().permissions();
+            permissions.set_readonly(true);
+            fs::set_permissions(&path, permissions).unwrap();
+        }
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+
+        let folder = crate::Builder::new()
+            .prefix("foo")
+            .clear_readonly(false)
+            .removal_retry(std::time::Duration::ZERO)
+            .folder()
+            .unwrap();
+        let path = folder.join("foo.txt");
+        fs::write(&path, "Hi there!").unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions.clone()).unwrap();
+        let error = folder.remove().unwrap_err();
+        assert!(error.to_string().contains("foo.txt"));
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&path, permissions).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn move_file_in() {
+        use std::fs;
+        use std::io::ErrorKind;
+
+        let folder = Folder::new("foo").unwrap();
+        // The in-memory folder is usually on another file system.
+        for other in [
+            Folder::new("bar").unwrap(),
+            Folder::in_memory("bar").unwrap(),
+        ] {
+            let source = other.write("bar.txt", "Hi there!").unwrap();
+            let name = format!("{}.txt", other.file_name().unwrap().to_str().unwrap());
+            let path = folder.move_file_in(&source, &name).unwrap();
+            assert_eq!(path, folder.join(&name));
+            assert_eq!(fs::read_to_string(&path).unwrap(), "Hi there!");
+            assert!(!source.exists());
+
+            let source = other.write("bar.txt", "Bye!").unwrap();
+            let error = folder.move_file_in(&source, &name).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+            assert_eq!(fs::read_to_string(&path).unwrap(), "Hi there!");
+            assert!(source.exists());
+
+            other.write("baz/qux.txt", "Bye!").unwrap();
+            let name = other.file_name().unwrap().to_str().unwrap();
+            let path = folder.move_dir_in(other.join("baz"), name).unwrap();
+            assert_eq!(fs::read_to_string(path.join("qux.txt")).unwrap(), "Bye!");
+            assert!(!other.join("baz").exists());
+        }
+
+        let other = Folder::with_files("bar", &[("bar/baz.txt", b"")]).unwrap();
+        for name in ["", "..", "bar/baz.txt"] {
+            let error = folder
+                .move_file_in(other.join("bar/baz.txt"), name)
+                .unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        }
+        let error = folder.move_file_in(other.join("bar"), "bar").unwrap_err();
+        assert!(error.to_string().contains("which is a folder"));
+        let error = folder
+            .move_dir_in(other.join("bar/baz.txt"), "bar")
+            .unwrap_err();
+        assert!(error.to_string().contains("which is not a folder"));
+        let error = folder
+            .move_file_in(other.join("qux.txt"), "qux")
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(error.to_string().contains("qux.txt"));
+    }
+
+    #[test]
+    fn move_file() {
+        let (one, other) = (Folder::new("foo").unwrap(), Folder::new("bar").unwrap());
+        let source = one.write("foo.txt", "Hi there!").unwrap();
+        super::move_file(&source, &other.join("bar.txt")).unwrap();
+        assert_eq!(other.read_to_string("bar.txt").unwrap(), "Hi there!");
+        assert!(!source.exists());
+
+        one.write("bar/baz.txt", "Bye!").unwrap();
+        super::move_dir(&one.join("bar"), &other.join("baz")).unwrap();
+        assert_eq!(other.read_to_string("baz/baz.txt").unwrap(), "Bye!");
+        assert!(one.is_empty().unwrap());
+
+        // A copy that fails midway is removed.
+        let source = one.write("qux.txt", "Hi there!").unwrap();
+        let error = super::move_dir(&source, &other.join("qux")).unwrap_err();
+        assert!(error.to_string().contains("qux.txt"));
+        assert!(!other.join("qux").exists());
+        assert!(source.exists());
+    }
+
+    #[test]
+    fn freeze() {
+        use std::fs;
+
+        let mut folder = Folder::with_files("foo", &[("bar/baz.txt", b"Hi there!")]).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions();
+        let original = mode(&folder.join("bar").join("baz.txt"));
+        folder.freeze().unwrap();
+        folder.freeze().unwrap();
+        for path in [
+            folder.to_path_buf(),
+            folder.join("bar"),
+            folder.join("bar/baz.txt"),
+        ] {
+            assert!(mode(&path).readonly());
+        }
+        #[cfg(unix)]
+        let privileged = unsafe { libc::geteuid() } == 0;
+        #[cfg(not(unix))]
+        let privileged = false;
+        if !privileged {
+            assert!(folder.write("bar/baz.txt", "Bye!").is_err());
+            #[cfg(unix)]
+            assert!(folder.write("qux.txt", "Bye!").is_err());
+        }
+        folder.thaw().unwrap();
+        assert_eq!(mode(&folder.join("bar").join("baz.txt")), original);
+        folder.write("bar/baz.txt", "Bye!").unwrap();
+
+        folder.freeze().unwrap();
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn force_remove() {
+        use std::fs::{self, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = Folder::new("foo").unwrap();
+        fs::set_permissions(&root, Permissions::from_mode(0o500)).unwrap();
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.join("bar").join("baz");
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("foo.txt"), "Hi there!").unwrap();
+        std::os::unix::fs::symlink(root.path(), folder.join("qux")).unwrap();
+        fs::set_permissions(&path, Permissions::from_mode(0o000)).unwrap();
+        fs::set_permissions(folder.join("bar"), Permissions::from_mode(0o000)).unwrap();
+        let path = folder.to_path_buf();
+        folder.force_remove().unwrap();
+        assert!(!path.exists());
+        let mode = fs::metadata(&root).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o500);
+        fs::set_permissions(&root, Permissions::from_mode(0o700)).unwrap();
+    }
+
+    #[test]
+    fn creation_attempts() {
+        let root = Folder::new("foo").unwrap();
+        let mut builder = crate::Builder::new();
+        builder.parent(&root).prefix("bar").deterministic_seed(42);
+        let one = builder.folder().unwrap();
+        let other = builder.folder().unwrap();
+        assert_eq!(one.creation_attempts(), 1);
+        assert_eq!(other.creation_attempts(), 2);
+    }
+
+    #[test]
+    fn try_remove() {
+        use std::fs;
+
+        let folder = Folder::new("foo").unwrap();
+        fs::create_dir_all(folder.join("bar").join("baz")).unwrap();
+        fs::write(folder.join("bar").join("bar.txt"), "Hi there!").unwrap();
+        let path = folder.to_path_buf();
+        folder.try_remove().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[cfg(feature = "camino")]
+    #[test]
+    fn utf8_path() {
+        use crate::Builder;
+
+        let root = Folder::new("foo").unwrap();
+        let parent = root.utf8_path().unwrap().join("bar");
+        std::fs::create_dir(&parent).unwrap();
+        let folder = Builder::new()
+            .parent(&parent)
+            .prefix("baz")
+            .folder()
+            .unwrap();
+        assert!(folder.utf8_path().unwrap().starts_with(&parent));
+        let path = folder.into_utf8_path().unwrap();
+        assert!(path.is_dir());
+
+        #[cfg(unix)]
+        {
+            use std::ffi::OsStr;
+            use std::os::unix::ffi::OsStrExt;
+
+            let parent = root.join(OsStr::from_bytes(b"\xFF"));
+            std::fs::create_dir(&parent).unwrap();
+            let folder = Folder::with_parent(&parent, "baz").unwrap();
+            let error = folder.utf8_path().unwrap_err();
+            assert!(error.to_string().contains("parent"));
+            assert!(folder.into_utf8_path().is_err());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn owner() {
+        use std::ffi::CString;
+        use std::io::ErrorKind;
+        use std::os::unix::ffi::OsStrExt;
+
+        use crate::Builder;
+
+        // Only the superuser can give folders away.
+        if unsafe { libc::geteuid() } != 0 {
+            return;
+        }
+        let chown = |path: &Path, owner: libc::uid_t| {
+            let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+            assert_eq!(unsafe { libc::chown(path.as_ptr(), owner, owner) }, 0);
+        };
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.to_path_buf();
+        chown(&path, 65534);
+        let error = folder.remove().unwrap_err();
+        assert_eq!(error.error.kind(), ErrorKind::PermissionDenied);
+        assert!(error.to_string().contains("owned by user 65534"));
+        assert!(path.exists());
+        std::fs::remove_dir(&path).unwrap();
+
+        let folder = Builder::new()
+            .prefix("foo")
+            .verify_owner(false)
+            .folder()
+            .unwrap();
+        chown(folder.path(), 65534);
+        folder.remove().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let previous = unsafe { libc::umask(0) };
+        let folder = Folder::new("foo");
+        unsafe { libc::umask(previous) };
+        let mode = folder.unwrap().metadata().unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o700);
+    }
+
+    #[test]
+    fn verbatim() {
+        use std::fs;
+
+        use crate::Builder;
+
+        let folder = Builder::new()
+            .prefix("foo")
+            .verbatim(true)
+            .folder()
+            .unwrap();
+        let mut path = folder.to_path_buf();
+        for _ in 0..8 {
+            path.push("a".repeat(40));
+        }
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("foo.txt"), "Hi there!").unwrap();
+        assert!(path.as_os_str().len() > 300);
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(fs::metadata(path).is_err());
+    }
+
+    #[cfg(all(unix, feature = "libc"))]
+    #[test]
+    fn native() {
+        use std::fs;
+        use std::io::ErrorKind;
+        use std::os::unix::fs::PermissionsExt;
+
+        use crate::{Backend, Builder, NameStyle};
+
+        let root = Folder::new("foo").unwrap();
+        let mut builder = Builder::new();
+        builder
+            .parent(&root)
+            .name_style(NameStyle::Alphanumeric)
+            .backend(Backend::Native);
+        let folder = builder
+            .clone()
+            .prefix("bar")
+            .timestamped(true)
+            .folder()
+            .unwrap();
+        let name = folder.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("bar."));
+        let random = name.rsplit('.').next().unwrap();
+        assert_eq!(random.len(), 12);
+        assert!(!random.ends_with("XXXXXX"));
+        assert!(random.chars().all(|c| c.is_ascii_alphanumeric()));
+        let mode = fs::metadata(&folder).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+
+        let folder = builder
+            .clone()
+            .permissions(fs::Permissions::from_mode(0o750))
+            .random_len(20)
+            .folder()
+            .unwrap();
+        let mode = fs::metadata(&folder).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+        assert_eq!(folder.file_name().unwrap().len(), 20);
+
+        for (result, message) in [
+            (builder.clone().suffix(".baz").folder(), "suffixes"),
+            (builder.clone().random_len(5).folder(), "six"),
+            (
+                builder.clone().name_style(NameStyle::Lowercase).folder(),
+                "alphanumeric",
+            ),
+        ] {
+            let error = result.unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+            assert!(error.to_string().contains(message));
+        }
+
+        let result = builder
+            .clone()
+            .prefix("a".repeat(libc::PATH_MAX as usize))
+            .max_name_len(usize::MAX)
+            .folder();
+        assert!(result.unwrap_err().to_string().contains("template"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn try_remove_failed() {
+        use std::fs::{self, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        // Permissions do not apply to the superuser.
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+        let folder = Folder::new("foo").unwrap();
+        for name in ["bar", "baz", "qux"] {
+            fs::create_dir(folder.join(name)).unwrap();
+            fs::write(folder.join(name).join("foo.txt"), "Hi there!").unwrap();
+        }
+        for name in ["bar", "qux"] {
+            fs::set_permissions(folder.join(name), Permissions::from_mode(0o500)).unwrap();
+        }
+        let path = folder.to_path_buf();
+        let report = folder.try_remove().unwrap_err();
+        assert_eq!(report.failed.len(), 2);
+        assert_eq!(report.removed, 2);
+        assert!(report.remains);
+        assert_eq!(report.to_string().lines().count(), 2);
+        assert!(!path.join("baz").exists());
+        for name in ["bar", "qux"] {
+            fs::set_permissions(path.join(name), Permissions::from_mode(0o700)).unwrap();
+        }
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verify_identity_failed() {
+        use std::fs::{self, Permissions};
+        use std::io::ErrorKind;
+        use std::os::unix::fs::PermissionsExt;
+
+        // Permissions do not apply to the superuser.
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+        let root = Folder::new("foo").unwrap();
+        let folder = Folder::with_parent(&root, "bar").unwrap();
+        let path = folder.to_path_buf();
+        fs::set_permissions(&root, Permissions::from_mode(0o600)).unwrap();
+        let error = folder.remove().unwrap_err();
+        fs::set_permissions(&root, Permissions::from_mode(0o700)).unwrap();
+        assert_eq!(error.error.kind(), ErrorKind::PermissionDenied);
+        assert!(error.to_string().contains("identity"));
+        assert!(path.exists());
+        drop(error);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn shred_and_remove() {
+        use std::fs;
+
+        let folder = Folder::new("foo").unwrap();
+        fs::create_dir(folder.join("bar")).unwrap();
+        fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+        fs::write(folder.join("bar").join("bar.txt"), "Hi there!").unwrap();
+        let path = folder.to_path_buf();
+        folder.shred_and_remove().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn sync() {
+        let root = Folder::new("foo").unwrap();
+        let folder = crate::Builder::new()
+            .parent(&root)
+            .prefix("bar")
+            .sync(true)
+            .folder()
+            .unwrap();
+        folder.sync_parent().unwrap();
+        let path = folder.rename_into(root.join("bar")).unwrap();
+        assert_eq!(path, root.join("bar"));
+        assert!(path.is_dir());
+
+        let folder = crate::Builder::new()
+            .parent(&root)
+            .prefix("baz")
+            .sync(true)
+            .folder()
+            .unwrap();
+        let path = folder.to_path_buf();
+        folder.remove().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn symlinks() {
+        use std::fs;
+
+        let other = Folder::new("bar").unwrap();
+        fs::write(other.join("bar.txt"), "Hi there!").unwrap();
+        let folder = Folder::new("foo").unwrap();
+        fs::create_dir(folder.join("foo")).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::symlink;
+
+            symlink(other.path(), folder.join("foo").join("bar")).unwrap();
+            symlink(other.join("baz"), folder.join("foo").join("baz")).unwrap();
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::{symlink_dir, symlink_file};
+
+            // Creating symbolic links requires a privilege.
+            if symlink_dir(other.path(), folder.join("foo").join("bar")).is_err() {
+                return;
+            }
+            symlink_file(other.join("baz"), folder.join("foo").join("baz")).unwrap();
+        }
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+        assert!(other.join("bar.txt").is_file());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn junctions() {
+        use std::fs;
+
+        let other = Folder::new("bar").unwrap();
+        other.write("bar/bar.txt", "Hi there!").unwrap();
+        let folder = Folder::new("foo").unwrap();
+        fs::create_dir(folder.join("foo")).unwrap();
+        crate::junction::create(other.path(), &folder.join("foo").join("bar")).unwrap();
+        crate::junction::create(&other.join("bar"), &folder.join("baz")).unwrap();
+        assert!(folder
+            .join("foo")
+            .join("bar")
+            .join("bar")
+            .join("bar.txt")
+            .is_file());
+
+        folder.clear().unwrap();
+        assert!(folder.is_empty().unwrap());
+        assert!(other.join("bar").join("bar.txt").is_file());
+
+        crate::junction::create(other.path(), &folder.join("bar")).unwrap();
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+        assert!(other.join("bar").join("bar.txt").is_file());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn unc() {
+        use std::path::{Component, PathBuf, Prefix};
+
+        // The administrative share of the drive is used if it is available.
+        let local = std::env::temp_dir();
+        let Some(Component::Prefix(prefix)) = local.components().next() else {
+            return;
+        };
+        let Prefix::Disk(letter) = prefix.kind() else {
+            return;
+        };
+        let rest = local.strip_prefix(prefix.as_os_str()).unwrap();
+        let rest = rest.strip_prefix(r"\").unwrap_or(rest);
+        let share = format!(r"localhost\{}$", letter as char);
+        let parents = [
+            PathBuf::from(format!(r"\\{}", share)).join(rest),
+            PathBuf::from(format!(r"\\?\UNC\{}", share)).join(rest),
+        ];
+        if !parents[0].is_dir() {
+            return;
+        }
+        for parent in parents {
+            let folder = Folder::with_parent(&parent, "foo").unwrap();
+            assert!(folder.starts_with(&parent));
+            assert_eq!(folder.as_os_str().len(), parent.as_os_str().len() + 17);
+            folder.write("bar/baz.txt", "Hi there!").unwrap();
+            let path = folder.to_path_buf();
+            drop(folder);
+            assert!(!path.exists());
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn open_files() {
+        let folder = Folder::new("foo").unwrap();
+        folder.write("bar/baz.txt", "Hi there!").unwrap();
+        // The standard library allows for deletion when opening files.
+        let file = std::fs::File::open(folder.join("bar").join("baz.txt")).unwrap();
+        let path = folder.to_path_buf();
+        folder.remove().unwrap();
+        assert!(!path.exists());
+        drop(file);
+    }
+
+    #[test]
+    fn command() {
+        use std::fs;
+        use std::path::PathBuf;
+        use std::process::Command;
+
+        const CHILD: &str = "TEMPORARY_TEST_COMMAND";
+
+        if std::env::var_os(CHILD).is_some() {
+            println!("current: {}", std::env::current_dir().unwrap().display());
+            println!("temporary: {}", std::env::temp_dir().display());
+            return;
+        }
+        let run = |mut command: Command| {
+            let output = command
+                .args(["folder::tests::command", "--exact", "--nocapture"])
+                .env(CHILD, "1")
+                .output()
+                .unwrap();
+            let output = String::from_utf8(output.stdout).unwrap();
+            let find = |prefix| {
+                let line = output
+                    .lines()
+                    .find_map(|line| Some(line.split_once(prefix)?.1));
+                fs::canonicalize(PathBuf::from(line.unwrap())).unwrap()
+            };
+            (find("current: "), find("temporary: "))
+        };
+        let program = std::env::current_exe().unwrap();
+        let mut folder = Folder::new("foo").unwrap();
+        fs::create_dir(folder.join("bar")).unwrap();
+        let path = fs::canonicalize(&folder).unwrap();
+        assert_eq!(run(folder.command(&program)), (path.clone(), path.clone()));
+        let command = folder.command_in("bar", &program).unwrap();
+        assert_eq!(run(command), (path.join("bar"), path.clone()));
+        assert!(folder.command_in("..", &program).is_err());
+
+        folder.set_command_env(false);
+        let (current, temporary) = run(folder.command(&program));
+        assert_eq!(current, path);
+        assert_ne!(temporary, path);
+    }
+
+    #[test]
+    fn replaced() {
+        use std::fs;
+
+        let other = Folder::new("bar").unwrap();
+        let folder = Folder::new("foo").unwrap();
+        fs::rename(&folder, other.join("foo")).unwrap();
+        fs::create_dir(&folder).unwrap();
+        fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+        let path = folder.to_path_buf();
+        let error = folder.remove().unwrap_err();
+        assert_eq!(error.error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(path.join("foo.txt").is_file());
+        fs::remove_dir_all(&path).unwrap();
+
+        #[cfg(unix)]
+        {
+            let folder = Folder::new("foo").unwrap();
+            fs::remove_dir(&folder).unwrap();
+            std::os::unix::fs::symlink(other.path(), folder.path()).unwrap();
+            let path = folder.to_path_buf();
+            assert!(folder.remove().is_err());
+            assert!(other.join("foo").is_dir());
+            fs::remove_file(&path).unwrap();
+        }
+
+        let folder = crate::Builder::new()
+            .prefix("foo")
+            .verify_identity(false)
+            .folder()
+            .unwrap();
+        fs::rename(&folder, other.join("bar")).unwrap();
+        fs::create_dir(&folder).unwrap();
+        let path = folder.to_path_buf();
+        folder.remove().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn with_affixes() {
+        let parent = std::env::temp_dir();
+        let folder = Folder::with_affixes(&parent, "foo", ".workdir").unwrap();
+        let name = folder.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("foo.") && name.ends_with(".workdir"));
+        let folder = Folder::with_affixes(&parent, "", ".workdir").unwrap();
+        let name = folder.file_name().unwrap().to_str().unwrap();
+        assert_eq!(name.find('.'), Some(12));
+        assert!(Folder::with_affixes(&parent, "foo", "/workdir").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn with_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let folder = Folder::with_permissions(std::env::temp_dir(), "foo", 0o750).unwrap();
+        let mode = std::fs::metadata(&folder).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode & !0o750, 0);
+        assert_eq!(mode & 0o700, 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let folder = Folder::new(OsStr::from_bytes(b"foo\xff")).unwrap();
+        let name = folder.file_name().unwrap().as_bytes();
+        assert!(name.starts_with(b"foo\xff."));
+        assert_eq!(name.len(), 5 + 12);
+    }
+
+    #[cfg(all(any(unix, windows), feature = "libc"))]
+    #[test]
+    fn for_process() {
+        let one = std::thread::spawn(|| Folder::for_process("foo").unwrap());
+        let other = std::thread::spawn(|| Folder::for_process("bar").unwrap());
+        let (one, other) = (one.join().unwrap(), other.join().unwrap());
+        assert!(std::ptr::eq(one, other));
+        let name = one.file_name().unwrap().to_str().unwrap();
+        assert!(name.contains(&format!(".{}.", std::process::id())));
+    }
+
+    #[test]
+    fn from_template() {
+        use std::fs;
+
+        let template = Folder::new("foo").unwrap();
+        fs::create_dir_all(template.join("foo").join("bar")).unwrap();
+        fs::write(template.join("foo").join("foo.txt"), "Hi there!").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("foo/foo.txt", template.join("bar")).unwrap();
+
+        let folder = Folder::from_template("bar", &template).unwrap();
+        assert!(folder.join("foo").join("bar").is_dir());
+        let content = fs::read_to_string(folder.join("foo").join("foo.txt")).unwrap();
+        assert_eq!(content, "Hi there!");
+        #[cfg(unix)]
+        assert_eq!(
+            fs::read_link(folder.join("bar")).unwrap(),
+            Path::new("foo/foo.txt"),
+        );
+
+        let error = folder.copy_from(&template).unwrap_err();
+        assert!(error.to_string().contains(template.to_str().unwrap()));
+    }
+
+    #[test]
+    fn write() {
+        use std::io::ErrorKind;
+        use std::time::{Duration, SystemTime};
+
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.write("foo/./bar/baz.txt", "Hi there!").unwrap();
+        assert_eq!(path, folder.join("foo").join("bar").join("baz.txt"));
+        assert_eq!(folder.read("foo/bar/baz.txt").unwrap(), b"Hi there!");
+        folder.write("foo/bar/baz.txt", "Bye!").unwrap();
+        assert_eq!(folder.read_to_string("foo/bar/baz.txt").unwrap(), "Bye!");
+
+        let error = folder.read("qux.txt").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(error
+            .to_string()
+            .contains(&*folder.join("qux.txt").to_string_lossy()));
+
+        let path = folder.touch("qux/qux.txt").unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+        let time = SystemTime::now() - Duration::from_secs(3600);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+        folder.write("qux/qux.txt", "Hi there!").unwrap();
+        folder.touch("qux/qux.txt").unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert_eq!(metadata.len(), 9);
+        assert!(metadata.modified().unwrap() > time);
+
+        let mut outside = folder.as_os_str().to_os_string();
+        outside.push(".txt");
+        let outside = std::path::PathBuf::from(outside);
+        for path in ["../foo.txt", "foo/../../foo.txt", "", "."] {
+            let error = folder.write(path, "Hi there!").unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+            assert!(folder.read(path).is_err());
+            assert!(folder.touch(path).is_err());
+        }
+        assert!(folder.write(&outside, "Hi there!").is_err());
+        assert!(!outside.exists());
+    }
+
+    #[test]
+    fn symlink() {
+        use std::fs;
+        use std::io::ErrorKind;
+
+        let root = Folder::new("foo").unwrap();
+        let outside = root.write("foo.txt", "Hi there!").unwrap();
+        let folder = Folder::with_parent(&root, "bar").unwrap();
+        match folder.symlink(&outside, "bar/foo.txt") {
+            #[cfg(windows)]
+            Err(error) if error.kind() == ErrorKind::PermissionDenied => return,
+            result => assert_eq!(result.unwrap(), folder.join("bar").join("foo.txt")),
+        }
+        assert_eq!(folder.read_to_string("bar/foo.txt").unwrap(), "Hi there!");
+        let path = folder.symlink("nonexistent", "baz").unwrap();
+        assert!(fs::symlink_metadata(&path)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert!(!path.exists());
+        folder.symlink(&*root, "qux").unwrap();
+        for link in ["../foo.txt", "/foo.txt", ""] {
+            let error = folder.symlink(&outside, link).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        }
+
+        #[cfg(all(unix, feature = "fifo"))]
+        {
+            use std::os::unix::fs::FileTypeExt;
+
+            let path = folder.fifo("pipes/qux", 0o600).unwrap();
+            assert!(fs::symlink_metadata(&path).unwrap().file_type().is_fifo());
+            let error = folder.fifo("pipes/qux", 0o600).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+            assert!(folder.fifo("../qux", 0o600).is_err());
+        }
+
+        folder.remove().unwrap();
+        assert!(outside.is_file());
+        assert!(root.is_dir());
+    }
+
+    #[test]
+    fn with_files() {
+        use std::io::ErrorKind;
+
+        let folder = Folder::with_files(
+            "foo",
+            &[("foo.txt", b"Hi there!"), ("bar/./baz/qux.txt", b"Bye!")],
+        )
+        .unwrap();
+        assert_eq!(folder.read("foo.txt").unwrap(), b"Hi there!");
+        assert_eq!(folder.read("bar/baz/qux.txt").unwrap(), b"Bye!");
+
+        let error = folder.populate(&[("foo.txt", b"Bye!")]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+        assert_eq!(folder.read("foo.txt").unwrap(), b"Hi there!");
+
+        let prefix = "temporary-with-files";
+        let leaked = || {
+            std::fs::read_dir(std::env::temp_dir())
+                .unwrap()
+                .filter(|entry| {
+                    let name = entry.as_ref().unwrap().file_name();
+                    name.to_string_lossy().starts_with(prefix)
+                })
+                .count()
+        };
+        for files in [
+            &[("foo.txt", &b"Hi there!"[..]), ("foo.txt", b"Bye!")][..],
+            &[("foo.txt", b"Hi there!"), ("../foo.txt", b"Bye!")],
+            &[("foo.txt", b"Hi there!"), ("/foo.txt", b"Bye!")],
+            &[("foo.txt", b"Hi there!"), ("foo.txt/bar.txt", b"Bye!")],
+        ] {
+            assert!(Folder::with_files(prefix, files).is_err());
+            assert_eq!(leaked(), 0);
+        }
+    }
+
+    #[test]
+    fn size() {
+        use std::fs;
+
+        let folder = Folder::new("foo").unwrap();
+        assert_eq!(
+            (folder.size().unwrap(), folder.count().unwrap()),
+            (0, (0, 0))
+        );
+        fs::create_dir_all(folder.join("foo").join("bar")).unwrap();
+        fs::write(folder.join("foo").join("foo.txt"), "Hi there!").unwrap();
+        fs::write(folder.join("bar.txt"), "Hello!").unwrap();
+        assert_eq!(folder.size().unwrap(), 15);
+        assert_eq!(folder.count().unwrap(), (2, 2));
+        #[cfg(unix)]
+        assert!(folder.disk_usage().unwrap() >= 15);
+    }
+
+    #[test]
+    fn deref() {
+        let folder = Folder::new("bar").unwrap();
+        work(&folder);
+
+        fn work(_: &Path) {}
+    }
+}
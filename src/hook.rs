@@ -0,0 +1,67 @@
+use std::io::Error;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// A handler of cleanup errors.
+pub type CleanupErrorHandler = fn(&Path, &Error);
+
+static HANDLER: RwLock<Option<CleanupErrorHandler>> = RwLock::new(None);
+
+/// Set the handler of errors occurring when temporary entries are removed as
+/// they go out of scope.
+///
+/// The handler is called with the path that failed to be removed and the
+/// error, and it replaces any previous handler. Errors of explicit removals,
+/// such as [`Folder::remove`](crate::Folder::remove), are returned directly
+/// instead. Panics of the handler are contained. If no handler is set and the
+/// `log` or `tracing` feature is enabled, errors are logged as warnings.
+pub fn set_cleanup_error_handler(handler: CleanupErrorHandler) {
+    *HANDLER.write().unwrap_or_else(|error| error.into_inner()) = Some(handler);
+}
+
+/// Report a cleanup error to the handler.
+pub fn report(path: &Path, error: &Error) {
+    let handler = *HANDLER.read().unwrap_or_else(|error| error.into_inner());
+    match handler {
+        Some(handler) => {
+            // Drop might be running during unwinding, where a second panic
+            // aborts the process.
+            let _ = std::panic::catch_unwind(AssertUnwindSafe(|| handler(path, error)));
+        }
+        None => default(path, error),
+    }
+}
+
+#[allow(unused_variables)]
+#[inline]
+fn default(path: &Path, error: &Error) {
+    #[cfg(feature = "log")]
+    log::warn!("failed to remove {}: {}", path.display(), error);
+    #[cfg(feature = "tracing")]
+    tracing::warn!(path = %path.display(), %error, "failed to remove");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Error;
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    static ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn handler(path: &Path, error: &Error) {
+        if path.ends_with("foo.hook") {
+            ERRORS.lock().unwrap().push(error.to_string());
+            panic!("Oh no!");
+        }
+    }
+
+    #[test]
+    fn report() {
+        super::set_cleanup_error_handler(handler);
+        super::report(Path::new("foo.hook"), &Error::other("failed"));
+        super::report(Path::new("bar"), &Error::other("failed"));
+        assert_eq!(*ERRORS.lock().unwrap(), ["failed"]);
+    }
+}
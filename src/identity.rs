@@ -0,0 +1,203 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+/// An identity of a file system entry that survives renaming.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Identity {
+    device: u64,
+    index: u64,
+}
+
+impl Identity {
+    /// Read the identity of an entry without following symbolic links.
+    ///
+    /// `None` is returned on platforms where identities are not available.
+    #[cfg(unix)]
+    pub fn of(path: &Path) -> Result<Option<Identity>> {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = std::fs::symlink_metadata(path)?;
+        Ok(Some(Identity {
+            device: metadata.dev(),
+            index: metadata.ino(),
+        }))
+    }
+
+    /// Read the identity of an open file.
+    #[cfg(unix)]
+    pub fn of_file(file: &std::fs::File) -> Result<Option<Identity>> {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = file.metadata()?;
+        Ok(Some(Identity {
+            device: metadata.dev(),
+            index: metadata.ino(),
+        }))
+    }
+
+    #[cfg(windows)]
+    pub fn of(path: &Path) -> Result<Option<Identity>> {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        const FILE_READ_ATTRIBUTES: u32 = 0x80;
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x02000000;
+        const FILE_FLAG_OPEN_REPARSE_POINT: u32 = 0x00200000;
+
+        let file = std::fs::OpenOptions::new()
+            .access_mode(FILE_READ_ATTRIBUTES)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT)
+            .open(path)?;
+        Identity::of_file(&file)
+    }
+
+    #[cfg(windows)]
+    pub fn of_file(file: &std::fs::File) -> Result<Option<Identity>> {
+        use std::ffi::c_void;
+        use std::os::windows::io::AsRawHandle;
+
+        #[repr(C)]
+        #[derive(Default)]
+        struct Information {
+            attributes: u32,
+            creation_time: [u32; 2],
+            last_access_time: [u32; 2],
+            last_write_time: [u32; 2],
+            volume_serial_number: u32,
+            file_size_high: u32,
+            file_size_low: u32,
+            number_of_links: u32,
+            file_index_high: u32,
+            file_index_low: u32,
+        }
+
+        #[link(name = "kernel32")]
+        extern "system" {
+            fn GetFileInformationByHandle(
+                handle: *mut c_void,
+                information: *mut Information,
+            ) -> i32;
+        }
+
+        let mut information = Information::default();
+        if unsafe { GetFileInformationByHandle(file.as_raw_handle(), &mut information) } == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Some(Identity {
+            device: information.volume_serial_number as u64,
+            index: (information.file_index_high as u64) << 32 | information.file_index_low as u64,
+        }))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    #[inline]
+    pub fn of(_: &Path) -> Result<Option<Identity>> {
+        Ok(None)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    #[inline]
+    pub fn of_file(_: &std::fs::File) -> Result<Option<Identity>> {
+        Ok(None)
+    }
+}
+
+/// What a folder was when it was created, which is checked before removal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Expected {
+    /// The identity of the folder if it is to be checked.
+    pub identity: Option<Identity>,
+    /// The owner of the folder if it is to be checked.
+    #[cfg(unix)]
+    pub owner: Option<u32>,
+}
+
+impl Expected {
+    /// Check that `path` still refers to the expected folder.
+    ///
+    /// The outer error means that the check could not be performed, and it is
+    /// `NotFound` as is if nothing exists at the path. The inner error means
+    /// that the path refers to something else, which is not to be removed.
+    pub fn verify(&self, path: &Path) -> Result<Result<()>> {
+        if let Some(identity) = self.identity {
+            let context = |error: Error| match error.kind() {
+                ErrorKind::NotFound => error,
+                kind => Error::new(
+                    kind,
+                    format!(
+                        "failed to verify the identity of {}: {}",
+                        path.display(),
+                        error,
+                    ),
+                ),
+            };
+            let metadata = std::fs::symlink_metadata(path).map_err(context)?;
+            let other = if metadata.file_type().is_symlink() {
+                None
+            } else {
+                Identity::of(path).map_err(context)?
+            };
+            if other != Some(identity) {
+                // The path belongs to something else now.
+                return Ok(Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "refusing to remove {}, which has been replaced",
+                        path.display(),
+                    ),
+                )));
+            }
+        }
+        #[cfg(unix)]
+        if let Some(owner) = self.owner {
+            use std::os::unix::fs::MetadataExt;
+
+            let metadata = std::fs::symlink_metadata(path).map_err(|error| match error.kind() {
+                ErrorKind::NotFound => error,
+                kind => Error::new(kind, format!("{}: {}", path.display(), error)),
+            })?;
+            if metadata.uid() != owner {
+                return Ok(Err(Error::new(
+                    ErrorKind::PermissionDenied,
+                    format!(
+                        "refusing to remove {}, which is owned by user {} instead of {}",
+                        path.display(),
+                        metadata.uid(),
+                        owner,
+                    ),
+                )));
+            }
+        }
+        Ok(Ok(()))
+    }
+}
+
+/// Return the effective user of the process.
+///
+/// `None` is returned if the user is unknown, which is the case without the
+/// `libc` feature.
+#[cfg(all(unix, feature = "libc"))]
+#[inline]
+pub fn user() -> Option<u32> {
+    Some(unsafe { libc::geteuid() })
+}
+
+#[cfg(all(unix, not(feature = "libc")))]
+#[inline]
+pub fn user() -> Option<u32> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Identity;
+    use crate::Folder;
+
+    #[test]
+    fn of() {
+        let one = Folder::new("foo").unwrap();
+        let other = Folder::new("foo").unwrap();
+        let identity = Identity::of(one.path()).unwrap();
+        assert_eq!(identity, Identity::of(one.path()).unwrap());
+        assert_ne!(identity, Identity::of(other.path()).unwrap());
+    }
+}
@@ -0,0 +1,92 @@
+//! Conversions to and from the types of the `tempfile` crate.
+//!
+//! The responsibility of removing an entry is transferred exactly once: the
+//! side that is converted from is disarmed, and only the side that is
+//! converted to removes the entry. Since `tempfile::TempDir` cannot be created
+//! from an existing path, folders can only be converted into.
+
+use std::io::Result;
+
+use crate::{File, Folder, TempPath};
+
+impl From<tempfile::TempDir> for Folder {
+    #[inline]
+    fn from(folder: tempfile::TempDir) -> Folder {
+        Folder::armed(folder.keep())
+    }
+}
+
+impl From<tempfile::NamedTempFile> for File {
+    fn from(file: tempfile::NamedTempFile) -> File {
+        let (file, mut path) = file.into_parts();
+        path.disable_cleanup(true);
+        File::armed(path.to_path_buf(), file)
+    }
+}
+
+impl From<tempfile::TempPath> for TempPath {
+    fn from(mut path: tempfile::TempPath) -> TempPath {
+        path.disable_cleanup(true);
+        TempPath::from_path(path.to_path_buf())
+    }
+}
+
+impl File {
+    /// Convert the file into a temporary file of the `tempfile` crate.
+    ///
+    /// Anonymous files cannot be converted.
+    pub fn into_named_temp_file(self) -> Result<tempfile::NamedTempFile> {
+        let (path, file) = self.into_parts()?;
+        match tempfile::TempPath::try_from_path(&path) {
+            Ok(path) => Ok(tempfile::NamedTempFile::from_parts(file, path)),
+            Err(error) => {
+                drop(File::armed(path, file));
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Write;
+
+    use crate::{File, Folder, TempPath};
+
+    #[test]
+    fn folder() {
+        let folder = tempfile::TempDir::new().unwrap();
+        fs::write(folder.path().join("foo.txt"), "Hi there!").unwrap();
+        let folder = Folder::from(folder);
+        assert!(folder.join("foo.txt").exists());
+        let path = folder.to_path_buf();
+        drop(folder);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"Hi there!").unwrap();
+        let file = File::from(file);
+        assert_eq!(fs::read(file.path()).unwrap(), b"Hi there!");
+        let path = file.path().to_path_buf();
+        let file = file.into_named_temp_file().unwrap();
+        assert_eq!(file.path(), path);
+        drop(file);
+        assert!(!path.exists());
+
+        assert!(File::unnamed().unwrap().into_named_temp_file().is_err());
+    }
+
+    #[test]
+    fn temp_path() {
+        let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+        let path = TempPath::from(path);
+        assert!(path.exists());
+        let other = path.to_path_buf();
+        drop(path);
+        assert!(!other.exists());
+    }
+}
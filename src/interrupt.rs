@@ -0,0 +1,56 @@
+use std::io::{ErrorKind, Result};
+
+/// The number of attempts after which interruptions are reported.
+const ATTEMPTS: usize = 100;
+
+/// Call a closure until it fails with an error other than `Interrupted`.
+///
+/// System calls such as `mkdir`, `unlink`, and `rename` might be interrupted
+/// by signals on some platforms. The number of attempts is bounded so that a
+/// stream of signals does not keep the caller forever.
+pub fn retry<F: FnMut() -> Result<T>, T>(mut f: F) -> Result<T> {
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(error) if error.kind() == ErrorKind::Interrupted && attempt < ATTEMPTS => {
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error, ErrorKind};
+
+    #[test]
+    fn retry() {
+        let mut count = 0;
+        let value = super::retry(|| {
+            count += 1;
+            match count {
+                1 | 2 => Err(Error::from(ErrorKind::Interrupted)),
+                _ => Ok(42),
+            }
+        });
+        assert_eq!(value.unwrap(), 42);
+        assert_eq!(count, 3);
+
+        let mut count = 0;
+        let value = super::retry(|| -> std::io::Result<()> {
+            count += 1;
+            Err(Error::from(ErrorKind::PermissionDenied))
+        });
+        assert_eq!(value.unwrap_err().kind(), ErrorKind::PermissionDenied);
+        assert_eq!(count, 1);
+
+        let mut count = 0;
+        let value = super::retry(|| -> std::io::Result<()> {
+            count += 1;
+            Err(Error::from(ErrorKind::Interrupted))
+        });
+        assert_eq!(value.unwrap_err().kind(), ErrorKind::Interrupted);
+        assert_eq!(count, super::ATTEMPTS);
+    }
+}
@@ -0,0 +1,79 @@
+use std::ffi::c_void;
+use std::fs;
+use std::io::{Error, Result};
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::fs::OpenOptionsExt;
+use std::os::windows::io::AsRawHandle;
+use std::path::Path;
+
+const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x02000000;
+const FILE_FLAG_OPEN_REPARSE_POINT: u32 = 0x00200000;
+const FSCTL_SET_REPARSE_POINT: u32 = 0x000900A4;
+const GENERIC_WRITE: u32 = 0x40000000;
+const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA0000003;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn DeviceIoControl(
+        handle: *mut c_void,
+        code: u32,
+        input: *const c_void,
+        input_size: u32,
+        output: *mut c_void,
+        output_size: u32,
+        returned: *mut u32,
+        overlapped: *mut c_void,
+    ) -> i32;
+}
+
+/// Create a junction at `link` pointing at the folder `target`.
+///
+/// Unlike symbolic links, junctions do not require any privileges. The target
+/// is made absolute.
+pub fn create(target: &Path, link: &Path) -> Result<()> {
+    let target = crate::verbatim::path(target)?;
+    // The substitute name uses the prefix of the object manager.
+    let mut name = r"\??\".encode_utf16().collect::<Vec<_>>();
+    name.extend(target.as_os_str().encode_wide().skip(4));
+    let size = (name.len() * 2) as u16;
+    let mut buffer = Vec::<u8>::with_capacity(20 + name.len() * 2);
+    buffer.extend(IO_REPARSE_TAG_MOUNT_POINT.to_le_bytes());
+    // The offsets and lengths followed by the substitute and print names.
+    buffer.extend((8 + size + 4).to_le_bytes());
+    buffer.extend(0u16.to_le_bytes());
+    buffer.extend(0u16.to_le_bytes());
+    buffer.extend(size.to_le_bytes());
+    buffer.extend((size + 2).to_le_bytes());
+    buffer.extend(0u16.to_le_bytes());
+    for unit in name.iter().chain(&[0, 0]) {
+        buffer.extend(unit.to_le_bytes());
+    }
+    fs::create_dir(link)?;
+    let result = fs::OpenOptions::new()
+        .access_mode(GENERIC_WRITE)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT)
+        .open(link)
+        .and_then(|file| {
+            let mut returned = 0;
+            let code = unsafe {
+                DeviceIoControl(
+                    file.as_raw_handle(),
+                    FSCTL_SET_REPARSE_POINT,
+                    buffer.as_ptr().cast(),
+                    buffer.len() as u32,
+                    std::ptr::null_mut(),
+                    0,
+                    &mut returned,
+                    std::ptr::null_mut(),
+                )
+            };
+            if code == 0 {
+                return Err(Error::last_os_error());
+            }
+            Ok(())
+        });
+    if result.is_err() {
+        let _ = fs::remove_dir(link);
+    }
+    result
+}
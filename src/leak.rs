@@ -0,0 +1,185 @@
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Once};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    next: 0,
+    records: BTreeMap::new(),
+});
+static INSTALL: Once = Once::new();
+
+struct Registry {
+    next: u64,
+    records: BTreeMap<u64, LeakRecord>,
+}
+
+/// A record of a temporary folder that has not been removed.
+#[derive(Clone, Debug)]
+pub struct LeakRecord {
+    /// The path to the folder.
+    pub path: PathBuf,
+    /// The state of the folder.
+    pub status: LeakStatus,
+    /// The backtrace of the creation of the folder.
+    ///
+    /// It is captured only if enabled via `RUST_BACKTRACE` or
+    /// `RUST_LIB_BACKTRACE`.
+    pub backtrace: Option<Arc<Backtrace>>,
+}
+
+/// The state of a temporary folder that has not been removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeakStatus {
+    /// The object responsible for the folder still exists.
+    Alive,
+    /// The folder has been kept intentionally, such as via `keep` or
+    /// `into_path`.
+    Kept,
+    /// The object has gone out of scope, but the folder failed to be removed.
+    Leaked,
+}
+
+/// A record of a folder in the registry of leaks.
+#[derive(Debug)]
+pub(crate) struct Tracker {
+    id: u64,
+}
+
+/// Start recording temporary folders that are not removed.
+///
+/// Folders created afterwards are recorded until they are removed, and the
+/// records can be inspected via [`leak_report`]. With the `libc` feature, the
+/// folders that are still in place without having been kept intentionally are
+/// listed in the standard error when the process exits. Until it is called,
+/// nothing is recorded, and there is no overhead.
+pub fn enable_leak_tracking() {
+    ENABLED.store(true, Ordering::Release);
+    INSTALL.call_once(install);
+}
+
+/// Return the records of the folders that have not been removed.
+///
+/// The records are in the order of creation. See [`enable_leak_tracking`].
+pub fn leak_report() -> Vec<LeakRecord> {
+    lock().records.values().cloned().collect()
+}
+
+impl Tracker {
+    /// Record a folder if tracking is enabled.
+    pub(crate) fn new(path: &Path) -> Option<Tracker> {
+        if !ENABLED.load(Ordering::Acquire) {
+            return None;
+        }
+        let backtrace = Backtrace::capture();
+        let backtrace = match backtrace.status() {
+            BacktraceStatus::Captured => Some(Arc::new(backtrace)),
+            _ => None,
+        };
+        let mut registry = lock();
+        let id = registry.next;
+        registry.next += 1;
+        registry.records.insert(
+            id,
+            LeakRecord {
+                path: path.to_path_buf(),
+                status: LeakStatus::Alive,
+                backtrace,
+            },
+        );
+        Some(Tracker { id })
+    }
+
+    /// Update the state of the folder.
+    pub(crate) fn set(&self, status: LeakStatus) {
+        if let Some(record) = lock().records.get_mut(&self.id) {
+            record.status = status;
+        }
+    }
+
+    /// Remove the record as the folder is gone.
+    #[inline]
+    pub(crate) fn forget(&self) {
+        lock().records.remove(&self.id);
+    }
+}
+
+fn install() {
+    #[cfg(all(any(unix, windows), feature = "libc"))]
+    unsafe {
+        libc::atexit(exit);
+    }
+}
+
+#[cfg(all(any(unix, windows), feature = "libc"))]
+extern "C" fn exit() {
+    for record in leak_report() {
+        // Folders registered for removal at exit might be gone already.
+        if record.status == LeakStatus::Kept || std::fs::symlink_metadata(&record.path).is_err() {
+            continue;
+        }
+        match &record.backtrace {
+            Some(backtrace) => eprintln!(
+                "leaked the temporary folder {}, which was created at:\n{}",
+                record.path.display(),
+                backtrace,
+            ),
+            _ => eprintln!("leaked the temporary folder {}", record.path.display()),
+        }
+    }
+}
+
+fn lock() -> MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::LeakStatus;
+    use crate::Folder;
+
+    fn status(path: &Path) -> Option<LeakStatus> {
+        super::leak_report()
+            .into_iter()
+            .find(|record| record.path == path)
+            .map(|record| record.status)
+    }
+
+    #[test]
+    fn report() {
+        super::enable_leak_tracking();
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.to_path_buf();
+        assert_eq!(status(&path), Some(LeakStatus::Alive));
+        drop(folder);
+        assert_eq!(status(&path), None);
+
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.into_path();
+        assert_eq!(status(&path), Some(LeakStatus::Kept));
+        fs::remove_dir(&path).unwrap();
+
+        let mut folder = Folder::new("foo").unwrap();
+        folder.keep();
+        assert_eq!(status(&folder), Some(LeakStatus::Kept));
+        folder.unkeep();
+        assert_eq!(status(&folder), Some(LeakStatus::Alive));
+        drop(folder);
+
+        // A folder that has been replaced is not removed.
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.to_path_buf();
+        let other = path.with_extension("bar");
+        fs::rename(&path, &other).unwrap();
+        fs::create_dir(&path).unwrap();
+        drop(folder);
+        assert_eq!(status(&path), Some(LeakStatus::Leaked));
+        fs::remove_dir(&path).unwrap();
+        fs::remove_dir(&other).unwrap();
+    }
+}
@@ -3,180 +3,166 @@
 //! ## Example
 //!
 //! ```rust
-//! use std::fs::File;
-//! use std::io::Write;
 //! use temporary::Folder;
 //!
 //! // Create a temporary folder.
 //! let folder = Folder::new("foo").unwrap();
 //!
 //! // Do some work.
-//! let mut file = File::create(folder.join("foo.txt")).unwrap();
-//! file.write_all(b"Hi there!").unwrap();
+//! folder.write("foo/bar.txt", "Hi there!").unwrap();
+//! assert_eq!(folder.read_to_string("foo/bar.txt").unwrap(), "Hi there!");
 //!
 //! // The folder and its content get removed automatically.
 //! ```
-
-use random::Source;
-use std::io::{Error, ErrorKind, Result};
-use std::ops::Deref;
-use std::path::{Path, PathBuf};
-use std::{env, fmt, fs};
-
-/// A temporary folder.
-pub struct Folder {
-    path: PathBuf,
-    removed: bool,
-}
-
-impl Folder {
-    /// Create a temporary folder.
-    ///
-    /// The folder will have a name starting from `prefix`, and it will be
-    /// automatically removed when the object goes out of scope.
-    #[inline]
-    pub fn new(prefix: &str) -> Result<Folder> {
-        Folder::with_parent(env::temp_dir(), prefix)
-    }
-
-    /// Create a temporary folder in a specific folder.
-    ///
-    /// The folder will have a name starting from `prefix`, and it will be
-    /// automatically removed when the object goes out of scope.
-    pub fn with_parent<T: AsRef<Path>>(parent: T, prefix: &str) -> Result<Folder> {
-        const RETRIES: u32 = 1 << 31;
-        const CHARS: usize = 12;
-
-        let parent = parent.as_ref();
-        if !parent.is_absolute() {
-            let current = env::current_dir()?;
-            return Folder::with_parent(current.join(parent), prefix);
-        }
-
-        let mut source = random::default(random_seed(parent, prefix));
-        for _ in 0..RETRIES {
-            let suffix: String = random_string(CHARS, &mut source);
-
-            let path = if prefix.is_empty() {
-                parent.join(&suffix)
-            } else {
-                parent.join(&format!("{}.{}", prefix, suffix))
-            };
-
-            match fs::create_dir(&path) {
-                Ok(_) => {
-                    return Ok(Folder {
-                        path,
-                        removed: false,
-                    })
-                }
-                Err(error) => match error.kind() {
-                    ErrorKind::AlreadyExists => {}
-                    _ => return Err(error),
-                },
-            }
-        }
-
-        Err(Error::new(
-            ErrorKind::AlreadyExists,
-            "failed to find a vacant name",
-        ))
-    }
-
-    /// Return the path to the folder.
-    #[inline]
-    pub fn path(&self) -> &Path {
-        self.as_ref()
-    }
-
-    /// Return the path to the folder and dispose the object without removing
-    /// the actual folder.
-    #[inline]
-    pub fn into_path(mut self) -> PathBuf {
-        self.removed = true;
-        self.path.clone()
-    }
-
-    /// Remove the folder.
-    #[inline]
-    pub fn remove(mut self) -> Result<()> {
-        self.cleanup()
-    }
-
-    fn cleanup(&mut self) -> Result<()> {
-        if self.removed {
-            return Ok(());
-        }
-        self.removed = true;
-
-        fs::remove_dir_all(&self.path)
-    }
-}
-
-impl AsRef<Path> for Folder {
-    #[inline]
-    fn as_ref(&self) -> &Path {
-        &self.path
-    }
-}
-
-impl fmt::Debug for Folder {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        self.path.fmt(formatter)
-    }
-}
-
-impl Deref for Folder {
-    type Target = Path;
-
-    #[inline]
-    fn deref(&self) -> &Path {
-        &self.path
-    }
-}
-
-impl Drop for Folder {
-    #[allow(unused_must_use)]
-    #[inline]
-    fn drop(&mut self) {
-        self.cleanup();
-    }
-}
-
-fn random_seed(_: &Path, prefix: &str) -> u64 {
-    prefix.as_bytes().iter().map(|&c| c as u64).sum::<u64>() ^ 0x12345678
-}
-
-fn random_string<S: Source>(length: usize, source: &mut S) -> String {
-    unsafe { String::from_utf8_unchecked((0..length).map(|_| random_letter(source)).collect()) }
-}
-
-fn random_letter<S: Source>(source: &mut S) -> u8 {
-    b'a' + (source.read::<u64>() % 26) as u8
-}
-
-#[cfg(test)]
-mod tests {
-    use super::Folder;
-    use std::path::Path;
-
-    #[test]
-    fn new() {
-        use std::fs;
-
-        let path = {
-            let folder = Folder::new("foo").unwrap();
-            assert!(fs::metadata(folder.path()).is_ok());
-            folder.path().to_path_buf()
-        };
-        assert!(fs::metadata(path).is_err());
-    }
-
-    #[test]
-    fn deref() {
-        let folder = Folder::new("bar").unwrap();
-        work(&folder);
-
-        fn work(_: &Path) {}
-    }
+//!
+//! ## Environment
+//!
+//! If `TEMPORARY_KEEP` is set to `1` or `true`, temporary folders are not
+//! removed, and their paths are printed to the standard error instead. If
+//! `TEMPORARY_DIR` is set, it overrides the default temporary folder. Both
+//! variables are read once per process. The default temporary folder can also
+//! be set programmatically with [`set_default_parent`]. On WASI, where there is
+//! no temporary folder of the system, `TMPDIR` is used, and it should point at
+//! a preopened folder.
+//!
+//! ## Dependencies
+//!
+//! Names are generated using the `random` crate and seeded with entropy from
+//! the operating system via the `getrandom` crate, which are enabled by the
+//! default features `random` and `os-random`, respectively. Without them, names
+//! are generated using the standard library only.
+//!
+//! System calls that the standard library does not expose are made via the
+//! `libc` crate on Unix and Windows, which is enabled by the default feature
+//! `libc` and required by the features `fifo`, `owner`, and `signals`. Without
+//! it, the crate has no dependencies with `default-features = false`, and the
+//! following is affected:
+//!
+//! * folders are not removed at exit, so `Folder::for_process` is not
+//!   available, [`Builder::register_for_exit`] relies on the panic hook only,
+//!   and leaks are not listed at exit;
+//! * [`Backend::Native`] is the same as [`Backend::Portable`];
+//! * removal on Linux and macOS is not anchored to descriptors;
+//! * renames without replacement on Linux check the target beforehand instead
+//!   of using `renameat2`;
+//! * anonymous files on Linux are named files unlinked right away;
+//! * the current user is unknown on Unix, so secure parents have to be owned by
+//!   root, [`Folder::runtime`] is the same as [`Folder::new`], and [`Sweep`]
+//!   skips entries unless [`Sweep::any_owner`] is set;
+//! * [`Folder::in_memory`] is the same as [`Folder::new`];
+//! * [`free_space`] and inheritable files are not supported on Unix.
+
+#[cfg(feature = "acl")]
+mod acl;
+mod alias;
+#[cfg(feature = "archive")]
+mod archive;
+#[cfg(feature = "async")]
+mod asynchronous;
+mod atomic;
+mod background;
+mod builder;
+mod copy;
+mod cwd;
+#[cfg(windows)]
+mod disposition;
+mod env;
+mod error;
+mod exit;
+mod ext;
+mod file;
+mod filesystem;
+mod folder;
+mod hook;
+mod identity;
+#[cfg(feature = "tempfile-interop")]
+mod interop;
+mod interrupt;
+#[cfg(windows)]
+mod junction;
+mod leak;
+mod link;
+mod lock;
+mod manifest;
+#[cfg(feature = "mmap")]
+mod map;
+mod memory;
+mod name;
+pub mod names;
+#[cfg(feature = "owner")]
+mod owner;
+mod path;
+mod pool;
+mod probe;
+#[cfg(all(any(unix, windows), feature = "libc"))]
+mod process;
+mod quota;
+mod remove;
+mod reserve;
+mod rng;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod session;
+mod shared;
+#[cfg(feature = "signals")]
+mod signal;
+mod space;
+mod spooled;
+mod sweep;
+mod sync;
+mod tempdir;
+#[cfg(feature = "macros")]
+mod test;
+mod trace;
+mod tree;
+mod verbatim;
+mod walk;
+#[cfg(feature = "watch")]
+mod watch;
+
+pub use alias::AliasGuard;
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncFolder;
+pub use atomic::AtomicWriteFile;
+pub use background::{BackgroundRemoval, RemovalOutcome};
+pub use builder::{Builder, Entry};
+pub use cwd::CwdGuard;
+pub use env::{default_parent, reset_default_parent, set_default_parent};
+pub use error::{Error, Operation, Result};
+pub use ext::TempPathExt;
+pub use file::{File, PersistError};
+pub use filesystem::{Filesystem, StdFilesystem};
+pub use folder::{with_folder, ChildFolder, Folder, RemoveError, RenameError};
+pub use hook::{set_cleanup_error_handler, CleanupErrorHandler};
+pub use leak::{enable_leak_tracking, leak_report, LeakRecord, LeakStatus};
+pub use lock::LockFolder;
+pub use manifest::{DiffReport, Manifest};
+#[cfg(feature = "mmap")]
+pub use map::{Map, MapMut};
+pub use name::{Backend, NameStyle};
+pub use names::unique_name;
+pub use path::TempPath;
+pub use pool::{FolderPool, PooledFolder};
+pub use probe::{probe, probe_in, ProbeReport, ProbeStep};
+pub use quota::{QuotaExceeded, QuotaStatus};
+pub use remove::RemovalReport;
+pub use reserve::{reserve, ReservedPath};
+pub use session::Session;
+pub use shared::SharedFolder;
+#[cfg(feature = "signals")]
+pub use signal::cleanup_on_interrupt;
+pub use space::free_space;
+pub use spooled::SpooledFile;
+pub use sweep::{sweep, Sweep, SweepReport};
+pub use tempdir::{with_temp_dir_override, TempDirGuard};
+#[cfg(feature = "macros")]
+pub use temporary_macros::test;
+pub use tree::TempTree;
+#[cfg(feature = "watch")]
+pub use watch::{Change, Watcher};
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::test::folder;
 }
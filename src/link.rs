@@ -0,0 +1,64 @@
+use std::io::Result;
+use std::path::Path;
+
+/// Create a symbolic link at `path` pointing at `target`.
+///
+/// Relative targets are resolved against the parent of the link. On Windows,
+/// a link to a folder is created if the target is a folder at that moment,
+/// and the lack of the privilege needed is reported as `PermissionDenied`.
+#[inline]
+pub fn symlink(target: &Path, path: &Path) -> Result<()> {
+    platform::symlink(target, path)
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::io::Result;
+    use std::path::Path;
+
+    #[inline]
+    pub fn symlink(target: &Path, path: &Path) -> Result<()> {
+        std::os::unix::fs::symlink(target, path)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::io::{Error, ErrorKind, Result};
+    use std::path::Path;
+
+    const ERROR_PRIVILEGE_NOT_HELD: i32 = 1314;
+
+    pub fn symlink(target: &Path, path: &Path) -> Result<()> {
+        let resolved = match path.parent() {
+            Some(parent) => parent.join(target),
+            _ => target.to_path_buf(),
+        };
+        let result = if resolved.is_dir() {
+            std::os::windows::fs::symlink_dir(target, path)
+        } else {
+            std::os::windows::fs::symlink_file(target, path)
+        };
+        result.map_err(|error| match error.raw_os_error() {
+            Some(ERROR_PRIVILEGE_NOT_HELD) => Error::new(
+                ErrorKind::PermissionDenied,
+                "creating symbolic links requires the corresponding privilege or the developer mode",
+            ),
+            _ => error,
+        })
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::io::{Error, ErrorKind, Result};
+    use std::path::Path;
+
+    #[inline]
+    pub fn symlink(_: &Path, _: &Path) -> Result<()> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "symbolic links are not supported on this platform",
+        ))
+    }
+}
@@ -0,0 +1,277 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::{self, TryLockError};
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::identity::Identity;
+use crate::Operation;
+
+const LOCK: &str = ".temporary.lock";
+
+/// A folder with a well-known name shared between processes.
+///
+/// The folder is created if it does not exist, and it is guarded by an
+/// advisory lock on a file inside it, which is `flock(2)` on Unix and
+/// `LockFileEx` on Windows. Each object holds a shared lock. When the object
+/// goes out of scope, the folder is removed if there are no other holders,
+/// that is, if the lock can be upgraded to an exclusive one. Locks of
+/// processes that exit without releasing them, including crashes, are
+/// released by the system, and the folder is then reused as is.
+///
+/// ```rust
+/// use temporary::LockFolder;
+///
+/// let folder = LockFolder::acquire(std::env::temp_dir(), "lock-folder-doc").unwrap();
+/// std::fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+/// ```
+pub struct LockFolder {
+    path: PathBuf,
+    file: Option<fs::File>,
+}
+
+impl LockFolder {
+    /// Acquire the folder `name` in `parent` waiting for it if needed.
+    ///
+    /// The call blocks only while the folder is being removed by its last
+    /// holder.
+    #[inline]
+    pub fn acquire<T: AsRef<Path>, U: AsRef<OsStr>>(
+        parent: T,
+        name: U,
+    ) -> crate::Result<LockFolder> {
+        LockFolder::open(parent.as_ref(), name.as_ref(), |file| file.lock_shared())
+    }
+
+    /// Acquire the folder `name` in `parent` without waiting.
+    ///
+    /// If the folder is being removed, an error of kind
+    /// [`ErrorKind::WouldBlock`] is returned.
+    #[inline]
+    pub fn try_acquire<T: AsRef<Path>, U: AsRef<OsStr>>(
+        parent: T,
+        name: U,
+    ) -> crate::Result<LockFolder> {
+        LockFolder::open(parent.as_ref(), name.as_ref(), |file| {
+            file.try_lock_shared().map_err(Into::into)
+        })
+    }
+
+    /// Acquire the folder `name` in `parent` waiting for at most `timeout`.
+    ///
+    /// If the folder is still being removed once the time runs out, an error
+    /// of kind [`ErrorKind::TimedOut`] is returned.
+    pub fn acquire_timeout<T: AsRef<Path>, U: AsRef<OsStr>>(
+        parent: T,
+        name: U,
+        timeout: Duration,
+    ) -> crate::Result<LockFolder> {
+        let deadline = Instant::now() + timeout;
+        LockFolder::open(parent.as_ref(), name.as_ref(), |file| {
+            let mut delay = Duration::from_millis(1);
+            loop {
+                match file.try_lock_shared() {
+                    Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                        std::thread::sleep(delay.min(deadline - Instant::now()));
+                        delay = (delay * 2).min(Duration::from_millis(100));
+                    }
+                    Err(TryLockError::WouldBlock) => return Err(Error::from(ErrorKind::TimedOut)),
+                    result => return result.map_err(Into::into),
+                }
+            }
+        })
+    }
+
+    fn open<F>(parent: &Path, name: &OsStr, mut lock: F) -> crate::Result<LockFolder>
+    where
+        F: FnMut(&fs::File) -> Result<()>,
+    {
+        let path = parent.join(name);
+        let context = |error| crate::Error::new(Operation::AcquireFolder, &path, error);
+        crate::name::check(name, "name").map_err(context)?;
+        let lock_path = path.join(LOCK);
+        loop {
+            fs::create_dir_all(&path).map_err(context)?;
+            let file = match fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&lock_path)
+            {
+                Ok(file) => file,
+                // The folder has been removed by its last holder in the meantime.
+                Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                Err(error) => return Err(context(error)),
+            };
+            lock(&file).map_err(context)?;
+            // The file might have been removed while waiting for the lock.
+            match Identity::of(&lock_path) {
+                Ok(identity) if identity == Identity::of_file(&file).map_err(context)? => {
+                    return Ok(LockFolder {
+                        path,
+                        file: Some(file),
+                    });
+                }
+                Err(error) if error.kind() != ErrorKind::NotFound => return Err(context(error)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Return the path to the folder.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Release the lock and remove the folder if there are no other holders.
+    ///
+    /// It returns whether the folder has been removed.
+    #[inline]
+    pub fn release(mut self) -> crate::Result<bool> {
+        self.cleanup()
+            .map_err(|error| crate::Error::new(Operation::Remove, &self.path, error))
+    }
+
+    fn cleanup(&mut self) -> Result<bool> {
+        let Some(file) = self.file.take() else {
+            return Ok(false);
+        };
+        // The shared lock is given up first since it cannot be upgraded in
+        // place on all platforms.
+        file.unlock()?;
+        match file.try_lock() {
+            Ok(_) => {}
+            Err(TryLockError::WouldBlock) => return Ok(false),
+            Err(TryLockError::Error(error)) => return Err(error),
+        }
+        // Other processes might be waiting for the lock, and the lock file is
+        // removed last so that they notice that the folder is gone.
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            if entry.file_name() != LOCK {
+                crate::remove::entry(&entry.path(), entry.file_type()?)?;
+            }
+        }
+        fs::remove_file(self.path.join(LOCK))?;
+        drop(file);
+        match fs::remove_dir(&self.path) {
+            // Another process has recreated the folder in the meantime.
+            Err(error) if error.kind() != ErrorKind::DirectoryNotEmpty => Err(error),
+            _ => Ok(true),
+        }
+    }
+}
+
+impl AsRef<Path> for LockFolder {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Debug for LockFolder {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.path.fmt(formatter)
+    }
+}
+
+impl Deref for LockFolder {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LockFolder {
+    fn drop(&mut self) {
+        if let Err(error) = self.cleanup() {
+            crate::hook::report(&self.path, &error);
+        }
+    }
+}
+
+#[cfg(all(test, any(unix, windows)))]
+mod tests {
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::process::Command;
+    use std::time::Duration;
+
+    use super::LockFolder;
+    use crate::Folder;
+
+    const CHILD: &str = "TEMPORARY_LOCK_CHILD";
+
+    #[test]
+    fn acquire() {
+        let root = Folder::new("foo").unwrap();
+        let one = LockFolder::acquire(&root, "bar").unwrap();
+        let other = LockFolder::try_acquire(&root, "bar").unwrap();
+        assert_eq!(one.path(), other.path());
+        fs::write(one.join("bar.txt"), "Hi there!").unwrap();
+        assert!(!one.release().unwrap());
+        assert!(fs::metadata(other.join("bar.txt")).is_ok());
+        let path = other.to_path_buf();
+        assert!(other.release().unwrap());
+        assert!(!path.exists());
+
+        assert!(LockFolder::try_acquire(&root, "bar/baz").is_err());
+    }
+
+    #[test]
+    fn acquire_timeout() {
+        let root = Folder::new("foo").unwrap();
+        let path = root.join("bar");
+        fs::create_dir(&path).unwrap();
+        // An exclusive lock is held only by the last holder removing the folder.
+        let guard = fs::File::create(path.join(super::LOCK)).unwrap();
+        guard.lock().unwrap();
+        let error = LockFolder::try_acquire(&root, "bar").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::WouldBlock);
+        let timeout = Duration::from_millis(50);
+        let error = LockFolder::acquire_timeout(&root, "bar", timeout).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+        drop(guard);
+        let folder = LockFolder::acquire_timeout(&root, "bar", timeout).unwrap();
+        drop(folder);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn stale() {
+        let root = Folder::new("foo").unwrap();
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "lock::tests::child", "--nocapture"])
+            .env(CHILD, root.path())
+            .status()
+            .unwrap();
+        assert_eq!(status.code(), Some(42));
+        // The child has exited without releasing the lock.
+        let path = root.join("bar");
+        assert!(path.join(super::LOCK).exists());
+
+        let folder = LockFolder::try_acquire(&root, "bar").unwrap();
+        assert_eq!(
+            fs::read_to_string(folder.join("bar.txt")).unwrap(),
+            "Hi there!"
+        );
+        drop(folder);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn child() {
+        let Some(root) = std::env::var_os(CHILD) else {
+            return;
+        };
+        let folder = LockFolder::acquire(root, "bar").unwrap();
+        fs::write(folder.join("bar.txt"), "Hi there!").unwrap();
+        std::process::exit(42);
+    }
+}
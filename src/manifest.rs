@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::{Path, PathBuf};
+
+use crate::Folder;
+
+/// A record of the content of a folder.
+///
+/// Files are recorded with their sizes and the hashes of their contents,
+/// symbolic links with their targets, and folders and special files with
+/// their kinds only. The hash is 64-bit FNV-1a, which is meant for detecting
+/// changes in tests and not for resisting deliberate collisions.
+///
+/// ```rust
+/// use temporary::Folder;
+///
+/// let folder = Folder::with_files("foo", &[("foo.txt", b"Hi there!")]).unwrap();
+/// let manifest = folder.manifest().unwrap();
+/// folder.write("bar.txt", "Bye!").unwrap();
+/// let report = manifest.diff(&folder).unwrap();
+/// assert_eq!(report.added, ["bar.txt"].map(std::path::PathBuf::from));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    entries: BTreeMap<PathBuf, Record>,
+}
+
+/// A report of the differences between a manifest and a folder.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiffReport {
+    /// The entries present in the folder but not in the manifest.
+    pub added: Vec<PathBuf>,
+    /// The entries present in the manifest but not in the folder.
+    pub removed: Vec<PathBuf>,
+    /// The entries whose kinds, sizes, contents, or targets have changed.
+    pub modified: Vec<PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Record {
+    File { size: u64, hash: u64 },
+    Folder,
+    Link(PathBuf),
+    Other,
+}
+
+impl Manifest {
+    /// Record the content of `root`.
+    ///
+    /// Entries are visited in lexicographic order without following symbolic
+    /// links. Entries disappearing during the walk are not recorded, and any
+    /// other failure to read an entry results in an error.
+    pub fn capture<T: AsRef<Path>>(root: T) -> Result<Manifest> {
+        let root = root.as_ref();
+        let mut manifest = Manifest::default();
+        manifest.visit(root, &mut PathBuf::new())?;
+        Ok(manifest)
+    }
+
+    /// Compare the manifest with the current content of `folder`.
+    pub fn diff(&self, folder: &Folder) -> Result<DiffReport> {
+        let current = Manifest::capture(folder)?;
+        let mut report = DiffReport::default();
+        for (path, record) in &self.entries {
+            match current.entries.get(path) {
+                None => report.removed.push(path.clone()),
+                Some(other) if other != record => report.modified.push(path.clone()),
+                _ => {}
+            }
+        }
+        for path in current.entries.keys() {
+            if !self.entries.contains_key(path) {
+                report.added.push(path.clone());
+            }
+        }
+        Ok(report)
+    }
+
+    /// Return the number of recorded entries.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no entries have been recorded.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the relative paths of the recorded entries in order.
+    #[inline]
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.entries.keys().map(PathBuf::as_path)
+    }
+
+    fn visit(&mut self, root: &Path, relative: &mut PathBuf) -> Result<()> {
+        let folder = root.join(&*relative);
+        let context = |path: &Path, error: Error| {
+            Error::new(
+                error.kind(),
+                format!("failed to record {}: {}", path.display(), error),
+            )
+        };
+        let entries = match fs::read_dir(&folder) {
+            Err(error) if error.kind() == ErrorKind::NotFound && folder != root => return Ok(()),
+            entries => entries.map_err(|error| context(&folder, error))?,
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            match entry {
+                Ok(entry) => names.push(entry.file_name()),
+                Err(error) if error.kind() == ErrorKind::NotFound => {}
+                Err(error) => return Err(context(&folder, error)),
+            }
+        }
+        names.sort();
+        for name in names {
+            relative.push(&name);
+            let path = folder.join(&name);
+            if let Some(record) = record(&path).map_err(|error| context(&path, error))? {
+                let nested = record == Record::Folder;
+                self.entries.insert(relative.clone(), record);
+                if nested {
+                    self.visit(root, relative)?;
+                }
+            }
+            relative.pop();
+        }
+        Ok(())
+    }
+}
+
+impl DiffReport {
+    /// Check if there are no differences.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(formatter, "no differences");
+        }
+        let mut first = true;
+        for (label, paths) in [
+            ("added", &self.added),
+            ("removed", &self.removed),
+            ("modified", &self.modified),
+        ] {
+            for path in paths {
+                if !first {
+                    writeln!(formatter)?;
+                }
+                first = false;
+                write!(formatter, "{}: {}", label, path.display())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn record(path: &Path) -> Result<Option<Record>> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error),
+    };
+    let file_type = metadata.file_type();
+    let record = if file_type.is_dir() {
+        Record::Folder
+    } else if file_type.is_symlink() {
+        match fs::read_link(path) {
+            Ok(target) => Record::Link(target),
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        }
+    } else if file_type.is_file() {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        let (size, hash) = hash(file)?;
+        Record::File { size, hash }
+    } else {
+        Record::Other
+    };
+    Ok(Some(record))
+}
+
+// The size is counted while reading so that it matches the hashed content even
+// if the file changes in the meantime.
+fn hash<R: Read>(mut reader: R) -> Result<(u64, u64)> {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let mut buffer = vec![0; 64 * 1024];
+    let (mut size, mut hash) = (0, OFFSET);
+    loop {
+        let count = match reader.read(&mut buffer) {
+            Ok(0) => return Ok((size, hash)),
+            Ok(count) => count,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        for byte in &buffer[..count] {
+            hash = (hash ^ u64::from(*byte)).wrapping_mul(PRIME);
+        }
+        size += count as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::Folder;
+
+    #[test]
+    fn diff() {
+        let folder = Folder::with_files(
+            "foo",
+            &[
+                ("foo.txt", b"Hi there!"),
+                ("bar/baz.txt", b"Bye!"),
+                ("bar/qux.txt", b""),
+            ],
+        )
+        .unwrap();
+        let manifest = folder.manifest().unwrap();
+        let paths = manifest.paths().collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            ["bar", "bar/baz.txt", "bar/qux.txt", "foo.txt"].map(std::path::Path::new),
+        );
+        let report = manifest.diff(&folder).unwrap();
+        assert!(report.is_empty());
+        assert_eq!(report.to_string(), "no differences");
+
+        folder.write("foo.txt", "Hi here!").unwrap();
+        folder.write("bar/qux.txt", "").unwrap();
+        std::fs::remove_file(folder.join("bar/baz.txt")).unwrap();
+        folder.touch("baz/qux.txt").unwrap();
+        let report = manifest.diff(&folder).unwrap();
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(report.added, paths(&["baz", "baz/qux.txt"]));
+        assert_eq!(report.removed, paths(&["bar/baz.txt"]));
+        assert_eq!(report.modified, paths(&["foo.txt"]));
+        assert_eq!(
+            report.to_string().replace('\\', "/"),
+            "added: baz\nadded: baz/qux.txt\nremoved: bar/baz.txt\nmodified: foo.txt",
+        );
+    }
+
+    #[test]
+    fn hash() {
+        assert_eq!(super::hash(&b""[..]).unwrap(), (0, 0xcbf29ce484222325));
+        assert_eq!(super::hash(&b"a"[..]).unwrap(), (1, 0xaf63dc4c8601ec8c));
+    }
+}
@@ -0,0 +1,79 @@
+use std::fs;
+use std::io::Result;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// A read-only memory mapping of a temporary file.
+#[derive(Debug)]
+pub struct Map<'l> {
+    inner: memmap2::Mmap,
+    phantom: PhantomData<&'l crate::File>,
+}
+
+/// A writable memory mapping of a temporary file.
+///
+/// The changes are flushed when the object goes out of scope.
+#[derive(Debug)]
+pub struct MapMut<'l> {
+    inner: memmap2::MmapMut,
+    phantom: PhantomData<&'l mut crate::File>,
+}
+
+impl Map<'_> {
+    pub(crate) fn new(file: &fs::File) -> Result<Self> {
+        Ok(Map {
+            // The file is private to the process as long as the borrow lasts.
+            inner: unsafe { memmap2::Mmap::map(file)? },
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl MapMut<'_> {
+    pub(crate) fn new(file: &fs::File) -> Result<Self> {
+        Ok(MapMut {
+            // The file is private to the process as long as the borrow lasts.
+            inner: unsafe { memmap2::MmapMut::map_mut(file)? },
+            phantom: PhantomData,
+        })
+    }
+
+    /// Flush the changes to disk.
+    #[inline]
+    pub fn flush(&self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Deref for Map<'_> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.inner
+    }
+}
+
+impl Deref for MapMut<'_> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.inner
+    }
+}
+
+impl DerefMut for MapMut<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.inner
+    }
+}
+
+impl Drop for MapMut<'_> {
+    #[allow(unused_must_use)]
+    #[inline]
+    fn drop(&mut self) {
+        self.inner.flush();
+    }
+}
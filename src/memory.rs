@@ -0,0 +1,95 @@
+use std::path::{Path, PathBuf};
+
+use crate::Folder;
+
+/// Create a folder in the first usable RAM-backed candidate, falling back to
+/// the default temporary folder.
+pub fn create<I>(prefix: &std::ffi::OsStr, candidates: I) -> crate::Result<Folder>
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    for candidate in candidates {
+        if !is_ram_backed(&candidate) {
+            continue;
+        }
+        if let Ok(folder) = Folder::with_parent(&candidate, prefix) {
+            return Ok(folder);
+        }
+    }
+    Folder::new(prefix)
+}
+
+/// Return the locations that are typically RAM-backed in order of preference.
+pub fn candidates() -> Vec<PathBuf> {
+    #[allow(unused_mut)]
+    let mut candidates = Vec::new();
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        candidates.push(PathBuf::from("/dev/shm"));
+        if let Some(user) = crate::identity::user() {
+            candidates.push(PathBuf::from(format!("/run/user/{}", user)));
+        }
+        candidates.push(crate::env::default_parent());
+    }
+    candidates
+}
+
+#[cfg(all(any(target_os = "linux", target_os = "android"), feature = "libc"))]
+const TMPFS_MAGIC: u32 = 0x01021994;
+#[cfg(all(any(target_os = "linux", target_os = "android"), feature = "libc"))]
+const RAMFS_MAGIC: u32 = 0x858458f6;
+
+/// Check if a path resides on a file system kept in memory.
+#[cfg(all(any(target_os = "linux", target_os = "android"), feature = "libc"))]
+pub fn is_ram_backed(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut information: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut information) } != 0 {
+        return false;
+    }
+    // The type of the field differs between architectures, and the magic
+    // numbers fit into 32 bits.
+    matches!(information.f_type as u32, TMPFS_MAGIC | RAMFS_MAGIC)
+}
+
+#[cfg(not(all(any(target_os = "linux", target_os = "android"), feature = "libc")))]
+#[inline]
+pub fn is_ram_backed(_: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::Folder;
+
+    #[test]
+    fn in_memory() {
+        let folder = Folder::in_memory("foo").unwrap();
+        #[cfg(target_os = "linux")]
+        if super::is_ram_backed("/dev/shm".as_ref()) {
+            assert!(folder.is_ram_backed());
+            assert!(folder.starts_with("/dev/shm"));
+        }
+        #[cfg(not(target_os = "linux"))]
+        assert!(!folder.is_ram_backed());
+    }
+
+    #[test]
+    fn fallback() {
+        let root = Folder::new("foo").unwrap();
+        let candidates = [root.join("bar"), PathBuf::from("/nonexistent")];
+        let folder = super::create("foo".as_ref(), candidates).unwrap();
+        assert!(!folder.starts_with(&root));
+        assert_eq!(
+            folder.is_ram_backed(),
+            super::is_ram_backed(folder.parent().unwrap()),
+        );
+    }
+}
@@ -0,0 +1,997 @@
+use std::borrow::Cow;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::names::NameGenerator;
+use crate::Builder;
+
+/// A style of the random part of names.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NameStyle {
+    /// Lowercase letters.
+    #[default]
+    Lowercase,
+    /// Lowercase letters, uppercase letters, and digits.
+    ///
+    /// On case-insensitive file systems, the effective number of distinct
+    /// names is smaller.
+    Alphanumeric,
+}
+
+/// A way of creating folders.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Names generated by the crate and created with the standard library.
+    #[default]
+    Portable,
+    /// Names generated and created by `mkdtemp(3)` on Unix.
+    ///
+    /// `mkdtemp` chooses the last six characters of the random part out of
+    /// letters and digits, and the rest is generated by the crate, to which
+    /// deterministic seeds apply. The style has to be
+    /// [`NameStyle::Alphanumeric`], and the random part has to have at least
+    /// six characters. Suffixes are not supported. Folders are created
+    /// private, and permissions given to the builder are applied afterwards.
+    /// Such folders are not recognized by [`Sweep`](crate::Sweep). On other
+    /// platforms or without the `libc` feature, it is the same as
+    /// [`Backend::Portable`].
+    Native,
+}
+
+impl NameStyle {
+    pub(crate) fn charset(&self) -> &'static [u8] {
+        match self {
+            NameStyle::Lowercase => b"abcdefghijklmnopqrstuvwxyz",
+            NameStyle::Alphanumeric => {
+                b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789"
+            }
+        }
+    }
+}
+
+/// Find a vacant path according to `builder` and create an entry there using
+/// `create`.
+///
+/// The closure is called with candidate paths until it succeeds, fails with an
+/// error other than `AlreadyExists`, or the number of retries is exhausted.
+/// Several parents are tried in order as long as the errors suggest that the
+/// parent itself is unusable.
+pub fn create<F, T>(builder: &Builder, mut create: F) -> Result<(PathBuf, T)>
+where
+    F: FnMut(&Path) -> Result<T>,
+{
+    each_parent(builder, |parent| {
+        resolve_in(parent, builder, &mut |parent, prefix, builder| {
+            create_in(parent, prefix, builder, &mut create)
+        })
+    })
+}
+
+/// Create a folder using `mkdtemp`.
+///
+/// The random part has twelve characters, of which `mkdtemp` chooses the last
+/// six, and suffixes are not supported.
+#[cfg(all(unix, feature = "libc"))]
+pub fn mkdtemp(builder: &Builder) -> Result<PathBuf> {
+    each_parent(builder, |parent| {
+        resolve_in(parent, builder, &mut |parent, prefix, builder| {
+            mkdtemp_in(parent, prefix, builder)
+        })
+    })
+}
+
+fn each_parent<F, T>(builder: &Builder, mut f: F) -> Result<T>
+where
+    F: FnMut(&Path) -> Result<T>,
+{
+    match &builder.parents[..] {
+        [] => {
+            let parent = crate::env::default_parent();
+            if parent.as_os_str().is_empty() {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "there is no default temporary folder on this platform; \
+                         set a parent, {}, or TMPDIR",
+                        crate::env::DIR,
+                    ),
+                ));
+            }
+            f(&parent).map_err(|error| {
+                if is_unusable(&error) {
+                    crate::env::unusable(&parent, error)
+                } else {
+                    error
+                }
+            })
+        }
+        [parent] => f(parent),
+        parents => {
+            let mut errors = Vec::with_capacity(parents.len());
+            for parent in parents {
+                match f(parent) {
+                    Ok(result) => return Ok(result),
+                    Err(error) if is_unusable(&error) => errors.push((parent, error)),
+                    Err(error) => return Err(error),
+                }
+            }
+            let kind = errors[errors.len() - 1].1.kind();
+            let errors = errors
+                .iter()
+                .map(|(parent, error)| format!("{} ({})", parent.display(), error))
+                .collect::<Vec<_>>();
+            Err(Error::new(
+                kind,
+                format!("failed to create an entry in {}", errors.join(", ")),
+            ))
+        }
+    }
+}
+
+fn is_unusable(error: &Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::NotFound
+            | ErrorKind::NotADirectory
+            | ErrorKind::PermissionDenied
+            | ErrorKind::ReadOnlyFilesystem
+            | ErrorKind::StorageFull
+    )
+}
+
+/// Validate the prefix and the suffix, prepare the parent, and call `f` with
+/// the absolute parent and the prefix to use.
+fn resolve_in<F, T>(parent: &Path, builder: &Builder, f: &mut F) -> Result<T>
+where
+    F: FnMut(&Path, &OsStr, &Builder) -> Result<T>,
+{
+    let sanitized;
+    let prefix = if builder.sanitize_prefix {
+        sanitized = sanitize_prefix(&builder.prefix);
+        sanitized.as_os_str()
+    } else {
+        // Prefixes may be empty, unlike names.
+        if !builder.prefix.is_empty() {
+            check(&builder.prefix, "prefix")?;
+        }
+        builder.prefix.as_os_str()
+    };
+    let suffix = builder.suffix.as_str();
+    if suffix.chars().any(std::path::is_separator) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("the suffix {:?} contains a path separator", suffix),
+        ));
+    }
+    let prefix = &*fit(prefix, builder)?;
+    let mut parent = absolute(parent)?;
+    if builder.create_parent {
+        create_parent(&parent, builder)?;
+    }
+    inspect_parent(&parent, builder)?;
+    // A custom file system might not be backed by the real one, which the
+    // resolution of links and the query of free space need.
+    let native = builder.filesystem.is_none();
+    if builder.canonicalize && native {
+        // The entry itself is not a symbolic link, and it suffices to resolve
+        // the parent.
+        parent = Cow::Owned(std::fs::canonicalize(&parent)?);
+    }
+    let parent = &*parent;
+    #[cfg(unix)]
+    if builder.secure_parent {
+        check_parent(parent, builder)?;
+    }
+    if let Some(bytes) = builder.free_space.filter(|_| native) {
+        crate::space::require(parent, bytes)?;
+    }
+    f(parent, prefix, builder).map_err(|error| match error.kind() {
+        ErrorKind::PermissionDenied => denied(parent, error),
+        _ => error,
+    })
+}
+
+/// Make the parent absolute and normalize it lexically.
+///
+/// Relative parents are resolved against the current folder, `.` components
+/// are removed, and `..` components remove the preceding ones without
+/// consulting the file system. On Windows, forward slashes are replaced with
+/// backslashes, and parents with the `\\?\` prefix, including `\\?\UNC\`, are
+/// taken as given, since such paths are passed to the system verbatim.
+fn absolute(parent: &Path) -> Result<Cow<'_, Path>> {
+    use std::path::Component;
+
+    #[cfg(windows)]
+    {
+        if crate::verbatim::is_verbatim(parent) {
+            return Ok(Cow::Borrowed(parent));
+        }
+        if let Some(parent) = crate::verbatim::backslashed(parent) {
+            return absolute(&parent).map(|path| Cow::Owned(path.into_owned()));
+        }
+    }
+
+    let dotted = parent
+        .components()
+        .any(|component| matches!(component, Component::CurDir | Component::ParentDir));
+    if parent.is_absolute() && !dotted {
+        return Ok(Cow::Borrowed(parent));
+    }
+    let mut path = if parent.is_absolute() {
+        PathBuf::new()
+    } else {
+        env::current_dir().map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!(
+                    "current directory unavailable while resolving relative parent {} ({})",
+                    parent.display(),
+                    error,
+                ),
+            )
+        })?
+    };
+    for component in parent.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                path.pop();
+            }
+            component => path.push(component),
+        }
+    }
+    Ok(Cow::Owned(path))
+}
+
+/// Check that the parent is a folder so that problems with it are reported as
+/// such rather than as failures to create an entry.
+fn inspect_parent(parent: &Path, builder: &Builder) -> Result<()> {
+    match metadata(parent, builder) {
+        Ok(metadata) if metadata.is_dir() => Ok(()),
+        Ok(_) => Err(Error::new(
+            ErrorKind::NotADirectory,
+            format!(
+                "parent path {} exists but is not a directory",
+                parent.display()
+            ),
+        )),
+        Err(error) => match error.kind() {
+            ErrorKind::NotFound => Err(Error::new(
+                ErrorKind::NotFound,
+                format!("parent path {} does not exist", parent.display()),
+            )),
+            ErrorKind::PermissionDenied => Err(denied(parent, error)),
+            _ => Err(error),
+        },
+    }
+}
+
+fn denied(parent: &Path, error: Error) -> Error {
+    Error::new(
+        ErrorKind::PermissionDenied,
+        format!(
+            "permission denied on parent path {} ({})",
+            parent.display(),
+            error
+        ),
+    )
+}
+
+/// Check that other users cannot tamper with entries in a folder.
+#[cfg(unix)]
+fn check_parent(parent: &Path, builder: &Builder) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = metadata(parent, builder)?;
+    let owner = metadata.uid();
+    if owner != 0 && Some(owner) != crate::identity::user() {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "the parent {} is owned by user {}, which is neither the current user nor root",
+                parent.display(),
+                owner,
+            ),
+        ));
+    }
+    let mode = metadata.mode() & 0o7777;
+    if mode & 0o002 != 0 && mode & 0o1000 == 0 {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "the parent {} is writable by everyone (mode {:o}) without the sticky bit",
+                parent.display(),
+                mode,
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Make sure that names stay within the limit on their length, truncating the
+/// prefix if requested.
+fn fit<'l>(prefix: &'l OsStr, builder: &Builder) -> Result<Cow<'l, OsStr>> {
+    let fixed =
+        builder.random_len + builder.suffix.len() + if builder.timestamped { 16 } else { 0 };
+    let separator = usize::from(!prefix.is_empty());
+    let length = prefix.len() + separator + fixed;
+    if length <= builder.max_name_len {
+        return Ok(Cow::Borrowed(prefix));
+    }
+    let error = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "names with prefix {:?} would have {} bytes, exceeding the limit of {} bytes",
+                prefix, length, builder.max_name_len,
+            ),
+        )
+    };
+    if !builder.truncate_prefix {
+        return Err(error());
+    }
+    let Some(value) = prefix.to_str().filter(|_| fixed <= builder.max_name_len) else {
+        return Err(error());
+    };
+    let mut end = builder.max_name_len.saturating_sub(fixed + 1);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    Ok(Cow::Owned(value[..end].into()))
+}
+
+fn create_in<F, T>(
+    parent: &Path,
+    prefix: &OsStr,
+    builder: &Builder,
+    create: &mut F,
+) -> Result<(PathBuf, T)>
+where
+    F: FnMut(&Path) -> Result<T>,
+{
+    let mut names = NameGenerator::from_builder(parent, prefix, builder);
+    let (head, length) = (names.head(), names.len());
+    let suffix = builder.suffix.as_str();
+    if head.is_empty() && length == 0 && suffix.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "the name would be empty",
+        ));
+    }
+    // The buffers are allocated once, and only the name is rewritten on each
+    // attempt so that retries do not allocate.
+    let mut random = vec![0; length];
+    let mut name = OsString::with_capacity(head.len() + length + suffix.len());
+    let mut path = PathBuf::with_capacity(parent.as_os_str().len() + 1 + name.capacity());
+    path.push(parent);
+    for attempt in 0..builder.retries {
+        names.fill(&mut random);
+        name.clear();
+        name.push(&head);
+        name.push(std::str::from_utf8(&random).expect("the random part should be ASCII"));
+        name.push(suffix);
+        if attempt == 0 {
+            path.push(&name);
+        } else {
+            path.set_file_name(&name);
+        }
+
+        match crate::interrupt::retry(|| create(&path)) {
+            Ok(value) => return Ok((path, value)),
+            Err(error) => match error.kind() {
+                ErrorKind::AlreadyExists => {
+                    if let Some(on_retry) = builder.on_retry {
+                        on_retry(&path, attempt + 1);
+                    }
+                }
+                _ => return Err(error),
+            },
+        }
+    }
+
+    Err(Error::new(
+        ErrorKind::AlreadyExists,
+        format!(
+            "failed to find a vacant name in {} with prefix {:?} after {} attempts",
+            parent.display(),
+            prefix,
+            builder.retries,
+        ),
+    ))
+}
+
+#[cfg(all(unix, feature = "libc"))]
+fn mkdtemp_in(parent: &Path, prefix: &OsStr, builder: &Builder) -> Result<PathBuf> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    if !builder.suffix.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "suffixes are not supported by the native backend",
+        ));
+    }
+    if builder.name_style != NameStyle::Alphanumeric {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "the native backend supports alphanumeric names only",
+        ));
+    }
+    if builder.random_len < 6 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "the native backend requires at least six random characters",
+        ));
+    }
+    // Only the last six characters are replaced on some systems, so the rest
+    // of the random part is filled in beforehand.
+    let mut names = NameGenerator::from_builder(parent, prefix, builder);
+    let mut name = names.head().into_vec();
+    let start = name.len();
+    name.resize(start + builder.random_len - 6, 0);
+    names.fill(&mut name[start..]);
+    name.extend_from_slice(b"XXXXXX");
+    let template = parent.join(OsString::from_vec(name));
+    let context = |error: Error| {
+        Error::new(
+            error.kind(),
+            format!(
+                "failed to create a folder from template {}: {}",
+                template.display(),
+                error,
+            ),
+        )
+    };
+    if template.as_os_str().len() >= libc::PATH_MAX as usize {
+        return Err(context(Error::from_raw_os_error(libc::ENAMETOOLONG)));
+    }
+    let template = CString::new(template.as_os_str().as_bytes())
+        .map_err(|_| context(Error::from(ErrorKind::InvalidInput)))?
+        .into_bytes_with_nul();
+    // The template is modified in place and has to be restored for retries.
+    let mut buffer = crate::interrupt::retry(|| {
+        let mut buffer = template.clone();
+        if unsafe { libc::mkdtemp(buffer.as_mut_ptr().cast()) }.is_null() {
+            return Err(Error::last_os_error());
+        }
+        Ok(buffer)
+    })
+    .map_err(context)?;
+    buffer.pop();
+    Ok(PathBuf::from(OsString::from_vec(buffer)))
+}
+
+/// Check that `name` can be used as a file name within a folder.
+pub fn check(name: &OsStr, what: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("the {} is empty", what),
+        ));
+    }
+    if name == "." || name == ".." || sanitize(name) != name {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("the {} {:?} is not a valid file name", what, name),
+        ));
+    }
+    Ok(())
+}
+
+/// Check if `name` has the shape of the names generated with `prefix`.
+///
+/// The random part is expected to have at least 12 characters, and names with
+/// suffixes do not match.
+pub fn matches(name: &OsStr, prefix: &OsStr) -> bool {
+    let name = name.as_encoded_bytes();
+    let prefix = prefix.as_encoded_bytes();
+    let rest = if prefix.is_empty() {
+        name
+    } else {
+        match name
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix(b"."))
+        {
+            Some(rest) => rest,
+            _ => return false,
+        }
+    };
+    let rest = match rest.split_at_checked(16) {
+        Some((stamp, rest)) if is_timestamp(stamp) => rest,
+        _ => rest,
+    };
+    rest.len() >= 12 && rest.iter().all(u8::is_ascii_alphanumeric)
+}
+
+fn is_timestamp(value: &[u8]) -> bool {
+    value.len() == 16
+        && value[8] == b'T'
+        && value[15] == b'.'
+        && value[..8]
+            .iter()
+            .chain(&value[9..15])
+            .all(u8::is_ascii_digit)
+}
+
+fn sanitize_prefix(prefix: &OsStr) -> OsString {
+    if prefix == "." || prefix == ".." {
+        return "_".repeat(prefix.len()).into();
+    }
+    sanitize(prefix)
+}
+
+#[cfg(unix)]
+fn sanitize(prefix: &OsStr) -> OsString {
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    let bytes = prefix.as_bytes().iter().map(|&unit| match unit {
+        0 | b'/' => b'_',
+        _ => unit,
+    });
+    OsString::from_vec(bytes.collect())
+}
+
+#[cfg(windows)]
+fn sanitize(prefix: &OsStr) -> OsString {
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+    let units = prefix.encode_wide().map(|unit| match unit {
+        0 | 0x2F | 0x5C => 0x5F,
+        _ => unit,
+    });
+    OsString::from_wide(&units.collect::<Vec<_>>())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn sanitize(prefix: &OsStr) -> OsString {
+    prefix.to_string_lossy().replace(['\0', '/'], "_").into()
+}
+
+fn metadata(path: &Path, builder: &Builder) -> Result<std::fs::Metadata> {
+    match &builder.filesystem {
+        Some(filesystem) => filesystem.metadata(path),
+        _ => std::fs::metadata(path),
+    }
+}
+
+fn create_parent(parent: &Path, builder: &Builder) -> Result<()> {
+    if let Some(filesystem) = &builder.filesystem {
+        return create_parent_in(&**filesystem, parent);
+    }
+    let mut options = std::fs::DirBuilder::new();
+    options.recursive(true);
+    #[cfg(unix)]
+    if let Some(permissions) = &builder.permissions {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        options.mode(permissions.mode());
+    }
+    #[cfg(not(unix))]
+    let _ = builder;
+    options.create(parent)
+}
+
+fn create_parent_in(filesystem: &dyn crate::Filesystem, parent: &Path) -> Result<()> {
+    match filesystem.metadata(parent) {
+        Ok(_) => return Ok(()),
+        Err(error) if error.kind() == ErrorKind::NotFound => {}
+        Err(error) => return Err(error),
+    }
+    if let Some(ancestor) = parent.parent() {
+        create_parent_in(filesystem, ancestor)?;
+    }
+    match filesystem.create_dir(parent) {
+        Err(error) if error.kind() == ErrorKind::AlreadyExists => Ok(()),
+        result => result,
+    }
+}
+
+pub fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    // The conversion from days to dates follows Howard Hinnant's algorithm.
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NameStyle;
+    use crate::Builder;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn absolute() {
+        use std::path::Path;
+
+        #[cfg(unix)]
+        let cases = [("/foo/./bar/../baz", "/foo/baz"), ("/foo/bar", "/foo/bar")];
+        #[cfg(windows)]
+        let cases = [
+            (r"\\server\share\foo", r"\\server\share\foo"),
+            (r"\\server\share/foo/bar", r"\\server\share\foo\bar"),
+            (r"//server/share/foo/./bar/..", r"\\server\share\foo"),
+            (r"\\server\share\..\foo", r"\\server\share\foo"),
+            (r"\\?\UNC\server\share\foo", r"\\?\UNC\server\share\foo"),
+            (
+                r"\\?\UNC\server\share\foo\..",
+                r"\\?\UNC\server\share\foo\..",
+            ),
+            (r"C:/foo/./bar", r"C:\foo\bar"),
+        ];
+        #[cfg(not(any(unix, windows)))]
+        let cases: [(&str, &str); 0] = [];
+        for (path, expected) in cases {
+            let path = super::absolute(Path::new(path)).unwrap();
+            assert_eq!(path.as_os_str(), Path::new(expected).as_os_str());
+        }
+    }
+
+    #[test]
+    fn prefix() {
+        let mut builder = Builder::new();
+        builder.parent("/");
+        for prefix in [".", "..", "../foo", "foo/bar", "foo\0bar"] {
+            let error = super::create(builder.prefix(prefix), |_| Ok(())).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+            assert!(error.to_string().contains(&format!("{:?}", prefix)));
+        }
+        let result = super::create(builder.prefix("foo\\bar"), |_| Ok(()));
+        assert_eq!(result.is_err(), cfg!(windows));
+
+        builder.sanitize_prefix(true);
+        for (prefix, expected) in [("..", "__."), ("../foo", ".._foo."), ("a\0b/c", "a_b_c.")] {
+            let (path, _) = super::create(builder.prefix(prefix), |_| Ok(())).unwrap();
+            let name = path.file_name().unwrap().to_str().unwrap();
+            assert!(name.starts_with(expected));
+        }
+    }
+
+    #[test]
+    fn check() {
+        let folder = crate::Folder::new("foo").unwrap();
+        let errors = [
+            folder.named_file("").unwrap_err().into_io(),
+            folder.write_guarded("", b"Hi there!").unwrap_err(),
+            folder.children_with(1, |_| "").unwrap_err(),
+        ];
+        for error in errors {
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+            assert!(error.to_string().contains("the name is empty"));
+        }
+        assert!(folder.is_empty().unwrap());
+    }
+
+    #[test]
+    fn matches() {
+        let mut builder = Builder::new();
+        builder.prefix("foo");
+        let folder = builder.folder().unwrap();
+        assert!(super::matches(folder.file_name().unwrap(), "foo".as_ref()));
+        let folder = builder.timestamped(true).folder().unwrap();
+        assert!(super::matches(folder.file_name().unwrap(), "foo".as_ref()));
+        let folder = builder
+            .name_style(NameStyle::Alphanumeric)
+            .folder()
+            .unwrap();
+        assert!(super::matches(folder.file_name().unwrap(), "foo".as_ref()));
+        for name in [
+            "foo",
+            "foo.",
+            "foo.abcdefghijk",
+            "fooabcdefghijkl",
+            "foo.abcdefghijkl.txt",
+            "foo.abcdefghijkl-",
+            "bar.abcdefghijkl",
+            "foo.bar.abcdefghijkl",
+        ] {
+            assert!(!super::matches(name.as_ref(), "foo".as_ref()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn timestamp() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        for (seconds, expected) in [
+            (0, "19700101T000000"),
+            (951782400, "20000229T000000"),
+            (1718115753, "20240611T142233"),
+            (4102444799, "20991231T235959"),
+        ] {
+            let time = UNIX_EPOCH + Duration::from_secs(seconds);
+            assert_eq!(super::timestamp(time), expected);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secure_parent() {
+        use std::fs::{self, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = crate::Folder::new("foo").unwrap();
+        let mut builder = Builder::new();
+        builder.parent(&root).require_secure_parent(true);
+        assert!(super::create(&builder, |_| Ok(())).is_ok());
+        fs::set_permissions(&root, Permissions::from_mode(0o777)).unwrap();
+        let error = super::create(&builder, |_| Ok(())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+        assert!(error.to_string().contains("mode 777"));
+        fs::set_permissions(&root, Permissions::from_mode(0o1777)).unwrap();
+        assert!(super::create(&builder, |_| Ok(())).is_ok());
+        assert!(super::create(builder.parent("/"), |_| Ok(())).is_ok());
+    }
+
+    #[test]
+    fn fit() {
+        let mut builder = Builder::new();
+        builder.parent("/").prefix("é".repeat(200)).suffix(".gz");
+        let error = super::create(&builder, |_| Ok(())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("416 bytes"));
+
+        builder.truncate_prefix(true);
+        let (path, _) = super::create(&builder, |_| Ok(())).unwrap();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert_eq!(name.len(), 254);
+        assert_eq!(name, format!("{}.{}.gz", "é".repeat(119), &name[239..251]));
+
+        builder.max_name_len(17).prefix("foo");
+        let (path, _) = super::create(&builder, |_| Ok(())).unwrap();
+        assert_eq!(path.file_name().unwrap().len(), 17);
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("f."));
+
+        builder.max_name_len(14);
+        assert!(super::create(&builder, |_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn create() {
+        let mut builder = Builder::new();
+        builder.parent("/").prefix("foo").suffix(".tar.gz");
+        let (path, _) = super::create(&builder, |_| Ok(())).unwrap();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("foo."));
+        assert!(name.ends_with(".tar.gz"));
+        assert_eq!(name.len(), 4 + 12 + 7);
+        assert!(name[4..16].chars().all(|c| c.is_ascii_lowercase()));
+
+        builder
+            .suffix("")
+            .name_style(NameStyle::Alphanumeric)
+            .random_len(32);
+        for _ in 0..10 {
+            let (path, _) = super::create(&builder, |_| Ok(())).unwrap();
+            let name = path.file_name().unwrap().to_str().unwrap();
+            let random = name.strip_prefix("foo.").unwrap();
+            assert_eq!(random.len(), 32);
+            assert!(random.chars().all(|c| c.is_ascii_alphanumeric()));
+        }
+
+        builder.retries(3);
+        let error =
+            super::create(&builder, |_| Err::<(), _>(ErrorKind::AlreadyExists.into())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+        assert!(error.to_string().contains("\"foo\" after 3 attempts"));
+
+        builder.timestamped(true);
+        let (path, _) = super::create(&builder, |_| Ok(())).unwrap();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let parts = name.split('.').collect::<Vec<_>>();
+        assert_eq!(parts.len(), 3);
+        assert_eq!((parts[0], parts[1].len(), parts[2].len()), ("foo", 15, 32));
+        assert_eq!(&parts[1][8..9], "T");
+
+        builder.suffix("a/b");
+        let error = super::create(&builder, |_| Ok(())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        builder
+            .prefix("")
+            .suffix("")
+            .timestamped(false)
+            .random_len(0);
+        let error = super::create(&builder, |_| Ok(())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn on_retry() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+        fn on_retry(path: &std::path::Path, attempt: u32) {
+            assert_eq!(path.parent(), Some(std::path::Path::new("/")));
+            ATTEMPTS.store(attempt, Ordering::SeqCst);
+        }
+
+        let mut builder = Builder::new();
+        builder.parent("/").prefix("foo").on_retry(on_retry);
+        let mut count = 0;
+        super::create(&builder, |_| {
+            count += 1;
+            match count {
+                1..=3 => Err(ErrorKind::AlreadyExists.into()),
+                _ => Ok(()),
+            }
+        })
+        .unwrap();
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn sequence() {
+        let mut builder = Builder::new();
+        builder.parent("/").prefix("foo");
+        let mut names = std::collections::HashSet::new();
+        for _ in 0..1000 {
+            let (path, _) = super::create(&builder, |_| Ok(())).unwrap();
+            assert!(names.insert(path));
+        }
+    }
+
+    #[test]
+    fn deterministic_seed() {
+        let root = crate::Folder::new("foo").unwrap();
+        let mut builder = Builder::new();
+        builder.parent(&root).prefix("bar").deterministic_seed(42);
+        let one = builder.folder().unwrap();
+        let other = builder.folder().unwrap();
+        assert_ne!(one.path(), other.path());
+        let root = crate::Folder::new("foo").unwrap();
+        builder.parent(&root);
+        let folders = [builder.folder().unwrap(), builder.folder().unwrap()];
+        assert_eq!(folders[0].file_name(), one.file_name());
+        assert_eq!(folders[1].file_name(), other.file_name());
+    }
+
+    #[test]
+    fn processes() {
+        const CHILD: &str = "TEMPORARY_TEST_CHILD";
+
+        let mut builder = Builder::new();
+        builder.parent("/").prefix("foo");
+        if std::env::var_os(CHILD).is_some() {
+            let (path, _) = super::create(&builder, |_| Ok(())).unwrap();
+            println!("candidate: {}", path.display());
+            return;
+        }
+        let candidates = (0..2)
+            .map(|_| {
+                let output = std::process::Command::new(std::env::current_exe().unwrap())
+                    .args(["name::tests::processes", "--exact", "--nocapture"])
+                    .env(CHILD, "1")
+                    .output()
+                    .unwrap();
+                let output = String::from_utf8(output.stdout).unwrap();
+                output
+                    .lines()
+                    .find_map(|line| Some(line.split_once("candidate: ")?.1))
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_ne!(candidates[0], candidates[1]);
+    }
+
+    #[test]
+    fn relative() {
+        use std::fs;
+
+        let _lock = crate::cwd::LOCK
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let root = crate::Folder::new("foo").unwrap();
+        let root = fs::canonicalize(&root).unwrap();
+        fs::create_dir_all(root.join("real").join("foo")).unwrap();
+        fs::create_dir(root.join("real").join("bar")).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("real").join("foo"), root.join("link")).unwrap();
+
+        let previous = std::env::current_dir().unwrap();
+        std::env::set_current_dir(root.join("real").join("foo")).unwrap();
+        let one = crate::Folder::with_parent("../bar", "baz");
+        let other = crate::Folder::with_parent("./.", "baz");
+        #[cfg(unix)]
+        let linked = {
+            std::env::set_current_dir(root.join("link")).unwrap();
+            crate::Folder::with_parent("../bar", "baz")
+        };
+        std::env::set_current_dir(previous).unwrap();
+
+        assert_eq!(one.unwrap().parent(), Some(&*root.join("real").join("bar")));
+        assert_eq!(
+            other.unwrap().parent(),
+            Some(&*root.join("real").join("foo"))
+        );
+        // The current folder is reported with symbolic links resolved.
+        #[cfg(unix)]
+        assert_eq!(
+            linked.unwrap().parent(),
+            Some(&*root.join("real").join("bar"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn relative_deleted() {
+        const CHILD: &str = "TEMPORARY_TEST_DELETED";
+
+        if let Some(path) = std::env::var_os(CHILD) {
+            std::fs::create_dir(&path).unwrap();
+            std::env::set_current_dir(&path).unwrap();
+            std::fs::remove_dir(&path).unwrap();
+            let error = crate::Folder::with_parent("foo", "bar").unwrap_err();
+            let message = error.to_string();
+            assert!(
+                message.contains("current directory unavailable"),
+                "{}",
+                message
+            );
+            assert!(message.contains("relative parent foo"), "{}", message);
+            std::process::exit(42);
+        }
+        let root = crate::Folder::new("foo").unwrap();
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["name::tests::relative_deleted", "--exact", "--nocapture"])
+            .env(CHILD, root.join("bar"))
+            .status()
+            .unwrap();
+        assert_eq!(status.code(), Some(42));
+    }
+
+    #[cfg(feature = "os-random")]
+    #[test]
+    fn contention() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let root = crate::Folder::new("foo").unwrap();
+        let attempts = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..16 {
+                scope.spawn(|| {
+                    let mut builder = Builder::new();
+                    builder.parent(&root).prefix("bar");
+                    for _ in 0..16 {
+                        super::create(&builder, |path| {
+                            attempts.fetch_add(1, Ordering::Relaxed);
+                            std::fs::create_dir(path)
+                        })
+                        .unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 256);
+        assert!(attempts.into_inner() < 256 + 8);
+    }
+}
@@ -0,0 +1,363 @@
+//! Generation of unique names.
+//!
+//! The names have the same shape as the ones of temporary folders: the
+//! prefix, a dot, an optional timestamp followed by a dot, a random part, and
+//! a suffix. [`Builder`] relies on the same generator.
+//!
+//! ```rust
+//! use temporary::names::NameGenerator;
+//!
+//! let mut names = NameGenerator::new("job");
+//! names.random_len(8).suffix(".log");
+//! for name in names.take(3) {
+//!     assert!(name.starts_with("job."));
+//!     assert!(name.ends_with(".log"));
+//! }
+//! ```
+//!
+//! Uniqueness is probabilistic only. The random part makes collisions
+//! unlikely, but names are not checked against anything, and callers have to
+//! handle collisions themselves, for instance, by creating entries with
+//! `create_new` and moving on to the next name on `AlreadyExists`.
+
+use std::cell::RefCell;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::rng::Generator;
+use crate::Builder;
+
+pub use crate::name::NameStyle;
+
+/// A generator of unique names.
+///
+/// The iterator never ends. See the [module](self) documentation for details.
+pub struct NameGenerator {
+    prefix: OsString,
+    suffix: String,
+    length: usize,
+    style: NameStyle,
+    timestamped: bool,
+    source: Option<Generator>,
+    salt: u64,
+}
+
+impl NameGenerator {
+    /// Create a generator of names starting from `prefix`.
+    ///
+    /// The prefix is taken as given. The random part has 12 lowercase letters,
+    /// and there is no suffix by default.
+    pub fn new<T: AsRef<str>>(prefix: T) -> NameGenerator {
+        NameGenerator {
+            prefix: prefix.as_ref().into(),
+            suffix: String::new(),
+            length: 12,
+            style: NameStyle::default(),
+            timestamped: false,
+            source: None,
+            salt: 0,
+        }
+    }
+
+    /// Set the style of the random part.
+    #[inline]
+    pub fn style(&mut self, value: NameStyle) -> &mut Self {
+        self.style = value;
+        self
+    }
+
+    /// Set the number of random characters.
+    #[inline]
+    pub fn random_len(&mut self, value: usize) -> &mut Self {
+        self.length = value;
+        self
+    }
+
+    /// Set the suffix appended after the random part.
+    #[inline]
+    pub fn suffix(&mut self, suffix: &str) -> &mut Self {
+        self.suffix = suffix.to_string();
+        self
+    }
+
+    /// Insert the current time in UTC before the random part.
+    #[inline]
+    pub fn timestamped(&mut self, value: bool) -> &mut Self {
+        self.timestamped = value;
+        self
+    }
+
+    /// Produce the same sequence of names for the same seed.
+    ///
+    /// It is meant for reproducible tests.
+    #[inline]
+    pub fn deterministic_seed(&mut self, value: u64) -> &mut Self {
+        self.source = Some(Generator::new(value));
+        self
+    }
+
+    /// Create a generator for one creation of an entry in `parent`.
+    ///
+    /// Unless the seed is deterministic, every draw is mixed with the parent
+    /// and a counter of creations in the process.
+    pub(crate) fn from_builder(parent: &Path, prefix: &OsStr, builder: &Builder) -> NameGenerator {
+        NameGenerator {
+            prefix: prefix.to_os_string(),
+            suffix: builder.suffix.clone(),
+            length: builder.random_len,
+            style: builder.name_style,
+            timestamped: builder.timestamped,
+            source: builder.seed.map(Generator::new),
+            salt: salt(parent),
+        }
+    }
+
+    /// Assemble the prefix and the timestamp, both followed by dots.
+    pub(crate) fn head(&self) -> OsString {
+        let mut head = OsString::with_capacity(self.prefix.len() + 18);
+        if !self.prefix.is_empty() {
+            head.push(&self.prefix);
+            head.push(".");
+        }
+        if self.timestamped {
+            head.push(crate::name::timestamp(SystemTime::now()));
+            head.push(".");
+        }
+        head
+    }
+
+    /// Overwrite `buffer` with random characters.
+    pub(crate) fn fill(&mut self, buffer: &mut [u8]) {
+        let style = self.style;
+        match &mut self.source {
+            Some(source) => fill(buffer, style, source, 0),
+            _ => {
+                let salt = self.salt;
+                SOURCE.with(|source| {
+                    let (process, source) = &mut *source.borrow_mut();
+                    // A forked child inherits the state and would replay the
+                    // names of its parent otherwise.
+                    if *process != process_id() {
+                        *process = process_id();
+                        *source = Generator::new(random_seed());
+                    }
+                    fill(buffer, style, source, salt)
+                })
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.length
+    }
+}
+
+impl std::fmt::Debug for NameGenerator {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("NameGenerator")
+            .field("prefix", &self.prefix)
+            .field("suffix", &self.suffix)
+            .field("random_len", &self.length)
+            .field("style", &self.style)
+            .field("timestamped", &self.timestamped)
+            .field("deterministic", &self.source.is_some())
+            .finish()
+    }
+}
+
+impl Iterator for NameGenerator {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let mut bytes = self.head().into_encoded_bytes();
+        let start = bytes.len();
+        bytes.resize(start + self.length, 0);
+        self.fill(&mut bytes[start..]);
+        bytes.extend_from_slice(self.suffix.as_bytes());
+        // The prefix of a public generator is a string, and the rest is ASCII.
+        Some(String::from_utf8(bytes).expect("the prefix should be a string"))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// Generate a unique name starting from `prefix`.
+///
+/// It is a shortcut for taking one name from [`NameGenerator`], and the same
+/// caveats regarding collisions apply.
+#[inline]
+pub fn unique_name<T: AsRef<str>>(prefix: T) -> String {
+    NameGenerator::new(prefix).next().unwrap()
+}
+
+thread_local! {
+    // The generator is seeded once per thread and process, and consecutive
+    // names never restart the same sequence.
+    static SOURCE: RefCell<(u32, Generator)> =
+        RefCell::new((process_id(), Generator::new(random_seed())));
+}
+
+#[inline]
+fn process_id() -> u32 {
+    // Processes have no identifiers on WASI.
+    #[cfg(not(target_os = "wasi"))]
+    return std::process::id();
+    #[cfg(target_os = "wasi")]
+    return 0;
+}
+
+/// Seed the generator of names.
+///
+/// The seed mixes the process, the thread, the time, the counter of creations,
+/// and the keys the standard library draws for hash maps, so that processes and
+/// threads try different names. With the `os-random` feature, entropy from the
+/// operating system is mixed in as well when available. The parent is mixed
+/// into the draws instead, as the generator is shared by all parents.
+fn random_seed() -> u64 {
+    let seed = mixed_seed();
+    #[cfg(feature = "os-random")]
+    {
+        let mut buffer = [0; 8];
+        if getrandom::fill(&mut buffer).is_ok() {
+            return seed ^ u64::from_ne_bytes(buffer);
+        }
+    }
+    seed
+}
+
+// The number of entries whose creation has started in the process.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Derive the value mixed into the draws of one creation in `parent`.
+fn salt(parent: &Path) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    parent.hash(&mut hasher);
+    COUNTER.fetch_add(1, Ordering::Relaxed).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn mixed_seed() -> u64 {
+    use std::hash::{BuildHasher, Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    process_id().hash(&mut hasher);
+    std::thread::current().id().hash(&mut hasher);
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0)
+        .hash(&mut hasher);
+    COUNTER.load(Ordering::Relaxed).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn fill(buffer: &mut [u8], style: NameStyle, source: &mut Generator, salt: u64) {
+    let charset = style.charset();
+    for byte in buffer {
+        // Mixing in a constant keeps the draws uniform.
+        let value = source.next() ^ salt;
+        *byte = charset[(value % charset.len() as u64) as usize];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NameGenerator, NameStyle};
+
+    #[test]
+    fn generate() {
+        let names = NameGenerator::new("foo").take(100).collect::<Vec<_>>();
+        let unique = names.iter().collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(unique.len(), names.len());
+        for name in &names {
+            assert_eq!(name.len(), 16);
+            assert!(crate::name::matches(name.as_ref(), "foo".as_ref()));
+        }
+
+        let mut names = NameGenerator::new("bar");
+        names
+            .style(NameStyle::Alphanumeric)
+            .random_len(20)
+            .suffix(".txt")
+            .timestamped(true);
+        let name = names.next().unwrap();
+        assert!(name.starts_with("bar.") && name.ends_with(".txt"));
+        assert_eq!(name.len(), 4 + 16 + 20 + 4);
+
+        let name = super::unique_name("");
+        assert_eq!(name.len(), 12);
+        assert!(name.bytes().all(|byte| byte.is_ascii_lowercase()));
+    }
+
+    #[test]
+    fn deterministic_seed() {
+        let sequence = |seed| {
+            let mut names = NameGenerator::new("foo");
+            names.deterministic_seed(seed);
+            names.take(3).collect::<Vec<_>>()
+        };
+        assert_eq!(sequence(42), sequence(42));
+        assert_ne!(sequence(42), sequence(43));
+    }
+
+    #[test]
+    fn salt() {
+        use std::path::Path;
+
+        use crate::rng::Generator;
+
+        let draw = |salt| {
+            let mut buffer = [0; 12];
+            super::fill(
+                &mut buffer,
+                NameStyle::Lowercase,
+                &mut Generator::new(42),
+                salt,
+            );
+            buffer
+        };
+        let parent = Path::new("/foo");
+        let (one, other) = (super::salt(parent), super::salt(parent));
+        assert_ne!(one, other);
+        assert_ne!(draw(one), draw(other));
+        assert_ne!(draw(one), draw(super::salt(Path::new("/bar"))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fork() {
+        use std::io::Read;
+        use std::os::fd::FromRawFd;
+
+        // The generator of the thread is initialized before forking.
+        let _ = super::unique_name("foo");
+        let mut descriptors = [0; 2];
+        assert_eq!(unsafe { libc::pipe(descriptors.as_mut_ptr()) }, 0);
+        let child = unsafe { libc::fork() };
+        assert!(child >= 0);
+        if child == 0 {
+            let name = super::unique_name("foo");
+            unsafe {
+                libc::write(descriptors[1], name.as_ptr().cast(), name.len());
+                libc::_exit(0);
+            }
+        }
+        unsafe { libc::close(descriptors[1]) };
+        let name = super::unique_name("foo");
+        let mut file = unsafe { std::fs::File::from_raw_fd(descriptors[0]) };
+        let mut other = String::new();
+        file.read_to_string(&mut other).unwrap();
+        unsafe { libc::waitpid(child, std::ptr::null_mut(), 0) };
+        assert_eq!(other.len(), name.len());
+        assert_ne!(other, name);
+    }
+}
@@ -0,0 +1,77 @@
+use std::io::Result;
+use std::path::Path;
+
+/// Change the owner and the group of a folder just created.
+///
+/// On Unix, the folder is opened without following symbolic links, and the
+/// change is applied to the descriptor as opposed to the path. It has no effect
+/// elsewhere.
+#[cfg(unix)]
+pub fn set(path: &Path, user: u32, group: u32) -> Result<()> {
+    use std::fs::OpenOptions;
+    use std::io::Error;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::AsRawFd;
+
+    let context = |error: Error| {
+        Error::new(
+            error.kind(),
+            format!(
+                "failed to change the owner of {} to {}:{}: {}",
+                path.display(),
+                user,
+                group,
+                error,
+            ),
+        )
+    };
+    let folder = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECTORY | libc::O_NOFOLLOW)
+        .open(path)
+        .map_err(context)?;
+    if unsafe { libc::fchown(folder.as_raw_fd(), user, group) } < 0 {
+        return Err(context(Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+#[inline]
+pub fn set(_: &Path, _: u32, _: u32) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::ErrorKind;
+    use std::os::unix::fs::MetadataExt;
+
+    use crate::{Builder, Folder};
+
+    #[test]
+    fn set() {
+        let privileged = unsafe { libc::geteuid() } == 0;
+        // Without privileges, handing the folder over to root has to fail.
+        let owner = if privileged { 65534 } else { 0 };
+        let root = Folder::new("foo").unwrap();
+        let result = Builder::new()
+            .parent(&root)
+            .prefix("bar")
+            .owner(owner, owner)
+            .folder();
+        if privileged {
+            let folder = result.unwrap();
+            let metadata = folder.metadata().unwrap();
+            assert_eq!((metadata.uid(), metadata.gid()), (65534, 65534));
+            let path = folder.to_path_buf();
+            drop(folder);
+            assert!(!path.exists());
+        } else {
+            let error = result.unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+            assert!(error.to_string().contains("0:0"));
+            assert!(root.is_empty().unwrap());
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::{ErrorKind, Result};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs};
+
+/// A path removed when the object goes out of scope.
+///
+/// It refers to either a folder or a file. A folder is removed recursively,
+/// and a file is removed as is.
+pub struct TempPath {
+    path: PathBuf,
+    removed: bool,
+}
+
+impl TempPath {
+    /// Take over the responsibility of removing an existing path.
+    #[inline]
+    pub fn from_path<T: Into<PathBuf>>(path: T) -> TempPath {
+        TempPath {
+            path: path.into(),
+            removed: false,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn disarmed(path: PathBuf) -> TempPath {
+        TempPath {
+            path,
+            removed: true,
+        }
+    }
+
+    /// Return the path.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.as_ref()
+    }
+
+    /// Return the path and dispose the object without removing the actual
+    /// folder or file.
+    #[inline]
+    pub fn keep(mut self) -> PathBuf {
+        self.removed = true;
+        std::mem::take(&mut self.path)
+    }
+
+    /// Remove the folder or file.
+    #[inline]
+    pub fn remove(mut self) -> Result<()> {
+        self.cleanup()
+    }
+
+    fn cleanup(&mut self) -> Result<()> {
+        if self.removed {
+            return Ok(());
+        }
+        self.removed = true;
+
+        match fs::symlink_metadata(&self.path) {
+            Ok(metadata) if metadata.is_dir() => {
+                crate::remove::folder(&self.path, &crate::remove::Policy::default())
+            }
+            Ok(_) => fs::remove_file(&self.path),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Debug for TempPath {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.path.fmt(formatter)
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempPath {
+    #[inline]
+    fn drop(&mut self) {
+        if let Err(error) = self.cleanup() {
+            crate::hook::report(&self.path, &error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TempPath;
+    use crate::{File, Folder};
+    use std::fs;
+
+    #[test]
+    fn into_temp_path() {
+        let folder = Folder::new("foo").unwrap();
+        fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+        let path = folder.into_temp_path();
+        assert!(path.is_dir());
+        let copy = path.to_path_buf();
+        drop(path);
+        assert!(fs::metadata(copy).is_err());
+
+        let path = File::new("foo").unwrap().into_temp_path();
+        assert!(path.is_file());
+        let copy = path.keep();
+        assert!(copy.is_file());
+        drop(TempPath::from_path(&copy));
+        assert!(fs::metadata(copy).is_err());
+    }
+
+    #[test]
+    fn send() {
+        fn check<T: Send>() {}
+        check::<TempPath>();
+    }
+}
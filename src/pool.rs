@@ -0,0 +1,157 @@
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::{Folder, Result};
+
+/// A pool of temporary folders.
+///
+/// Folders are checked out using [`get`](FolderPool::get) and are cleared and
+/// returned to the pool when the resulting guard goes out of scope. The pool
+/// can be shared between threads, and the folders are removed when the pool
+/// goes out of scope.
+pub struct FolderPool {
+    prefix: OsString,
+    folders: Mutex<Vec<Folder>>,
+}
+
+/// A folder checked out from a pool.
+pub struct PooledFolder<'l> {
+    folder: Option<Folder>,
+    pool: &'l FolderPool,
+}
+
+impl FolderPool {
+    /// Create a pool with a number of temporary folders.
+    ///
+    /// The folders will have names starting from `prefix`.
+    pub fn new<T: AsRef<OsStr>>(prefix: T, capacity: usize) -> Result<FolderPool> {
+        let prefix = prefix.as_ref().to_os_string();
+        let folders = (0..capacity)
+            .map(|_| Folder::new(&prefix))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(FolderPool {
+            prefix,
+            folders: Mutex::new(folders),
+        })
+    }
+
+    /// Check out a folder.
+    ///
+    /// A new folder is created if the pool is exhausted.
+    pub fn get(&self) -> Result<PooledFolder<'_>> {
+        let folder = match self.lock().pop() {
+            Some(folder) => folder,
+            None => Folder::new(&self.prefix)?,
+        };
+        Ok(PooledFolder {
+            folder: Some(folder),
+            pool: self,
+        })
+    }
+
+    /// Return the number of folders available for checking out.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check if no folders are available for checking out.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Folder>> {
+        self.folders
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl fmt::Debug for FolderPool {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("FolderPool")
+            .field("prefix", &self.prefix)
+            .field("folders", &*self.lock())
+            .finish()
+    }
+}
+
+impl PooledFolder<'_> {
+    /// Return the path to the folder.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.as_ref()
+    }
+}
+
+impl AsRef<Path> for PooledFolder<'_> {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.folder.as_ref().unwrap().path()
+    }
+}
+
+impl fmt::Debug for PooledFolder<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.path().fmt(formatter)
+    }
+}
+
+impl Deref for PooledFolder<'_> {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Drop for PooledFolder<'_> {
+    fn drop(&mut self) {
+        if let Some(folder) = self.folder.take() {
+            // A folder that cannot be cleared is not reused.
+            if folder.clear().is_ok() {
+                self.pool.lock().push(folder);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FolderPool;
+
+    #[test]
+    fn get() {
+        use std::fs;
+
+        let pool = FolderPool::new("foo", 2).unwrap();
+        assert_eq!(pool.len(), 2);
+        let paths = std::thread::scope(|scope| {
+            let handles = (0..3)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let folder = pool.get().unwrap();
+                        fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+                        folder.to_path_buf()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert!(pool.len() >= 2);
+        let folder = pool.get().unwrap();
+        assert!(fs::read_dir(&folder).unwrap().next().is_none());
+        drop(folder);
+        drop(pool);
+        assert!(paths.iter().all(|path| !path.exists()));
+    }
+}
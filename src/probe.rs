@@ -0,0 +1,164 @@
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::Builder;
+
+/// A report of checking that temporary entries can be created in a parent.
+///
+/// ```rust
+/// let report = temporary::probe().unwrap();
+/// assert!(report.is_usable(), "{}", report);
+/// ```
+#[derive(Debug)]
+pub struct ProbeReport {
+    /// The absolute path to the parent that was checked.
+    pub parent: PathBuf,
+    /// Whether a file could be written in a folder created in the parent.
+    pub writable: bool,
+    /// The number of bytes available in the parent if it could be queried.
+    pub free_space: Option<u64>,
+    /// The step that failed and the error it failed with, if any.
+    pub failure: Option<(ProbeStep, Error)>,
+}
+
+/// A step of a probe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProbeStep {
+    /// Creating a folder in the parent.
+    Create,
+    /// Writing a file in the folder.
+    Write,
+    /// Listing the content of the folder.
+    List,
+    /// Removing the folder.
+    Remove,
+}
+
+impl ProbeReport {
+    /// Check if all the steps have succeeded.
+    #[inline]
+    pub fn is_usable(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl fmt::Display for ProbeReport {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}: ", self.parent.display())?;
+        match &self.failure {
+            Some((step, error)) => write!(formatter, "failed to {}: {}", step, error)?,
+            _ => write!(formatter, "usable")?,
+        }
+        if let Some(bytes) = self.free_space {
+            write!(formatter, " ({} bytes free)", bytes)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProbeStep {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            ProbeStep::Create => "create a folder",
+            ProbeStep::Write => "write a file",
+            ProbeStep::List => "list a folder",
+            ProbeStep::Remove => "remove a folder",
+        })
+    }
+}
+
+/// Check that temporary entries can be created in the default parent.
+///
+/// It is [`probe_in`] given [`default_parent`](crate::default_parent).
+#[inline]
+pub fn probe() -> Result<ProbeReport> {
+    probe_in(crate::env::default_parent())
+}
+
+/// Check that temporary entries can be created in `parent`.
+///
+/// A folder is created in the parent, a small file is written in the folder,
+/// the folder is listed, and the folder is removed. The first step to fail is
+/// recorded in the report, and the folder is removed regardless. An error is
+/// returned only if the parent cannot be made absolute.
+pub fn probe_in<T: AsRef<Path>>(parent: T) -> Result<ProbeReport> {
+    let parent = std::path::absolute(parent.as_ref())?;
+    let free_space = crate::space::free_space(&parent).ok();
+    let mut report = ProbeReport {
+        parent,
+        writable: false,
+        free_space,
+        failure: None,
+    };
+    let folder = match Builder::new()
+        .parent(&report.parent)
+        .prefix("probe")
+        .folder()
+    {
+        Ok(folder) => folder,
+        Err(error) => {
+            report.failure = Some((ProbeStep::Create, error.into_io()));
+            return Ok(report);
+        }
+    };
+    let result = folder
+        .write("probe.txt", "Hi there!")
+        .map_err(|error| (ProbeStep::Write, error))
+        .and_then(|_| {
+            report.writable = true;
+            list(folder.path()).map_err(|error| (ProbeStep::List, error))
+        });
+    report.failure = result.err();
+    // A folder that fails to be removed here is tried again when dropped.
+    if let Err(error) = folder.remove() {
+        report
+            .failure
+            .get_or_insert((ProbeStep::Remove, error.into()));
+    }
+    Ok(report)
+}
+
+fn list(path: &Path) -> Result<()> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(path)? {
+        names.push(entry?.file_name());
+    }
+    if names != ["probe.txt"] {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("unexpected content in {}: {:?}", path.display(), names),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProbeStep;
+    use crate::Folder;
+
+    #[test]
+    fn probe() {
+        let report = super::probe().unwrap();
+        assert!(report.is_usable(), "{}", report);
+        assert!(report.writable);
+        assert!(report.parent.is_absolute());
+        #[cfg(any(all(unix, feature = "libc"), windows))]
+        assert!(report.free_space.is_some());
+
+        let folder = Folder::new("foo").unwrap();
+        let report = super::probe_in(folder.join("bar")).unwrap();
+        let (step, error) = report.failure.as_ref().unwrap();
+        assert_eq!(*step, ProbeStep::Create);
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(!report.writable);
+        assert!(report.to_string().contains("failed to create a folder"));
+
+        let report = super::probe_in(&folder).unwrap();
+        assert!(report.is_usable(), "{}", report);
+        assert_eq!(std::fs::read_dir(&folder).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,41 @@
+use std::ffi::{OsStr, OsString};
+use std::sync::{Mutex, OnceLock};
+
+use crate::{Builder, Folder};
+
+static FOLDER: OnceLock<Folder> = OnceLock::new();
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Return the temporary folder of the process creating it if needed.
+pub fn folder(prefix: &OsStr) -> crate::Result<&'static Folder> {
+    if let Some(folder) = FOLDER.get() {
+        return Ok(folder);
+    }
+    // The lock ensures that only one folder is ever created.
+    let _guard = LOCK.lock().unwrap_or_else(|error| error.into_inner());
+    if let Some(folder) = FOLDER.get() {
+        return Ok(folder);
+    }
+    let mut name = OsString::from(prefix);
+    if !name.is_empty() {
+        name.push(".");
+    }
+    name.push(std::process::id().to_string());
+    let folder = Builder::new().prefix(name).folder()?;
+    let folder = FOLDER.get_or_init(|| folder);
+    unsafe { libc::atexit(remove) };
+    Ok(folder)
+}
+
+extern "C" fn remove() {
+    if let Some(folder) = FOLDER.get() {
+        if crate::env::keep() {
+            eprintln!("keeping the temporary folder {}", folder.path().display());
+            return;
+        }
+        if let Err(error) = crate::remove::folder(folder.path(), &crate::remove::Policy::default())
+        {
+            crate::hook::report(folder.path(), &error);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// A usage of a folder relative to its quota.
+///
+/// See [`Folder::set_quota`](crate::Folder::set_quota).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuotaStatus {
+    /// The apparent size of the content of the folder in bytes.
+    pub used: u64,
+    /// The quota in bytes if any.
+    pub limit: Option<u64>,
+}
+
+impl QuotaStatus {
+    /// Return the number of bytes that can still be written if there is a
+    /// quota.
+    #[inline]
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|limit| limit.saturating_sub(self.used))
+    }
+
+    /// Check if the usage is above the quota.
+    #[inline]
+    pub fn is_exceeded(&self) -> bool {
+        self.limit.is_some_and(|limit| self.used > limit)
+    }
+}
+
+/// An error of writing more than the quota of a folder allows.
+///
+/// It is carried by I/O errors of kind [`ErrorKind::QuotaExceeded`] and can be
+/// extracted using [`std::io::Error::get_ref`] and `downcast_ref`.
+#[derive(Clone, Debug)]
+pub struct QuotaExceeded {
+    path: PathBuf,
+    status: QuotaStatus,
+    requested: u64,
+}
+
+impl QuotaExceeded {
+    #[inline]
+    pub(crate) fn new(path: PathBuf, status: QuotaStatus, requested: u64) -> QuotaExceeded {
+        QuotaExceeded {
+            path,
+            status,
+            requested,
+        }
+    }
+
+    /// Return the path that failed to be written.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return the usage of the folder at the time of the write.
+    #[inline]
+    pub fn status(&self) -> QuotaStatus {
+        self.status
+    }
+
+    /// Return the number of bytes that failed to be written.
+    #[inline]
+    pub fn requested(&self) -> u64 {
+        self.requested
+    }
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "writing {} bytes to {} would exceed the quota of {} bytes with {} bytes used",
+            self.requested,
+            self.path.display(),
+            self.status.limit.unwrap_or(0),
+            self.status.used,
+        )
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+impl From<QuotaExceeded> for std::io::Error {
+    #[inline]
+    fn from(error: QuotaExceeded) -> std::io::Error {
+        std::io::Error::new(ErrorKind::QuotaExceeded, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::ErrorKind;
+
+    use super::{QuotaExceeded, QuotaStatus};
+    use crate::Folder;
+
+    #[test]
+    fn write_guarded() {
+        let mut folder = Folder::new("foo").unwrap();
+        folder.write_guarded("foo.txt", &[42; 100]).unwrap();
+        let status = folder.check_quota().unwrap();
+        assert_eq!(
+            status,
+            QuotaStatus {
+                used: 100,
+                limit: None
+            }
+        );
+        assert!(!status.is_exceeded());
+
+        folder.set_quota(150);
+        fs::create_dir(folder.join("bar")).unwrap();
+        fs::write(folder.join("bar").join("bar.txt"), [42; 20]).unwrap();
+        let status = folder.check_quota().unwrap();
+        assert_eq!(status.remaining(), Some(30));
+
+        let path = folder.write_guarded("baz.txt", &[42; 30]).unwrap();
+        assert_eq!(path, folder.join("baz.txt"));
+        let error = folder.write_guarded("qux.txt", &[42; 1]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::QuotaExceeded);
+        let error = error.get_ref().unwrap().downcast_ref::<QuotaExceeded>();
+        assert_eq!(error.unwrap().requested(), 1);
+        assert!(!folder.join("qux.txt").exists());
+
+        // Replacing a file accounts for its current size.
+        folder.write_guarded("foo.txt", &[42; 90]).unwrap();
+        folder.write_guarded("qux.txt", &[42; 10]).unwrap();
+        assert_eq!(folder.check_quota().unwrap().remaining(), Some(0));
+
+        fs::write(folder.join("quux.txt"), [42; 10]).unwrap();
+        assert!(folder.check_quota().unwrap().is_exceeded());
+        folder.clear_quota();
+        assert!(!folder.check_quota().unwrap().is_exceeded());
+        assert!(folder.write_guarded("bar/bar.txt", b"").is_err());
+    }
+}
@@ -0,0 +1,518 @@
+use std::fmt;
+use std::fs::{self, FileType};
+use std::io::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+#[cfg(not(all(any(target_os = "linux", target_os = "macos"), feature = "libc")))]
+use std::{io::ErrorKind, time::Instant};
+
+#[cfg(windows)]
+use crate::disposition::remove as unlink;
+#[cfg(not(windows))]
+use std::fs::remove_file as unlink;
+
+#[cfg(all(any(target_os = "linux", target_os = "macos"), feature = "libc"))]
+mod anchored;
+mod parallel;
+
+pub use parallel::folder as parallel;
+
+/// A way of removing folders.
+#[derive(Clone, Copy, Debug)]
+pub struct Policy {
+    /// The time to spend retrying on transient errors.
+    pub retry: Duration,
+    /// Clear read-only attributes on Windows.
+    pub clear_readonly: bool,
+    /// Repair permissions on Unix.
+    pub force: bool,
+    /// Overwrite the content of files before removing them.
+    pub shred: bool,
+}
+
+impl Default for Policy {
+    #[inline]
+    fn default() -> Self {
+        Policy {
+            retry: Duration::from_millis(1500),
+            clear_readonly: true,
+            force: false,
+            shred: false,
+        }
+    }
+}
+
+/// A report of a removal that failed partially.
+#[derive(Debug, Default)]
+pub struct RemovalReport {
+    /// The entries that failed to be removed along with the errors.
+    pub failed: Vec<(PathBuf, Error)>,
+    /// The number of entries removed.
+    pub removed: usize,
+    /// Whether the folder itself remains.
+    pub remains: bool,
+}
+
+impl fmt::Display for RemovalReport {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for (index, (_, error)) in self.failed.iter().enumerate() {
+            if index > 0 {
+                formatter.write_str("\n")?;
+            }
+            // The errors name the entries.
+            error.fmt(formatter)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RemovalReport {}
+
+/// Remove an entry without following symbolic links.
+///
+/// On Windows, folders that are reparse points acting as links, which are
+/// junctions, directory symbolic links, and mount points, are reported as
+/// links, and the link objects are removed as empty folders without descending
+/// into their targets. Other entries are removed with POSIX semantics where
+/// supported, so that files held open by other processes do not prevent the
+/// removal of the folder containing them.
+pub fn entry(path: &Path, file_type: FileType) -> Result<()> {
+    if file_type.is_dir() {
+        return folder(path, &Policy::default());
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::FileTypeExt;
+
+        if file_type.is_symlink_dir() {
+            return crate::interrupt::retry(|| fs::remove_dir(path));
+        }
+    }
+    crate::interrupt::retry(|| unlink(path))
+}
+
+/// Remove an entry according to `policy`.
+///
+/// Folders are removed as [`folder`] does. Other entries are removed as
+/// [`entry`] does, and the removal is repaired, retried, and preceded by
+/// shredding according to the policy, as it is for the entries inside folders.
+pub fn entry_with(path: &Path, file_type: FileType, policy: &Policy) -> Result<()> {
+    if file_type.is_dir() {
+        return folder(path, policy);
+    }
+    #[cfg(all(any(target_os = "linux", target_os = "macos"), feature = "libc"))]
+    return anchored::entry(path, policy);
+    #[cfg(not(all(any(target_os = "linux", target_os = "macos"), feature = "libc")))]
+    {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut walker = Walker {
+            policy,
+            deadline: Instant::now() + policy.retry,
+            delay: Duration::from_millis(10),
+            exhaustive: false,
+            removed: 0,
+            failed: Vec::new(),
+            unwiped: Vec::new(),
+        };
+        walker.child(parent, path, Ok(file_type))?;
+        unwiped(walker.unwiped)
+    }
+}
+
+/// Remove a folder recursively.
+///
+/// On Windows, entries held open by other processes, such as antivirus
+/// scanners, cannot be removed for a while, and the removal of such entries is
+/// retried with an exponential backoff for up to `policy.retry` in total.
+/// Read-only entries are made writable if `policy.clear_readonly` is set. On
+/// Unix, if `policy.force` is set, permissions of the folder and its content
+/// are extended as needed, and the parent of the folder is never modified. The
+/// error names the entry that failed to be removed.
+///
+/// If `policy.shred` is set, the content of regular files is overwritten with
+/// zeros before they are removed. Failures to overwrite files do not stop the
+/// removal, and they are reported together at the end.
+///
+/// Symbolic links are removed as opposed to being followed, including the
+/// case of `path` itself being a symbolic link, and so are junctions and mount
+/// points on Windows. On Linux and macOS with the `libc` feature, the walk is
+/// anchored to descriptors of the folders visited, so that components of the
+/// path replaced with symbolic links midway are not followed either. On
+/// Windows, the walk uses extended-length paths, which are not limited to
+/// `MAX_PATH` characters, and errors name the entries in the form given.
+pub fn folder(path: &Path, policy: &Policy) -> Result<()> {
+    let verbatim = crate::verbatim::path(path).unwrap_or_else(|_| path.to_path_buf());
+    remove_folder(&verbatim, policy)
+        .map_err(|error| crate::verbatim::restore(error, &verbatim, path))
+}
+
+fn remove_folder(path: &Path, policy: &Policy) -> Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.is_dir() => {
+            return entry(path, metadata.file_type()).map_err(|error| context(error, path));
+        }
+        Err(error) => return Err(context(error, path)),
+        _ => {}
+    }
+    #[cfg(all(any(target_os = "linux", target_os = "macos"), feature = "libc"))]
+    return anchored::folder(path, policy);
+    #[cfg(not(all(any(target_os = "linux", target_os = "macos"), feature = "libc")))]
+    {
+        let mut walker = Walker {
+            policy,
+            deadline: Instant::now() + policy.retry,
+            delay: Duration::from_millis(10),
+            exhaustive: false,
+            removed: 0,
+            failed: Vec::new(),
+            unwiped: Vec::new(),
+        };
+        walker.folder(path, None)?;
+        unwiped(walker.unwiped)
+    }
+}
+
+/// Remove a folder recursively continuing past failures.
+///
+/// It is the same as [`folder`] except that all the entries that failed to be
+/// removed are reported. Folders with such entries are not attempted to be
+/// removed.
+pub fn exhaustive(path: &Path, policy: &Policy) -> RemovalReport {
+    let verbatim = crate::verbatim::path(path).unwrap_or_else(|_| path.to_path_buf());
+    restored(remove_exhaustive(&verbatim, policy), &verbatim, path)
+}
+
+/// Name the entries in the form given as opposed to the extended-length one.
+fn restored(mut report: RemovalReport, verbatim: &Path, original: &Path) -> RemovalReport {
+    if verbatim != original {
+        report.failed = report
+            .failed
+            .into_iter()
+            .map(|(path, error)| {
+                let path = match path.strip_prefix(verbatim) {
+                    Ok(rest) => original.join(rest),
+                    _ => path,
+                };
+                (path, crate::verbatim::restore(error, verbatim, original))
+            })
+            .collect();
+    }
+    report
+}
+
+fn remove_exhaustive(path: &Path, policy: &Policy) -> RemovalReport {
+    let mut report = RemovalReport::default();
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.is_dir() => {
+            match entry(path, metadata.file_type()) {
+                Ok(_) => report.removed += 1,
+                Err(error) => {
+                    report
+                        .failed
+                        .push((path.to_path_buf(), context(error, path)));
+                    report.remains = true;
+                }
+            }
+            return report;
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return report,
+        Err(error) => {
+            report
+                .failed
+                .push((path.to_path_buf(), context(error, path)));
+            report.remains = true;
+            return report;
+        }
+        _ => {}
+    }
+    #[cfg(all(any(target_os = "linux", target_os = "macos"), feature = "libc"))]
+    return anchored::exhaustive(path, policy);
+    #[cfg(not(all(any(target_os = "linux", target_os = "macos"), feature = "libc")))]
+    {
+        let mut walker = Walker {
+            policy,
+            deadline: Instant::now() + policy.retry,
+            delay: Duration::from_millis(10),
+            exhaustive: true,
+            removed: 0,
+            failed: Vec::new(),
+            unwiped: Vec::new(),
+        };
+        match walker.folder(path, None) {
+            Ok(complete) => report.remains = !complete,
+            Err(error) => {
+                walker.failed.push((path.to_path_buf(), error));
+                report.remains = true;
+            }
+        }
+        report.removed = walker.removed;
+        report.failed = walker.failed;
+        report.failed.extend(unwiped_each(walker.unwiped));
+        report
+    }
+}
+
+#[cfg(not(all(any(target_os = "linux", target_os = "macos"), feature = "libc")))]
+struct Walker<'l> {
+    policy: &'l Policy,
+    deadline: Instant,
+    delay: Duration,
+    exhaustive: bool,
+    removed: usize,
+    failed: Vec<(PathBuf, Error)>,
+    unwiped: Vec<(PathBuf, Error)>,
+}
+
+#[cfg(not(all(any(target_os = "linux", target_os = "macos"), feature = "libc")))]
+impl Walker<'_> {
+    /// Remove a folder and return whether it has been removed completely.
+    fn folder(&mut self, path: &Path, parent: Option<&Path>) -> Result<bool> {
+        let targets = repairable(path, &[path]);
+        let Some(entries) = self.attempt(path, &targets, || fs::read_dir(path))? else {
+            return Ok(true);
+        };
+        let mut complete = true;
+        for item in entries {
+            let item = item.map_err(|error| context(error, path))?;
+            let child = item.path();
+            match self.child(path, &child, item.file_type()) {
+                Ok(done) => complete &= done,
+                Err(error) if self.exhaustive => {
+                    self.failed.push((child, error));
+                    complete = false;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        if !complete {
+            return Ok(false);
+        }
+        let targets = match parent {
+            Some(parent) => repairable(path, &[parent]),
+            None => repairable(path, &[]),
+        };
+        if self
+            .attempt(path, &targets, || fs::remove_dir(path))?
+            .is_some()
+        {
+            self.removed += 1;
+        }
+        Ok(true)
+    }
+
+    fn child(&mut self, path: &Path, child: &Path, file_type: Result<FileType>) -> Result<bool> {
+        let file_type = file_type.map_err(|error| context(error, child))?;
+        if file_type.is_dir() {
+            return self.folder(child, Some(path));
+        }
+        if self.policy.shred && file_type.is_file() {
+            if let Err(error) = wipe(child) {
+                self.unwiped.push((child.to_path_buf(), error));
+            }
+        }
+        let targets = repairable(child, &[path]);
+        if self
+            .attempt(child, &targets, || entry(child, file_type))?
+            .is_some()
+        {
+            self.removed += 1;
+        }
+        Ok(true)
+    }
+
+    fn attempt<F, T>(&mut self, path: &Path, targets: &[&Path], mut f: F) -> Result<Option<T>>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let mut repaired = false;
+        loop {
+            let error = match crate::interrupt::retry(&mut f) {
+                Ok(value) => return Ok(Some(value)),
+                // The entry has been removed externally, which is the goal anyway.
+                Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(error) => error,
+            };
+            if error.kind() == ErrorKind::PermissionDenied && !repaired {
+                repaired = true;
+                let mut changed = false;
+                for target in targets {
+                    changed |= self.repair(target)?;
+                }
+                if changed {
+                    continue;
+                }
+            }
+            if is_transient(&error) && Instant::now() + self.delay <= self.deadline {
+                std::thread::sleep(self.delay);
+                self.delay *= 2;
+                continue;
+            }
+            return Err(context(error, path));
+        }
+    }
+
+    #[cfg(windows)]
+    fn repair(&self, path: &Path) -> Result<bool> {
+        if !self.policy.clear_readonly {
+            return Ok(false);
+        }
+        let metadata = fs::symlink_metadata(path).map_err(|error| context(error, path))?;
+        // Setting permissions would affect the target of a symbolic link.
+        if metadata.file_type().is_symlink() || !metadata.permissions().readonly() {
+            return Ok(false);
+        }
+        let mut permissions = metadata.permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(path, permissions).map_err(|error| context(error, path))?;
+        Ok(true)
+    }
+
+    #[cfg(all(
+        unix,
+        not(all(any(target_os = "linux", target_os = "macos"), feature = "libc"))
+    ))]
+    fn repair(&self, path: &Path) -> Result<bool> {
+        use std::os::unix::fs::PermissionsExt;
+
+        if !self.policy.force {
+            return Ok(false);
+        }
+        let metadata = fs::symlink_metadata(path).map_err(|error| context(error, path))?;
+        // Setting permissions would affect the target of a symbolic link.
+        if !metadata.is_dir() {
+            return Ok(false);
+        }
+        let mode = metadata.permissions().mode();
+        if mode & 0o700 == 0o700 {
+            return Ok(false);
+        }
+        fs::set_permissions(path, fs::Permissions::from_mode(mode | 0o700))
+            .map_err(|error| context(error, path))?;
+        Ok(true)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    #[inline]
+    fn repair(&self, _: &Path) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Select the entries whose permissions might prevent an operation on `path`.
+///
+/// On Unix, it is the containing folder, or the folder itself when it is being
+/// read. On Windows, it is the entry itself.
+#[cfg(all(
+    unix,
+    not(all(any(target_os = "linux", target_os = "macos"), feature = "libc"))
+))]
+fn repairable<'l>(_: &'l Path, folders: &[&'l Path]) -> Vec<&'l Path> {
+    folders.to_vec()
+}
+
+#[cfg(not(unix))]
+fn repairable<'l>(path: &'l Path, _: &[&'l Path]) -> Vec<&'l Path> {
+    vec![path]
+}
+
+#[cfg(windows)]
+fn is_transient(error: &Error) -> bool {
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    const ERROR_DIR_NOT_EMPTY: i32 = 145;
+
+    error.kind() == ErrorKind::PermissionDenied
+        || matches!(
+            error.raw_os_error(),
+            Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION | ERROR_DIR_NOT_EMPTY)
+        )
+}
+
+#[cfg(not(any(
+    windows,
+    all(any(target_os = "linux", target_os = "macos"), feature = "libc")
+)))]
+#[inline]
+fn is_transient(_: &Error) -> bool {
+    false
+}
+
+/// Overwrite the content of a file with zeros without following symbolic
+/// links.
+#[cfg(not(all(any(target_os = "linux", target_os = "macos"), feature = "libc")))]
+fn wipe(path: &Path) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        const FILE_FLAG_OPEN_REPARSE_POINT: u32 = 0x00200000;
+        options.custom_flags(FILE_FLAG_OPEN_REPARSE_POINT);
+    }
+    let mut file = options.open(path)?;
+    if !file.metadata()?.is_file() {
+        return Ok(());
+    }
+    overwrite(&mut file)
+}
+
+/// Overwrite the content of an open file with zeros and truncate it.
+///
+/// The content is written in chunks, so that memory usage does not depend on
+/// the size of the file.
+fn overwrite(file: &mut fs::File) -> Result<()> {
+    use std::io::{Seek, SeekFrom, Write};
+
+    static CHUNK: [u8; 64 * 1024] = [0; 64 * 1024];
+
+    let mut length = file.metadata()?.len();
+    file.seek(SeekFrom::Start(0))?;
+    while length > 0 {
+        let size = length.min(CHUNK.len() as u64) as usize;
+        file.write_all(&CHUNK[..size])?;
+        length -= size as u64;
+    }
+    file.sync_all()?;
+    file.set_len(0)
+}
+
+fn unwiped_each(errors: Vec<(PathBuf, Error)>) -> impl Iterator<Item = (PathBuf, Error)> {
+    errors.into_iter().map(|(path, error)| {
+        let error = Error::new(
+            error.kind(),
+            format!("failed to overwrite {}: {}", path.display(), error),
+        );
+        (path, error)
+    })
+}
+
+fn unwiped(errors: Vec<(PathBuf, Error)>) -> Result<()> {
+    let Some((_, last)) = errors.last() else {
+        return Ok(());
+    };
+    let kind = last.kind();
+    let errors = errors
+        .iter()
+        .map(|(path, error)| format!("{} ({})", path.display(), error))
+        .collect::<Vec<_>>();
+    Err(Error::new(
+        kind,
+        format!("failed to overwrite {}", errors.join(", ")),
+    ))
+}
+
+fn context(error: Error, path: &Path) -> Error {
+    // Errors from nested entries already name them.
+    if error.get_ref().is_some() {
+        return error;
+    }
+    Error::new(
+        error.kind(),
+        format!("failed to remove {}: {}", path.display(), error),
+    )
+}
@@ -0,0 +1,413 @@
+use std::ffi::{CStr, CString, OsStr};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use super::{context, overwrite, unwiped, unwiped_each, Policy, RemovalReport};
+
+const FLAGS: libc::c_int = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+
+/// Remove a folder walking it via descriptors of the folders visited.
+///
+/// Every entry is addressed relative to an open descriptor of its folder, and
+/// folders are opened without following symbolic links, so replacing a
+/// component of the path with a symbolic link during the walk cannot redirect
+/// the removal elsewhere.
+pub fn folder(path: &Path, policy: &Policy) -> Result<()> {
+    let mut walker = Walker::new(path, policy, false);
+    walker.root(path)?;
+    unwiped(walker.unwiped)
+}
+
+/// Remove an entry other than a folder via a descriptor of its parent.
+pub fn entry(path: &Path, policy: &Policy) -> Result<()> {
+    let Some(name) = path.file_name() else {
+        return Err(context(Error::from(ErrorKind::InvalidInput), path));
+    };
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut walker = Walker::new(path, policy, false);
+    let Some(descriptor) = walker.open(parent)? else {
+        return Err(context(Error::from(ErrorKind::NotFound), path));
+    };
+    walker.remove(&descriptor, &CString::new(name.as_bytes())?, false)?;
+    unwiped(walker.unwiped)
+}
+
+/// Remove a folder continuing past failures.
+pub fn exhaustive(path: &Path, policy: &Policy) -> RemovalReport {
+    let mut walker = Walker::new(path, policy, true);
+    let remains = match walker.root(path) {
+        Ok(complete) => !complete,
+        Err(error) => {
+            walker.failed.push((path.to_path_buf(), error));
+            true
+        }
+    };
+    let mut failed = walker.failed;
+    failed.extend(unwiped_each(walker.unwiped));
+    RemovalReport {
+        failed,
+        removed: walker.removed,
+        remains,
+    }
+}
+
+struct Walker<'l> {
+    policy: &'l Policy,
+    path: PathBuf,
+    exhaustive: bool,
+    removed: usize,
+    failed: Vec<(PathBuf, Error)>,
+    unwiped: Vec<(PathBuf, Error)>,
+}
+
+impl Walker<'_> {
+    fn new<'l>(path: &Path, policy: &'l Policy, exhaustive: bool) -> Walker<'l> {
+        Walker {
+            policy,
+            path: path.to_path_buf(),
+            exhaustive,
+            removed: 0,
+            failed: Vec::new(),
+            unwiped: Vec::new(),
+        }
+    }
+
+    /// Remove a folder and return whether it has been removed completely.
+    fn root(&mut self, path: &Path) -> Result<bool> {
+        let Some(descriptor) = self.open(path)? else {
+            return Ok(true);
+        };
+        let complete = self.clear(&descriptor)?;
+        drop(descriptor);
+        if !complete {
+            return Ok(false);
+        }
+        match crate::interrupt::retry(|| fs::remove_dir(path)) {
+            Ok(_) => self.removed += 1,
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => return Err(context(error, path)),
+        }
+        Ok(true)
+    }
+
+    fn open(&self, path: &Path) -> Result<Option<OwnedFd>> {
+        let name = CString::new(path.as_os_str().as_bytes())?;
+        let mut repaired = false;
+        loop {
+            match crate::interrupt::retry(|| {
+                descriptor(unsafe { libc::open(name.as_ptr(), FLAGS) })
+            }) {
+                Ok(descriptor) => return Ok(Some(descriptor)),
+                Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(error)
+                    if error.kind() == ErrorKind::PermissionDenied
+                        && self.policy.force
+                        && !repaired =>
+                {
+                    repaired = true;
+                    // The parent is not open, and the folder is repaired by path.
+                    let metadata = fs::symlink_metadata(path).map_err(|e| context(e, path))?;
+                    if !metadata.is_dir() {
+                        return Err(context(error, path));
+                    }
+                    let mode = metadata.permissions().mode();
+                    fs::set_permissions(path, fs::Permissions::from_mode(mode | 0o700))
+                        .map_err(|error| context(error, path))?;
+                }
+                Err(error) => return Err(context(error, path)),
+            }
+        }
+    }
+
+    fn clear(&mut self, directory: &OwnedFd) -> Result<bool> {
+        let mut complete = true;
+        for (name, is_dir) in self.list(directory)? {
+            self.path.push(OsStr::from_bytes(name.to_bytes()));
+            let result = self.remove(directory, &name, is_dir);
+            match result {
+                Ok(done) => complete &= done,
+                Err(error) if self.exhaustive => {
+                    self.failed.push((self.path.clone(), error));
+                    complete = false;
+                }
+                Err(error) => {
+                    self.path.pop();
+                    return Err(error);
+                }
+            }
+            self.path.pop();
+        }
+        Ok(complete)
+    }
+
+    fn remove(&mut self, parent: &OwnedFd, name: &CStr, is_dir: bool) -> Result<bool> {
+        if is_dir {
+            let Some(child) = self.attempt(parent, Some(name), || {
+                descriptor(unsafe { libc::openat(parent.as_raw_fd(), name.as_ptr(), FLAGS) })
+            })?
+            else {
+                return Ok(true);
+            };
+            let complete = self.clear(&child)?;
+            drop(child);
+            if !complete {
+                return Ok(false);
+            }
+        } else if self.policy.shred {
+            if let Err(error) = wipe(parent, name) {
+                self.unwiped.push((self.path.clone(), error));
+            }
+        }
+        let flags = if is_dir { libc::AT_REMOVEDIR } else { 0 };
+        let removed = self.attempt(parent, None, || {
+            check(unsafe { libc::unlinkat(parent.as_raw_fd(), name.as_ptr(), flags) })
+        })?;
+        if removed.is_some() {
+            self.removed += 1;
+        }
+        Ok(true)
+    }
+
+    fn list(&self, directory: &OwnedFd) -> Result<Vec<(CString, bool)>> {
+        let duplicate = descriptor(unsafe { libc::dup(directory.as_raw_fd()) })
+            .map_err(|error| context(error, &self.path))?;
+        let stream = unsafe { libc::fdopendir(duplicate.as_raw_fd()) };
+        if stream.is_null() {
+            return Err(context(Error::last_os_error(), &self.path));
+        }
+        // The stream owns the descriptor from now on.
+        std::mem::forget(duplicate);
+        let mut entries = Vec::new();
+        let result = loop {
+            unsafe { *errno() = 0 };
+            let entry = unsafe { libc::readdir(stream) };
+            if entry.is_null() {
+                let error = Error::last_os_error();
+                break match error.raw_os_error() {
+                    Some(0) => Ok(()),
+                    _ => Err(error),
+                };
+            }
+            let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) };
+            if matches!(name.to_bytes(), b"." | b"..") {
+                continue;
+            }
+            let kind = unsafe { (*entry).d_type };
+            entries.push((name.to_owned(), kind));
+        };
+        unsafe { libc::closedir(stream) };
+        result.map_err(|error| context(error, &self.path))?;
+        let mut output = Vec::with_capacity(entries.len());
+        for (name, kind) in entries {
+            let is_dir = match kind {
+                libc::DT_DIR => true,
+                libc::DT_UNKNOWN => match status(directory, &name) {
+                    Ok(status) => status.st_mode & libc::S_IFMT == libc::S_IFDIR,
+                    Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                    Err(error) => {
+                        let path = self.path.join(OsStr::from_bytes(name.to_bytes()));
+                        return Err(context(error, &path));
+                    }
+                },
+                _ => false,
+            };
+            output.push((name, is_dir));
+        }
+        Ok(output)
+    }
+
+    fn attempt<F, T>(&self, parent: &OwnedFd, child: Option<&CStr>, mut f: F) -> Result<Option<T>>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let mut repaired = false;
+        loop {
+            let error = match crate::interrupt::retry(&mut f) {
+                Ok(value) => return Ok(Some(value)),
+                // The entry has been removed externally, which is the goal anyway.
+                Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(error) => error,
+            };
+            if error.kind() == ErrorKind::PermissionDenied && self.policy.force && !repaired {
+                repaired = true;
+                if self.repair(parent, child)? {
+                    continue;
+                }
+            }
+            return Err(context(error, &self.path));
+        }
+    }
+
+    fn repair(&self, parent: &OwnedFd, child: Option<&CStr>) -> Result<bool> {
+        let mut changed = false;
+        let mode = status(parent, c".")
+            .map_err(|error| context(error, &self.path))?
+            .st_mode;
+        if mode & 0o700 != 0o700 {
+            check(unsafe { libc::fchmod(parent.as_raw_fd(), (mode | 0o700) & 0o7777) })
+                .map_err(|error| context(error, &self.path))?;
+            changed = true;
+        }
+        if let Some(child) = child {
+            let mode = status(parent, child)
+                .map_err(|error| context(error, &self.path))?
+                .st_mode;
+            // Setting permissions would affect the target of a symbolic link.
+            if mode & libc::S_IFMT == libc::S_IFDIR && mode & 0o700 != 0o700 {
+                let mode = (mode | 0o700) & 0o7777;
+                check(unsafe { libc::fchmodat(parent.as_raw_fd(), child.as_ptr(), mode, 0) })
+                    .map_err(|error| context(error, &self.path))?;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+fn wipe(parent: &OwnedFd, name: &CStr) -> Result<()> {
+    if status(parent, name)?.st_mode & libc::S_IFMT != libc::S_IFREG {
+        return Ok(());
+    }
+    // Opening a special file that has replaced the regular one must not block.
+    let flags = libc::O_WRONLY | libc::O_NOFOLLOW | libc::O_NONBLOCK | libc::O_CLOEXEC;
+    let descriptor = descriptor(unsafe { libc::openat(parent.as_raw_fd(), name.as_ptr(), flags) })?;
+    let mut file = fs::File::from(descriptor);
+    if !file.metadata()?.is_file() {
+        return Ok(());
+    }
+    overwrite(&mut file)
+}
+
+fn status(directory: &OwnedFd, name: &CStr) -> Result<libc::stat> {
+    let mut status = std::mem::MaybeUninit::uninit();
+    check(unsafe {
+        libc::fstatat(
+            directory.as_raw_fd(),
+            name.as_ptr(),
+            status.as_mut_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    })?;
+    Ok(unsafe { status.assume_init() })
+}
+
+fn descriptor(value: libc::c_int) -> Result<OwnedFd> {
+    check(value).map(|value| unsafe { OwnedFd::from_raw_fd(value) })
+}
+
+fn check(value: libc::c_int) -> Result<libc::c_int> {
+    if value < 0 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(value)
+    }
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn errno() -> *mut libc::c_int {
+    libc::__errno_location()
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn errno() -> *mut libc::c_int {
+    libc::__error()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::os::unix::ffi::OsStrExt;
+
+    use crate::remove::Policy;
+    use crate::Folder;
+
+    #[test]
+    fn deep() {
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.join("foo");
+        fs::create_dir(&path).unwrap();
+        {
+            let _lock = crate::cwd::LOCK.lock().unwrap();
+            let previous = std::env::current_dir().unwrap();
+            std::env::set_current_dir(&path).unwrap();
+            // The total length exceeds what paths can have.
+            for _ in 0..200 {
+                let name = "a".repeat(32);
+                fs::create_dir(&name).unwrap();
+                std::env::set_current_dir(&name).unwrap();
+            }
+            fs::write("foo.txt", "Hi there!").unwrap();
+            std::env::set_current_dir(previous).unwrap();
+        }
+        super::folder(&path, &Policy::default()).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let other = Folder::new("bar").unwrap();
+        fs::create_dir(other.join("bar")).unwrap();
+        fs::write(other.join("bar").join("bar.txt"), "Hi there!").unwrap();
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.join("foo");
+        fs::create_dir(&path).unwrap();
+        symlink(other.join("bar"), path.join("bar")).unwrap();
+        symlink(other.path(), path.join("baz")).unwrap();
+        super::folder(&path, &Policy::default()).unwrap();
+        assert!(!path.exists());
+        assert!(other.join("bar").join("bar.txt").is_file());
+
+        symlink(other.join("bar"), &path).unwrap();
+        assert!(super::folder(&path, &Policy::default()).is_err());
+        assert!(other.join("bar").join("bar.txt").is_file());
+    }
+
+    #[test]
+    fn shred() {
+        use std::os::unix::fs::symlink;
+
+        let other = Folder::new("bar").unwrap();
+        fs::write(other.join("bar.txt"), "Hi there!").unwrap();
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.join("foo");
+        fs::create_dir_all(path.join("bar")).unwrap();
+        fs::write(path.join("foo.txt"), vec![42; 200 * 1024]).unwrap();
+        fs::write(path.join("bar").join("bar.txt"), "Hi there!").unwrap();
+        symlink(other.join("bar.txt"), path.join("baz.txt")).unwrap();
+        let policy = Policy {
+            shred: true,
+            ..Policy::default()
+        };
+        super::folder(&path, &policy).unwrap();
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(other.join("bar.txt")).unwrap(),
+            "Hi there!"
+        );
+    }
+
+    #[test]
+    fn names() {
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.join("foo");
+        fs::create_dir(&path).unwrap();
+        let names: &[&[u8]] = &[b"\xff\xfe", b"a\nb", b" ", b"-", b"\\", b"..."];
+        for name in names {
+            let name = OsStr::from_bytes(name);
+            fs::create_dir(path.join(name)).unwrap();
+            fs::write(path.join(name).join(name), "Hi there!").unwrap();
+        }
+        super::folder(&path, &Policy::default()).unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+use std::fs::{self, FileType};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{Policy, RemovalReport};
+
+/// The number of entries below which spawning threads does not pay off.
+const THRESHOLD: usize = 1024;
+
+/// The number of entries a thread takes at a time.
+const CHUNK: usize = 64;
+
+/// Remove a folder using several threads continuing past failures.
+///
+/// The folder is walked breadth-first without following symbolic links, the
+/// entries other than folders are distributed among the threads, and the
+/// folders are then removed bottom-up. Small folders are removed sequentially,
+/// and so is whatever remains after a failure, so that the entries that cannot
+/// be removed are reported as with [`exhaustive`](super::exhaustive).
+pub fn folder(path: &Path, policy: &Policy, threads: usize) -> RemovalReport {
+    if threads <= 1 || policy.shred {
+        return super::exhaustive(path, policy);
+    }
+    let verbatim = crate::verbatim::path(path).unwrap_or_else(|_| path.to_path_buf());
+    super::restored(remove(&verbatim, policy, threads), &verbatim, path)
+}
+
+fn remove(path: &Path, policy: &Policy, threads: usize) -> RemovalReport {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => {}
+        _ => return super::exhaustive(path, policy),
+    }
+    let (folders, entries, complete) = walk(path);
+    if entries.len() < THRESHOLD {
+        return super::exhaustive(path, policy);
+    }
+    let next = AtomicUsize::new(0);
+    let removed = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(usize::from(!complete));
+    std::thread::scope(|scope| {
+        for _ in 0..threads.min(entries.len().div_ceil(CHUNK)) {
+            scope.spawn(|| loop {
+                let start = next.fetch_add(CHUNK, Ordering::Relaxed);
+                let Some(chunk) = entries.get(start..entries.len().min(start + CHUNK)) else {
+                    break;
+                };
+                if chunk.is_empty() {
+                    break;
+                }
+                for (path, file_type) in chunk {
+                    match super::entry(path, *file_type) {
+                        Ok(_) => removed.fetch_add(1, Ordering::Relaxed),
+                        Err(error) if error.kind() == ErrorKind::NotFound => 0,
+                        Err(_) => failed.fetch_add(1, Ordering::Relaxed),
+                    };
+                }
+            });
+        }
+    });
+    let mut removed = removed.into_inner();
+    if failed.into_inner() == 0 {
+        // Children are listed after their parents.
+        for folder in folders.iter().rev() {
+            match fs::remove_dir(folder) {
+                Ok(_) => removed += 1,
+                Err(error) if error.kind() == ErrorKind::NotFound => {}
+                Err(_) => break,
+            }
+        }
+    }
+    let mut report = RemovalReport {
+        removed,
+        ..RemovalReport::default()
+    };
+    if fs::symlink_metadata(path).is_ok() {
+        let rest = super::exhaustive(path, policy);
+        report.removed += rest.removed;
+        report.failed = rest.failed;
+        report.remains = rest.remains;
+    }
+    report
+}
+
+/// List the folders breadth-first and the other entries.
+///
+/// The last value tells whether all the folders could be read.
+fn walk(path: &Path) -> (Vec<PathBuf>, Vec<(PathBuf, FileType)>, bool) {
+    let mut folders = vec![path.to_path_buf()];
+    let mut entries = Vec::new();
+    let mut complete = true;
+    let mut queue = VecDeque::from([0]);
+    while let Some(index) = queue.pop_front() {
+        let Ok(items) = fs::read_dir(&folders[index]) else {
+            complete = false;
+            continue;
+        };
+        for item in items {
+            let Ok((child, file_type)) = item.and_then(|item| Ok((item.path(), item.file_type()?)))
+            else {
+                complete = false;
+                continue;
+            };
+            // The type of the entry itself is reported, so links are not
+            // followed.
+            if file_type.is_dir() {
+                queue.push_back(folders.len());
+                folders.push(child);
+            } else {
+                entries.push((child, file_type));
+            }
+        }
+    }
+    (folders, entries, complete)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::remove::Policy;
+    use crate::Folder;
+
+    #[test]
+    fn folder() {
+        let other = Folder::new("bar").unwrap();
+        other.write("bar/bar.txt", "Hi there!").unwrap();
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.join("foo");
+        for i in 0..20 {
+            for j in 0..100 {
+                fs::create_dir_all(path.join(format!("{i}")).join(format!("{}", j % 3))).unwrap();
+                let name = format!("{i}/{}/{j}.txt", j % 3);
+                fs::write(path.join(name), "Hi there!").unwrap();
+            }
+        }
+        fs::create_dir(path.join("empty")).unwrap();
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(other.join("bar"), path.join("bar")).unwrap();
+            std::os::unix::fs::symlink(other.path(), path.join("0").join("baz")).unwrap();
+        }
+        #[cfg(windows)]
+        {
+            crate::junction::create(&other.join("bar"), &path.join("bar")).unwrap();
+            crate::junction::create(other.path(), &path.join("0").join("baz")).unwrap();
+        }
+
+        let report = super::folder(&path, &Policy::default(), 4);
+        assert!(report.failed.is_empty());
+        assert!(!report.remains);
+        #[cfg(any(unix, windows))]
+        assert_eq!(report.removed, 2000 + 20 * 3 + 20 + 2 + 2);
+        assert!(!path.exists());
+        assert!(other.join("bar").join("bar.txt").is_file());
+
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("foo.txt"), "Hi there!").unwrap();
+        let report = super::folder(&path, &Policy::default(), 4);
+        assert_eq!(report.removed, 2);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn folder_failed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.join("foo");
+        for i in 0..2000 {
+            let name = format!("{}/{i}.txt", i % 2);
+            fs::create_dir_all(path.join(format!("{}", i % 2))).unwrap();
+            fs::write(path.join(name), "Hi there!").unwrap();
+        }
+        let locked = path.join("1");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o500)).unwrap();
+        let report = super::folder(&path, &Policy::default(), 4);
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o700)).unwrap();
+        assert!(report.remains);
+        assert_eq!(report.failed.len(), 1000);
+        assert!(!path.join("0").exists());
+        assert_eq!(fs::read_dir(&locked).unwrap().count(), 1000);
+    }
+}
@@ -0,0 +1,151 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::Result;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use crate::{Builder, TempPath};
+
+/// A vacant path reserved for an entry to be created by someone else.
+///
+/// Nothing is created at the path, which is useful for tools that insist on
+/// creating files or folders themselves, such as databases or child processes
+/// writing to a given output folder. Whatever ends up at the path, a file, a
+/// folder, or a link, is removed when the object goes out of scope.
+///
+/// The name is only known to be vacant at the moment of reservation: another
+/// process can take it before the entry is created, which is inherent to
+/// handing out paths without creating anything. Random names make collisions
+/// unlikely but do not protect against malicious actors with access to the
+/// parent, so parents writable by others should be avoided.
+pub struct ReservedPath {
+    path: TempPath,
+}
+
+impl ReservedPath {
+    #[inline]
+    pub(crate) fn new(path: PathBuf) -> ReservedPath {
+        ReservedPath {
+            path: TempPath::from_path(path),
+        }
+    }
+
+    /// Return the path.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.as_ref()
+    }
+
+    /// Check if something has been created at the path.
+    ///
+    /// Links are not followed, so a dangling link counts as created.
+    #[inline]
+    pub fn materialized(&self) -> bool {
+        std::fs::symlink_metadata(&*self.path).is_ok()
+    }
+
+    /// Return the path and dispose the object without removing anything.
+    #[inline]
+    pub fn release(self) -> PathBuf {
+        self.path.keep()
+    }
+
+    /// Remove whatever has been created at the path.
+    #[inline]
+    pub fn remove(self) -> Result<()> {
+        self.path.remove()
+    }
+}
+
+impl AsRef<Path> for ReservedPath {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Debug for ReservedPath {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.path.fmt(formatter)
+    }
+}
+
+impl Deref for ReservedPath {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Reserve a vacant path in `parent` with a name starting from `prefix`.
+///
+/// See [`ReservedPath`] for details.
+///
+/// ```rust
+/// let root = temporary::Folder::new("foo").unwrap();
+/// let path = temporary::reserve(&root, "bar").unwrap();
+/// assert!(!path.materialized());
+/// std::fs::create_dir(&path).unwrap();
+/// assert!(path.materialized());
+/// ```
+#[inline]
+pub fn reserve<T: AsRef<Path>, U: AsRef<OsStr>>(
+    parent: T,
+    prefix: U,
+) -> crate::Result<ReservedPath> {
+    Builder::new().parent(parent).prefix(prefix).reserve()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{Builder, Folder};
+
+    #[test]
+    fn reserve() {
+        let root = Folder::new("foo").unwrap();
+
+        let path = super::reserve(&root, "bar").unwrap();
+        assert_eq!(path.parent(), Some(root.path()));
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("bar."));
+        assert!(!path.materialized());
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("bar.txt"), "Hi there!").unwrap();
+        assert!(path.materialized());
+        let copy = path.to_path_buf();
+        drop(path);
+        assert!(!copy.exists());
+
+        let path = super::reserve(&root, "bar").unwrap();
+        fs::write(&path, "Hi there!").unwrap();
+        let copy = path.release();
+        assert!(copy.is_file());
+
+        let path = super::reserve(&root, "bar").unwrap();
+        path.remove().unwrap();
+    }
+
+    #[test]
+    fn reserve_taken() {
+        let root = Folder::new("foo").unwrap();
+        let mut builder = Builder::new();
+        builder
+            .parent(&root)
+            .prefix("bar")
+            .deterministic_seed(42)
+            .retries(1);
+        let path = builder.reserve().unwrap().release();
+        fs::create_dir(&path).unwrap();
+        let error = builder.reserve().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(error.operation(), crate::Operation::ReservePath);
+    }
+}
@@ -0,0 +1,68 @@
+/// A generator of the random parts of names.
+///
+/// With the `random` feature, it is the default generator of the `random`
+/// crate. Otherwise, it is SplitMix64 relying on nothing but the standard
+/// library.
+#[cfg(feature = "random")]
+pub struct Generator(random::Default);
+
+#[cfg(not(feature = "random"))]
+pub struct Generator(u64);
+
+impl Generator {
+    /// Create a generator with a seed.
+    #[cfg(feature = "random")]
+    #[inline]
+    pub fn new(seed: u64) -> Generator {
+        Generator(random::default(seed))
+    }
+
+    #[cfg(not(feature = "random"))]
+    #[inline]
+    pub fn new(seed: u64) -> Generator {
+        Generator(seed)
+    }
+
+    /// Produce the next number.
+    #[cfg(feature = "random")]
+    #[inline]
+    pub fn next(&mut self) -> u64 {
+        use random::Source;
+
+        self.0.read::<u64>()
+    }
+
+    #[cfg(not(feature = "random"))]
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut value = self.0;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+        value ^ (value >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Generator;
+
+    #[test]
+    fn distribution() {
+        const COUNT: usize = 26 * 1000;
+
+        let mut generator = Generator::new(42);
+        let mut counts = [0usize; 26];
+        for _ in 0..COUNT {
+            counts[(generator.next() % 26) as usize] += 1;
+        }
+        for count in counts {
+            assert!((800..1200).contains(&count), "{:?}", counts);
+        }
+
+        let mut one = Generator::new(42);
+        let mut other = Generator::new(42);
+        assert!((0..10).all(|_| one.next() == other.next()));
+        let mut other = Generator::new(43);
+        assert!((0..10).any(|_| one.next() != other.next()));
+    }
+}
@@ -0,0 +1,110 @@
+//! Serialization and deserialization.
+//!
+//! [`Folder`] and [`TempPath`] are serialized as their paths. They are not
+//! deserialized directly, since that would make it easy to arm the removal
+//! of an arbitrary path inadvertently. Instead, [`Adopt`] has to be asked for
+//! explicitly:
+//!
+//! ```rust
+//! use temporary::serde::Adopt;
+//! use temporary::Folder;
+//!
+//! let folder = Folder::new("foo").unwrap();
+//! let state = serde_json::to_string(&folder).unwrap();
+//! let path = folder.into_path();
+//!
+//! // The folder gets removed when `folder` goes out of scope.
+//! let Adopt(folder) = serde_json::from_str::<Adopt<Folder>>(&state).unwrap();
+//! assert_eq!(folder.path(), path);
+//! ```
+//!
+//! ```compile_fail
+//! use temporary::Folder;
+//!
+//! let folder: Folder = serde_json::from_str("\"/etc\"").unwrap();
+//! ```
+
+use std::path::PathBuf;
+
+use ::serde::de::{Deserialize, Deserializer, Error};
+use ::serde::ser::{Serialize, Serializer};
+
+use crate::{Folder, TempPath};
+
+/// A value deserialized from a path with the responsibility of removing it.
+///
+/// Folders are checked to exist and to be folders.
+#[derive(Debug)]
+pub struct Adopt<T>(pub T);
+
+impl<T> Adopt<T> {
+    /// Return the value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for Adopt<Folder> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let path = PathBuf::deserialize(deserializer)?;
+        Folder::adopt(path).map(Adopt).map_err(D::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Adopt<TempPath> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PathBuf::deserialize(deserializer).map(|path| Adopt(TempPath::from_path(path)))
+    }
+}
+
+impl Serialize for Folder {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.path().serialize(serializer)
+    }
+}
+
+impl Serialize for TempPath {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.path().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Adopt;
+    use crate::{Folder, TempPath};
+
+    #[test]
+    fn folder() {
+        let folder = Folder::new("foo").unwrap();
+        fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+        let state = serde_json::to_string(&folder).unwrap();
+        let path = folder.into_path();
+        let Adopt(folder) = serde_json::from_str::<Adopt<Folder>>(&state).unwrap();
+        assert_eq!(folder.path(), path);
+        assert_eq!(serde_json::to_string(&folder).unwrap(), state);
+        drop(folder);
+        assert!(!path.exists());
+
+        let error = serde_json::from_str::<Adopt<Folder>>(&state).unwrap_err();
+        assert!(error.to_string().contains("failed to adopt"));
+    }
+
+    #[test]
+    fn temp_path() {
+        let folder = Folder::new("foo").unwrap();
+        let path = TempPath::from_path(folder.join("foo.txt"));
+        fs::write(&path, "Hi there!").unwrap();
+        let state = serde_json::to_string(&path).unwrap();
+        let path = path.keep();
+        let Adopt(other) = serde_json::from_str::<Adopt<TempPath>>(&state).unwrap();
+        assert_eq!(other.path(), path);
+        drop(other);
+        assert!(!path.exists());
+    }
+}
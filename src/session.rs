@@ -0,0 +1,386 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::identity::Expected;
+use crate::remove::Policy;
+use crate::{Builder, File, Folder, RemovalReport};
+
+/// A group of temporary resources sharing a lifetime.
+///
+/// Folders and files created via the session, or via a [`Builder`] given the
+/// session with [`Builder::session`], are removed in the reverse order of
+/// creation when the session goes out of scope or is closed. Resources that
+/// are disposed otherwise, such as via `into_path` or `remove`, or kept via
+/// `keep` leave the session, and the session never removes them.
+///
+/// ```rust
+/// use temporary::Session;
+///
+/// let session = Session::new();
+/// let downloads = session.folder("downloads").unwrap();
+/// let scratch = session.file("scratch").unwrap();
+/// let paths = [downloads.to_path_buf(), scratch.path().to_path_buf()];
+/// session.close().unwrap();
+/// assert!(paths.iter().all(|path| !path.exists()));
+/// ```
+///
+/// The resources themselves can still be used after the session has removed
+/// them, but there is nothing left behind them. On Windows, files that are
+/// open cannot be removed, and they should go out of scope before the session.
+#[derive(Debug, Default)]
+pub struct Session {
+    handle: Handle,
+}
+
+/// A reference to the registry of a session.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Handle(Arc<Mutex<Registry>>);
+
+/// A record of a resource in a session.
+///
+/// The record is removed from the registry when the object goes out of scope.
+#[derive(Debug)]
+pub(crate) struct Membership {
+    handle: Handle,
+    id: u64,
+    path: PathBuf,
+    kind: Kind,
+    expected: Expected,
+    policy: Policy,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Kind {
+    File,
+    Folder,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    next: u64,
+    kept: bool,
+    entries: BTreeMap<u64, (PathBuf, Kind, Expected, Policy)>,
+}
+
+impl Session {
+    /// Start an empty session.
+    #[inline]
+    pub fn new() -> Session {
+        Session::default()
+    }
+
+    /// Create a temporary folder belonging to the session.
+    #[inline]
+    pub fn folder<T: AsRef<std::ffi::OsStr>>(&self, prefix: T) -> crate::Result<Folder> {
+        Builder::new().prefix(prefix).session(self).folder()
+    }
+
+    /// Create a temporary file belonging to the session.
+    #[inline]
+    pub fn file<T: AsRef<std::ffi::OsStr>>(&self, prefix: T) -> crate::Result<File> {
+        Builder::new().prefix(prefix).session(self).file()
+    }
+
+    /// Return the number of resources belonging to the session.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.handle.lock().entries.len()
+    }
+
+    /// Check if no resources belong to the session.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.handle.lock().entries.is_empty()
+    }
+
+    /// Prevent all the resources belonging to the session from being removed.
+    ///
+    /// Neither the session nor the resources themselves remove anything once
+    /// it has been called, including resources created afterwards, which is
+    /// useful for inspecting them after a failure. The paths to the resources
+    /// are returned in the order of creation.
+    pub fn keep_all(&self) -> Vec<PathBuf> {
+        let mut registry = self.handle.lock();
+        registry.kept = true;
+        registry
+            .entries
+            .values()
+            .map(|(path, ..)| path.clone())
+            .collect()
+    }
+
+    /// Remove all the resources belonging to the session.
+    ///
+    /// Removal continues past failures, and the resulting report lists all the
+    /// entries that remain.
+    #[inline]
+    pub fn close(mut self) -> std::result::Result<(), RemovalReport> {
+        self.cleanup()
+    }
+
+    fn cleanup(&mut self) -> std::result::Result<(), RemovalReport> {
+        let entries = {
+            let mut registry = self.handle.lock();
+            if registry.kept {
+                return Ok(());
+            }
+            std::mem::take(&mut registry.entries)
+        };
+        let mut report = RemovalReport::default();
+        for (path, kind, expected, policy) in entries.into_values().rev() {
+            if crate::env::keep() {
+                eprintln!("keeping the temporary {} {}", kind, path.display());
+                continue;
+            }
+            // Resources are removed only if they are still the ones created.
+            match expected.verify(&path) {
+                Ok(Ok(_)) => {}
+                Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                Ok(Err(error)) | Err(error) => {
+                    report.failed.push((path, error));
+                    report.remains = true;
+                    continue;
+                }
+            }
+            match kind {
+                Kind::Folder => {
+                    let other = crate::remove::exhaustive(&path, &policy);
+                    report.removed += other.removed;
+                    report.remains |= other.remains;
+                    report.failed.extend(other.failed);
+                }
+                Kind::File => match crate::interrupt::retry(|| fs::remove_file(&path)) {
+                    Ok(_) => report.removed += 1,
+                    Err(error) if error.kind() == ErrorKind::NotFound => {}
+                    Err(error) => {
+                        let error = Error::new(
+                            error.kind(),
+                            format!("failed to remove {}: {}", path.display(), error),
+                        );
+                        report.failed.push((path, error));
+                        report.remains = true;
+                    }
+                },
+            }
+        }
+        if report.failed.is_empty() {
+            Ok(())
+        } else {
+            Err(report)
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Err(report) = self.cleanup() {
+            for (path, error) in &report.failed {
+                crate::hook::report(path, error);
+            }
+        }
+    }
+}
+
+impl Handle {
+    #[inline]
+    pub(crate) fn of(session: &Session) -> Handle {
+        session.handle.clone()
+    }
+
+    /// Register a resource, which is removed with `policy` only if it is still
+    /// as expected.
+    pub(crate) fn join(
+        &self,
+        path: &Path,
+        kind: Kind,
+        expected: Expected,
+        policy: Policy,
+    ) -> Membership {
+        let mut registry = self.lock();
+        let id = registry.next;
+        registry.next += 1;
+        registry
+            .entries
+            .insert(id, (path.to_path_buf(), kind, expected, policy));
+        Membership {
+            handle: self.clone(),
+            id,
+            path: path.to_path_buf(),
+            kind,
+            expected,
+            policy,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Registry> {
+        self.0.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl Membership {
+    /// Check if the session keeps its resources.
+    #[inline]
+    pub(crate) fn kept(&self) -> bool {
+        self.handle.lock().kept
+    }
+
+    /// Leave the session for the session not to remove the resource.
+    #[inline]
+    pub(crate) fn leave(&self) {
+        self.handle.lock().entries.remove(&self.id);
+    }
+
+    /// Rejoin the session at the original position.
+    #[inline]
+    pub(crate) fn rejoin(&self) {
+        let mut registry = self.handle.lock();
+        let entry = (self.path.clone(), self.kind, self.expected, self.policy);
+        registry.entries.insert(self.id, entry);
+    }
+}
+
+impl Drop for Membership {
+    #[inline]
+    fn drop(&mut self) {
+        self.leave();
+    }
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Kind::File => formatter.write_str("file"),
+            Kind::Folder => formatter.write_str("folder"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Session;
+    use crate::Builder;
+
+    #[test]
+    fn close() {
+        let session = Session::new();
+        let one = session.folder("foo").unwrap();
+        // The folders are removed in reverse order, so the nested one first.
+        let other = Builder::new()
+            .parent(&one)
+            .prefix("bar")
+            .session(&session)
+            .folder()
+            .unwrap();
+        other.write("bar.txt", "Hi there!").unwrap();
+        let file = session.file("baz").unwrap();
+        let detached = session.folder("qux").unwrap().into_path();
+        assert_eq!(session.len(), 3);
+
+        let dropped = session.folder("qux").unwrap();
+        let path = dropped.to_path_buf();
+        drop(dropped);
+        assert!(!path.exists());
+        assert_eq!(session.len(), 3);
+
+        let paths = [one.to_path_buf(), other.to_path_buf(), file.to_path_buf()];
+        session.close().unwrap();
+        assert!(paths.iter().all(|path| !path.exists()));
+        assert!(detached.exists());
+        std::fs::remove_dir(detached).unwrap();
+        drop((one, other, file));
+    }
+
+    #[test]
+    fn replaced() {
+        use std::fs;
+
+        let root = crate::Folder::new("foo").unwrap();
+        let session = Session::new();
+        let mut folder = Builder::new()
+            .parent(&root)
+            .prefix("bar")
+            .session(&session)
+            .folder()
+            .unwrap();
+        let path = folder.to_path_buf();
+        // The original folder is kept aside for its identity not to be reused.
+        fs::rename(&path, root.join("baz")).unwrap();
+        fs::create_dir(&path).unwrap();
+        let report = session.close().unwrap_err();
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, path);
+        assert!(path.exists());
+        folder.keep();
+    }
+
+    #[test]
+    fn replaced_file() {
+        use std::fs;
+
+        let root = crate::Folder::new("foo").unwrap();
+        let session = Session::new();
+        let file = Builder::new()
+            .parent(&root)
+            .prefix("bar")
+            .session(&session)
+            .file()
+            .unwrap();
+        let path = file.to_path_buf();
+        // The original file is kept aside for its identity not to be reused.
+        fs::rename(&path, root.join("baz")).unwrap();
+        fs::write(&path, "Hi there!").unwrap();
+        let report = session.close().unwrap_err();
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Hi there!");
+        drop(file);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn policy() {
+        use std::fs;
+
+        let links = crate::Folder::new("foo").unwrap();
+        let session = Session::new();
+        let folder = Builder::new()
+            .prefix("bar")
+            .shred(true)
+            .session(&session)
+            .folder()
+            .unwrap();
+        let path = folder.write("baz.txt", "Hi there!").unwrap();
+        fs::hard_link(&path, links.join("baz.txt")).unwrap();
+        session.close().unwrap();
+        assert!(!folder.exists());
+        assert_eq!(fs::metadata(links.join("baz.txt")).unwrap().len(), 0);
+        drop(folder);
+    }
+
+    #[test]
+    fn keep_all() {
+        let session = Session::new();
+        let folder = session.folder("foo").unwrap();
+        let mut kept = session.folder("bar").unwrap();
+        kept.keep();
+        let file = session.file("baz").unwrap();
+        let paths = session.keep_all();
+        assert_eq!(paths, [folder.to_path_buf(), file.to_path_buf()]);
+        let later = session.folder("qux").unwrap();
+        let path = later.to_path_buf();
+        drop((folder, file, later));
+        drop(session);
+        for path in paths.iter().chain([&path]) {
+            assert!(path.exists());
+        }
+        let kept = kept.into_path();
+        assert!(kept.exists());
+        for path in paths.iter().chain([&path, &kept]) {
+            let _ = std::fs::remove_dir(path);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
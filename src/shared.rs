@@ -0,0 +1,269 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use crate::Folder;
+
+/// A temporary folder with several owners.
+///
+/// Clones refer to the same folder, which is removed exactly once, when the
+/// last clone goes out of scope, unless it has been removed via
+/// [`remove`](SharedFolder::remove) or [`remove_now`](SharedFolder::remove_now)
+/// or kept via [`keep`](SharedFolder::keep) before. The object can be sent to
+/// and shared between threads.
+///
+/// ```rust
+/// use temporary::Folder;
+///
+/// let folder = Folder::new("foo").unwrap().shared();
+/// let other = folder.clone();
+/// std::thread::spawn(move || assert!(other.exists())).join().unwrap();
+/// assert!(folder.exists());
+/// ```
+pub struct SharedFolder {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    folder: Folder,
+    clones: Mutex<usize>,
+    released: Condvar,
+}
+
+impl SharedFolder {
+    /// Create a temporary folder with several owners.
+    #[inline]
+    pub fn new<T: AsRef<OsStr>>(prefix: T) -> crate::Result<SharedFolder> {
+        Folder::new(prefix).map(SharedFolder::from)
+    }
+
+    /// Return the path to the folder.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.inner.folder.path()
+    }
+
+    /// Check if the folder has been removed.
+    ///
+    /// The state is the same for all clones.
+    #[inline]
+    pub fn is_removed(&self) -> bool {
+        self.inner.folder.is_removed()
+    }
+
+    /// Prevent the folder from being removed when the last clone goes out of
+    /// scope.
+    ///
+    /// The state is the same for all clones.
+    #[inline]
+    pub fn keep(&self) {
+        self.inner.folder.keep_shared();
+    }
+
+    /// Remove the folder without waiting for other clones to go out of scope.
+    ///
+    /// The folder is removed at most once regardless of the number of calls
+    /// made by different clones. In case of failure, the removal is attempted
+    /// again when the last clone goes out of scope.
+    #[inline]
+    pub fn remove(&self) -> Result<()> {
+        self.inner.folder.cleanup()
+    }
+
+    /// Remove the folder provided that no other clones are alive.
+    ///
+    /// If other clones are alive, an error of kind `ResourceBusy` is returned
+    /// unless `wait` is set, in which case the call blocks until they have
+    /// gone out of scope. Waiting for clones held by the calling thread never
+    /// ends. Otherwise, it is the same as [`remove`](SharedFolder::remove).
+    pub fn remove_now(&self, wait: bool) -> Result<()> {
+        let mut clones = self.inner.clones();
+        if *clones > 1 && !wait {
+            return Err(Error::new(
+                ErrorKind::ResourceBusy,
+                format!(
+                    "failed to remove {}: {} other clones are alive",
+                    self.path().display(),
+                    *clones - 1,
+                ),
+            ));
+        }
+        while *clones > 1 {
+            clones = self
+                .inner
+                .released
+                .wait(clones)
+                .unwrap_or_else(|error| error.into_inner());
+        }
+        self.inner.folder.cleanup()
+    }
+}
+
+impl Inner {
+    fn clones(&self) -> MutexGuard<'_, usize> {
+        self.clones
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl Clone for SharedFolder {
+    fn clone(&self) -> SharedFolder {
+        *self.inner.clones() += 1;
+        SharedFolder {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl Drop for SharedFolder {
+    fn drop(&mut self) {
+        // The folder itself is removed when the last reference goes away.
+        *self.inner.clones() -= 1;
+        self.inner.released.notify_all();
+    }
+}
+
+impl From<Folder> for SharedFolder {
+    #[inline]
+    fn from(folder: Folder) -> SharedFolder {
+        SharedFolder {
+            inner: Arc::new(Inner {
+                folder,
+                clones: Mutex::new(1),
+                released: Condvar::new(),
+            }),
+        }
+    }
+}
+
+impl AsRef<Path> for SharedFolder {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl fmt::Debug for SharedFolder {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.path().fmt(formatter)
+    }
+}
+
+impl Deref for SharedFolder {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        self.path()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::sync::{Arc, Barrier};
+    use std::time::Duration;
+
+    use crate::{Folder, SharedFolder};
+
+    const fn assert_shareable<T: Send + Sync>() {}
+    const _: () = assert_shareable::<Folder>();
+    const _: () = assert_shareable::<SharedFolder>();
+
+    #[test]
+    fn drop() {
+        let folder = Folder::new("foo").unwrap().shared();
+        let path = folder.to_path_buf();
+        std::fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+        let handles = (0..4)
+            .map(|_| {
+                let folder = folder.clone();
+                std::thread::spawn(move || assert!(folder.join("foo.txt").exists()))
+            })
+            .collect::<Vec<_>>();
+        std::mem::drop(folder);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn remove() {
+        let folder = Folder::new("foo").unwrap().shared();
+        let other = folder.clone();
+        assert!(!other.is_removed());
+        let handles = (0..4)
+            .map(|_| {
+                let folder = folder.clone();
+                std::thread::spawn(move || folder.remove())
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+        assert!(other.is_removed());
+        assert!(!other.exists());
+    }
+
+    #[test]
+    fn race() {
+        for _ in 0..10 {
+            let folder = SharedFolder::new("foo").unwrap();
+            let path = folder.to_path_buf();
+            let barrier = Arc::new(Barrier::new(8));
+            let handles = (0..8)
+                .map(|i| {
+                    let folder = folder.clone();
+                    let barrier = barrier.clone();
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                        for j in 0..10 {
+                            let other = folder.clone();
+                            std::fs::write(other.join(format!("{i}.{j}.txt")), "Hi there!")
+                                .unwrap();
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            std::mem::drop(folder);
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            assert!(!path.exists());
+        }
+    }
+
+    #[test]
+    fn remove_now() {
+        let folder = Folder::new("foo").unwrap().into_shared();
+        let other = folder.clone();
+        let error = folder.remove_now(false).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ResourceBusy);
+        assert!(folder.exists());
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            std::fs::write(other.join("foo.txt"), "Hi there!").unwrap();
+        });
+        folder.remove_now(true).unwrap();
+        assert!(folder.is_removed());
+        assert!(!folder.exists());
+        handle.join().unwrap();
+        folder.remove_now(false).unwrap();
+    }
+
+    #[test]
+    fn keep() {
+        let folder = SharedFolder::new("foo").unwrap();
+        let other = folder.clone();
+        other.keep();
+        let path = folder.to_path_buf();
+        std::mem::drop((folder, other));
+        assert!(path.exists());
+        std::fs::remove_dir(path).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+use std::io::Result;
+
+/// Remove temporary folders when the process is interrupted.
+///
+/// Handlers of `SIGINT` and `SIGTERM` on Unix and a console control handler
+/// on Windows are installed. Once a signal arrives, the folders created with
+/// [`Builder::register_for_exit`](crate::Builder::register_for_exit) that are
+/// still around are removed, and the signal is passed to the handler
+/// previously installed by the application or, if there is none, to the
+/// default one, which terminates the process. Signals ignored by the
+/// application stay ignored. The removal itself happens on a dedicated thread,
+/// and the signal handler only uses operations that are safe in that context.
+/// Calling the function more than once has no effect.
+#[inline]
+pub fn cleanup_on_interrupt() -> Result<()> {
+    platform::install()
+}
+
+pub use platform::masked;
+
+#[cfg(unix)]
+mod platform {
+    use std::io::{Error, Result};
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::sync::{Mutex, OnceLock};
+
+    const SIGNALS: [libc::c_int; 2] = [libc::SIGINT, libc::SIGTERM];
+
+    type Handler = extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void);
+
+    // The handler asks the thread to remove the folders and waits for the reply.
+    static REQUEST: AtomicI32 = AtomicI32::new(-1);
+    static REPLY: AtomicI32 = AtomicI32::new(-1);
+    static PREVIOUS: OnceLock<[libc::sigaction; 2]> = OnceLock::new();
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    pub fn install() -> Result<()> {
+        let _guard = LOCK.lock().unwrap_or_else(|error| error.into_inner());
+        if PREVIOUS.get().is_some() {
+            return Ok(());
+        }
+        let (request, requested) = pipe()?;
+        let (replied, reply) = pipe()?;
+        std::thread::Builder::new()
+            .name("temporary".into())
+            .spawn(move || serve(requested, replied))?;
+        REQUEST.store(request, Ordering::SeqCst);
+        REPLY.store(reply, Ordering::SeqCst);
+        let mut previous: [libc::sigaction; 2] = unsafe { std::mem::zeroed() };
+        for (signal, previous) in SIGNALS.iter().zip(previous.iter_mut()) {
+            check(unsafe { libc::sigaction(*signal, std::ptr::null(), previous) })?;
+        }
+        let _ = PREVIOUS.set(previous);
+        for (signal, previous) in SIGNALS.iter().zip(PREVIOUS.get().unwrap()) {
+            if previous.sa_sigaction == libc::SIG_IGN {
+                continue;
+            }
+            let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+            action.sa_sigaction = handle as Handler as libc::sighandler_t;
+            action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+            unsafe { libc::sigemptyset(&mut action.sa_mask) };
+            check(unsafe { libc::sigaction(*signal, &action, std::ptr::null_mut()) })?;
+        }
+        Ok(())
+    }
+
+    /// Run a closure with the signals blocked on the current thread.
+    pub fn masked<F: FnOnce() -> T, T>(f: F) -> T {
+        let mut previous = unsafe { std::mem::zeroed() };
+        unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set(), &mut previous) };
+        let value = f();
+        unsafe { libc::pthread_sigmask(libc::SIG_SETMASK, &previous, std::ptr::null_mut()) };
+        value
+    }
+
+    fn serve(requested: libc::c_int, replied: libc::c_int) {
+        // The handler must not run on the thread it is waiting for.
+        unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set(), std::ptr::null_mut()) };
+        let mut buffer = [0u8; 1];
+        loop {
+            match unsafe { libc::read(requested, buffer.as_mut_ptr().cast(), 1) } {
+                1 => {}
+                -1 if Error::last_os_error().kind() == std::io::ErrorKind::Interrupted => continue,
+                _ => return,
+            }
+            crate::exit::clear();
+            unsafe { libc::write(replied, buffer.as_ptr().cast(), 1) };
+        }
+    }
+
+    extern "C" fn handle(
+        signal: libc::c_int,
+        info: *mut libc::siginfo_t,
+        context: *mut libc::c_void,
+    ) {
+        let request = REQUEST.load(Ordering::SeqCst);
+        let reply = REPLY.load(Ordering::SeqCst);
+        let mut buffer = [0u8; 1];
+        if unsafe { libc::write(request, buffer.as_ptr().cast(), 1) } == 1 {
+            while unsafe { libc::read(reply, buffer.as_mut_ptr().cast(), 1) } < 0
+                && Error::last_os_error().kind() == std::io::ErrorKind::Interrupted
+            {}
+        }
+        let Some(previous) = PREVIOUS.get() else {
+            return;
+        };
+        let previous = match signal {
+            libc::SIGINT => &previous[0],
+            _ => &previous[1],
+        };
+        match previous.sa_sigaction {
+            libc::SIG_DFL => unsafe {
+                libc::sigaction(signal, previous, std::ptr::null_mut());
+                // The signal is blocked until the handler returns.
+                libc::raise(signal);
+            },
+            libc::SIG_IGN => {}
+            handler if previous.sa_flags & libc::SA_SIGINFO != 0 => unsafe {
+                let handler: Handler = std::mem::transmute(handler);
+                handler(signal, info, context);
+            },
+            handler => unsafe {
+                let handler: extern "C" fn(libc::c_int) = std::mem::transmute(handler);
+                handler(signal);
+            },
+        }
+    }
+
+    fn set() -> libc::sigset_t {
+        let mut set = unsafe { std::mem::zeroed() };
+        unsafe { libc::sigemptyset(&mut set) };
+        for signal in SIGNALS {
+            unsafe { libc::sigaddset(&mut set, signal) };
+        }
+        set
+    }
+
+    fn pipe() -> Result<(libc::c_int, libc::c_int)> {
+        let mut descriptors = [0; 2];
+        check(unsafe { libc::pipe(descriptors.as_mut_ptr()) })?;
+        for descriptor in descriptors {
+            check(unsafe { libc::fcntl(descriptor, libc::F_SETFD, libc::FD_CLOEXEC) })?;
+        }
+        Ok((descriptors[1], descriptors[0]))
+    }
+
+    fn check(value: libc::c_int) -> Result<()> {
+        if value < 0 {
+            Err(Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::io::{Error, Result};
+    use std::sync::Mutex;
+
+    static INSTALLED: Mutex<bool> = Mutex::new(false);
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(
+            handler: Option<unsafe extern "system" fn(u32) -> i32>,
+            add: i32,
+        ) -> i32;
+    }
+
+    pub fn install() -> Result<()> {
+        let mut installed = INSTALLED.lock().unwrap_or_else(|error| error.into_inner());
+        if *installed {
+            return Ok(());
+        }
+        if unsafe { SetConsoleCtrlHandler(Some(handle), 1) } == 0 {
+            return Err(Error::last_os_error());
+        }
+        *installed = true;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn masked<F: FnOnce() -> T, T>(f: F) -> T {
+        f()
+    }
+
+    // Console handlers run on a thread of their own and are chained by the
+    // system, so returning false passes the event to the next one.
+    unsafe extern "system" fn handle(_: u32) -> i32 {
+        crate::exit::clear();
+        0
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::io::Result;
+
+    #[inline]
+    pub fn install() -> Result<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn masked<F: FnOnce() -> T, T>(f: F) -> T {
+        f()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::Builder;
+
+    static HANDLED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn handle(_: libc::c_int) {
+        HANDLED.store(true, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn cleanup_on_interrupt() {
+        let _lock = crate::exit::LOCK.lock().unwrap();
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(libc::SIGTERM, &action, std::ptr::null_mut());
+        }
+        super::cleanup_on_interrupt().unwrap();
+        super::cleanup_on_interrupt().unwrap();
+        let folder = Builder::new()
+            .prefix("foo")
+            .register_for_exit(true)
+            .folder()
+            .unwrap();
+        std::fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+        unsafe { libc::raise(libc::SIGTERM) };
+        assert!(HANDLED.load(Ordering::SeqCst));
+        assert!(!folder.exists());
+    }
+}
@@ -0,0 +1,137 @@
+use std::io::Result;
+use std::path::Path;
+
+/// Return the number of bytes available to the current user on the file
+/// system containing `parent`.
+///
+/// It is `statvfs(3)` on Unix and `GetDiskFreeSpaceExW` on Windows. On other
+/// platforms or on Unix without the `libc` feature, an error is returned.
+pub fn free_space<T: AsRef<Path>>(parent: T) -> Result<u64> {
+    let parent = parent.as_ref();
+    platform::free_space(parent).map_err(|error| {
+        std::io::Error::new(
+            error.kind(),
+            format!(
+                "failed to query the free space of {}: {}",
+                parent.display(),
+                error,
+            ),
+        )
+    })
+}
+
+/// Check that the file system containing `parent` has at least `bytes`
+/// available.
+pub fn require(parent: &Path, bytes: u64) -> Result<()> {
+    let available = free_space(parent)?;
+    if available < bytes {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::StorageFull,
+            format!(
+                "the parent {} has {} bytes available, but {} bytes are required",
+                parent.display(),
+                available,
+                bytes,
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, feature = "libc"))]
+mod platform {
+    use std::ffi::CString;
+    use std::io::{Error, Result};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    pub fn free_space(path: &Path) -> Result<u64> {
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        let mut status: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut status) } != 0 {
+            return Err(Error::last_os_error());
+        }
+        // The types of the fields differ between platforms.
+        let (blocks, size) = (status.f_bavail as u64, status.f_frsize as u64);
+        Ok(blocks.saturating_mul(size))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::io::{Error, Result};
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            available: *mut u64,
+            total: *mut u64,
+            free: *mut u64,
+        ) -> i32;
+    }
+
+    pub fn free_space(path: &Path) -> Result<u64> {
+        let path = path
+            .as_os_str()
+            .encode_wide()
+            .chain(Some(0))
+            .collect::<Vec<_>>();
+        let mut available = 0;
+        let code = unsafe {
+            GetDiskFreeSpaceExW(
+                path.as_ptr(),
+                &mut available,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if code == 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(available)
+    }
+}
+
+#[cfg(not(any(all(unix, feature = "libc"), windows)))]
+mod platform {
+    use std::io::{Error, ErrorKind, Result};
+    use std::path::Path;
+
+    #[inline]
+    pub fn free_space(_: &Path) -> Result<u64> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "free space cannot be queried on this platform",
+        ))
+    }
+}
+
+#[cfg(all(test, any(all(unix, feature = "libc"), windows)))]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::{Builder, Folder};
+
+    #[test]
+    fn free_space() {
+        let root = Folder::new("foo").unwrap();
+        assert!(super::free_space(&root).unwrap() > 0);
+        assert!(super::free_space(root.join("bar")).is_err());
+
+        Builder::new()
+            .parent(&root)
+            .require_free_space(1)
+            .folder()
+            .unwrap();
+        let error = Builder::new()
+            .parent(&root)
+            .require_free_space(u64::MAX)
+            .folder()
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::StorageFull);
+        assert!(error.to_string().contains("bytes are required"));
+    }
+}
@@ -0,0 +1,155 @@
+use std::io::{Cursor, Read, Result, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::File;
+
+/// A temporary file kept in memory until its size exceeds a threshold.
+///
+/// Once the threshold is exceeded, the content is moved to a [`File`], which is
+/// referred to as rolling over.
+#[derive(Debug)]
+pub struct SpooledFile {
+    limit: usize,
+    inner: Inner,
+}
+
+#[derive(Debug)]
+enum Inner {
+    Memory(Cursor<Vec<u8>>),
+    Disk(File),
+}
+
+impl SpooledFile {
+    /// Create a spooled temporary file.
+    ///
+    /// The content will be kept in memory as long as its size does not exceed
+    /// `limit` bytes.
+    #[inline]
+    pub fn new(limit: usize) -> SpooledFile {
+        SpooledFile {
+            limit,
+            inner: Inner::Memory(Cursor::new(Vec::new())),
+        }
+    }
+
+    /// Check if the content has been moved to disk.
+    #[inline]
+    pub fn is_rolled(&self) -> bool {
+        matches!(self.inner, Inner::Disk(_))
+    }
+
+    /// Move the content to disk.
+    ///
+    /// The current position is preserved. In case of failure, the content
+    /// stays in memory intact.
+    pub fn roll(&mut self) -> Result<()> {
+        let cursor = match &self.inner {
+            Inner::Memory(cursor) => cursor,
+            Inner::Disk(_) => return Ok(()),
+        };
+        let mut file = File::new("")?;
+        file.write_all(cursor.get_ref())?;
+        file.seek(SeekFrom::Start(cursor.position()))?;
+        self.inner = Inner::Disk(file);
+        Ok(())
+    }
+
+    /// Return the path to the file if the content has been moved to disk.
+    #[inline]
+    pub fn path(&self) -> Option<&Path> {
+        match &self.inner {
+            Inner::Memory(_) => None,
+            Inner::Disk(file) => Some(file.path()),
+        }
+    }
+
+    /// Move the content to disk if needed and return the file.
+    pub fn into_file(mut self) -> Result<File> {
+        self.roll()?;
+        match self.inner {
+            Inner::Disk(file) => Ok(file),
+            Inner::Memory(_) => unreachable!(),
+        }
+    }
+}
+
+impl Read for SpooledFile {
+    #[inline]
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        match &mut self.inner {
+            Inner::Memory(cursor) => cursor.read(buffer),
+            Inner::Disk(file) => file.read(buffer),
+        }
+    }
+}
+
+impl Seek for SpooledFile {
+    #[inline]
+    fn seek(&mut self, position: SeekFrom) -> Result<u64> {
+        match &mut self.inner {
+            Inner::Memory(cursor) => cursor.seek(position),
+            Inner::Disk(file) => file.seek(position),
+        }
+    }
+}
+
+impl Write for SpooledFile {
+    fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+        if let Inner::Memory(cursor) = &self.inner {
+            if cursor.position().saturating_add(buffer.len() as u64) > self.limit as u64 {
+                self.roll()?;
+            }
+        }
+        match &mut self.inner {
+            Inner::Memory(cursor) => cursor.write(buffer),
+            Inner::Disk(file) => file.write(buffer),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        match &mut self.inner {
+            Inner::Memory(cursor) => cursor.flush(),
+            Inner::Disk(file) => file.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpooledFile;
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn write() {
+        let mut file = SpooledFile::new(4);
+        file.write_all(b"Hi, ").unwrap();
+        assert!(!file.is_rolled());
+        assert!(file.path().is_none());
+        file.write_all(b"there!").unwrap();
+        assert!(file.is_rolled());
+        assert!(file.path().is_some());
+
+        let mut buffer = String::new();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_string(&mut buffer).unwrap();
+        assert_eq!(buffer, "Hi, there!");
+    }
+
+    #[test]
+    fn roll() {
+        let mut file = SpooledFile::new(1024);
+        file.seek(SeekFrom::Start(2)).unwrap();
+        file.write_all(b"Hi!").unwrap();
+        file.seek(SeekFrom::Start(7)).unwrap();
+        file.roll().unwrap();
+        assert!(file.is_rolled());
+        assert_eq!(file.stream_position().unwrap(), 7);
+
+        let mut file = file.into_file().unwrap();
+        let mut buffer = Vec::new();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_end(&mut buffer).unwrap();
+        assert_eq!(buffer, b"\0\0Hi!");
+    }
+}
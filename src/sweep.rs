@@ -0,0 +1,210 @@
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// A removal of stale temporary entries.
+///
+/// Entries left behind by crashed processes are recognized by their names,
+/// which have to consist of the prefix, a dot, an optional timestamp, and at
+/// least 12 random characters. Entries that do not have this shape are never
+/// touched.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use temporary::Sweep;
+///
+/// let report = Sweep::new("job", Duration::from_secs(24 * 60 * 60))
+///     .dry_run(true)
+///     .run(std::env::temp_dir())
+///     .unwrap();
+/// println!("{} entries would be removed", report.removed.len());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Sweep {
+    prefix: OsString,
+    older_than: Duration,
+    dry_run: bool,
+    any_owner: bool,
+}
+
+/// A report of a removal of stale temporary entries.
+#[derive(Debug, Default)]
+pub struct SweepReport {
+    /// The entries removed or, in the dry-run mode, to be removed.
+    pub removed: Vec<PathBuf>,
+    /// The entries that failed to be removed along with the errors.
+    pub failed: Vec<(PathBuf, Error)>,
+    /// The number of matching entries left intact for being too recent or
+    /// owned by other users.
+    pub skipped: usize,
+}
+
+impl Sweep {
+    /// Prepare a removal of entries with names starting from `prefix` that
+    /// have not been modified for at least `older_than`.
+    pub fn new<T: AsRef<OsStr>>(prefix: T, older_than: Duration) -> Sweep {
+        Sweep {
+            prefix: prefix.as_ref().to_os_string(),
+            older_than,
+            dry_run: false,
+            any_owner: false,
+        }
+    }
+
+    /// Only report the entries that would be removed.
+    #[inline]
+    pub fn dry_run(&mut self, value: bool) -> &mut Self {
+        self.dry_run = value;
+        self
+    }
+
+    /// Remove entries owned by other users too.
+    ///
+    /// By default, such entries are skipped. It has an effect only on Unix.
+    /// Without the `libc` feature, the current user is unknown, and all entries
+    /// are skipped unless it is set.
+    #[inline]
+    pub fn any_owner(&mut self, value: bool) -> &mut Self {
+        self.any_owner = value;
+        self
+    }
+
+    /// Remove stale entries in `parent`.
+    ///
+    /// Failures to remove individual entries are collected in the report, and
+    /// an error is returned only if the prefix is invalid or the folder cannot
+    /// be read.
+    pub fn run<T: AsRef<Path>>(&self, parent: T) -> Result<SweepReport> {
+        let parent = parent.as_ref();
+        if self.prefix.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "the prefix of entries to remove is empty",
+            ));
+        }
+        crate::name::check(&self.prefix, "prefix")?;
+        let now = SystemTime::now();
+        let mut report = SweepReport::default();
+        let entries = fs::read_dir(parent).map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!("failed to read {}: {}", parent.display(), error),
+            )
+        })?;
+        for entry in entries {
+            let entry = entry?;
+            if !crate::name::matches(&entry.file_name(), &self.prefix) {
+                continue;
+            }
+            let path = entry.path();
+            let metadata = match fs::symlink_metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                Err(error) => {
+                    report.failed.push((path, error));
+                    continue;
+                }
+            };
+            if !self.owned(&metadata) || !self.stale(&metadata, now) {
+                report.skipped += 1;
+                continue;
+            }
+            if !self.dry_run {
+                let policy = crate::remove::Policy::default();
+                match crate::remove::folder(&path, &policy) {
+                    Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                    Err(error) => {
+                        report.failed.push((path, error));
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            report.removed.push(path);
+        }
+        Ok(report)
+    }
+
+    fn stale(&self, metadata: &fs::Metadata, now: SystemTime) -> bool {
+        let Ok(time) = metadata.modified().or_else(|_| metadata.created()) else {
+            return false;
+        };
+        // Times in the future are considered recent.
+        now.duration_since(time)
+            .is_ok_and(|elapsed| elapsed >= self.older_than)
+    }
+
+    #[cfg(unix)]
+    fn owned(&self, metadata: &fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        self.any_owner || Some(metadata.uid()) == crate::identity::user()
+    }
+
+    #[cfg(not(unix))]
+    #[inline]
+    fn owned(&self, _: &fs::Metadata) -> bool {
+        true
+    }
+}
+
+/// Remove entries in `parent` with names starting from `prefix` that have not
+/// been modified for at least `older_than`.
+///
+/// See [`Sweep`] for details.
+#[inline]
+pub fn sweep<T: AsRef<Path>, U: AsRef<OsStr>>(
+    parent: T,
+    prefix: U,
+    older_than: Duration,
+) -> Result<SweepReport> {
+    Sweep::new(prefix, older_than).run(parent)
+}
+
+#[cfg(all(test, any(not(unix), feature = "libc")))]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::Sweep;
+    use crate::{Builder, Folder};
+
+    #[test]
+    fn run() {
+        let root = Folder::new("foo").unwrap();
+        let paths = (0..3)
+            .map(|_| {
+                let folder = Builder::new().parent(&root).prefix("bar").folder().unwrap();
+                fs::write(folder.join("bar.txt"), "Hi there!").unwrap();
+                folder.into_path()
+            })
+            .collect::<Vec<_>>();
+        for name in ["bar", "bar.baz", "bar.abcdefghijkl.txt", "baz.abcdefghijkl"] {
+            fs::create_dir(root.join(name)).unwrap();
+        }
+        fs::write(root.join("bar.abcdefghijklm"), "Hi there!").unwrap();
+
+        let report = Sweep::new("bar", Duration::from_secs(3600))
+            .run(&root)
+            .unwrap();
+        assert!(report.removed.is_empty());
+        assert_eq!(report.skipped, 4);
+
+        let report = Sweep::new("bar", Duration::ZERO)
+            .dry_run(true)
+            .run(&root)
+            .unwrap();
+        assert_eq!(report.removed.len(), 4);
+        assert!(paths.iter().all(|path| path.exists()));
+
+        let report = super::sweep(&root, "bar", Duration::ZERO).unwrap();
+        assert_eq!(report.removed.len(), 4);
+        assert!(report.failed.is_empty());
+        assert!(paths.iter().all(|path| !path.exists()));
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 4);
+
+        assert!(super::sweep(&root, "", Duration::ZERO).is_err());
+    }
+}
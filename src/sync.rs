@@ -0,0 +1,45 @@
+#[cfg(any(unix, windows))]
+use std::fs;
+use std::io::Result;
+use std::path::Path;
+
+/// Synchronize the folder containing an entry with the disk.
+#[cfg(unix)]
+pub fn parent(path: &Path) -> Result<()> {
+    fs::File::open(containing(path))?.sync_all()
+}
+
+/// Synchronize the folder containing an entry with the disk.
+///
+/// Folders can be flushed only when opened for writing, which is not always
+/// permitted, and such cases are skipped.
+#[cfg(windows)]
+pub fn parent(path: &Path) -> Result<()> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x02000000;
+
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(containing(path));
+    match file {
+        Ok(file) => file.sync_all(),
+        Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+#[inline]
+pub fn parent(_: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(any(unix, windows))]
+fn containing(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
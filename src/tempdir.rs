@@ -0,0 +1,125 @@
+use std::env;
+use std::ffi::OsString;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::Folder;
+
+/// A guard restoring the variables pointing at the temporary folder when it
+/// goes out of scope.
+///
+/// The variables are `TMPDIR` on Unix and other platforms and `TMP` and `TEMP`
+/// on Windows, as well as `TEMPORARY_DIR` for children relying on this crate,
+/// which this process itself reads only once. The environment is global to
+/// the process, and changing it affects all threads, including tests run in
+/// parallel; see [`Folder::set_as_temp_dir`] for the conditions under which
+/// it can be changed. Nested guards restore the respective previous values
+/// provided that they are dropped in the reverse order of creation, which is
+/// the case for guards bound to local variables.
+#[must_use = "the variables are restored as soon as the guard is dropped"]
+pub struct TempDirGuard<'l> {
+    previous: Vec<(&'static str, Option<OsString>)>,
+    phantom: PhantomData<&'l Folder>,
+}
+
+impl TempDirGuard<'_> {
+    /// # Safety
+    ///
+    /// The conditions are the ones of [`Folder::set_as_temp_dir`].
+    pub(crate) unsafe fn set(path: &Path) -> TempDirGuard<'_> {
+        let mut previous = Vec::with_capacity(crate::env::TEMP_VARIABLES.len());
+        for name in crate::env::TEMP_VARIABLES {
+            previous.push((*name, env::var_os(name)));
+            // SAFETY: The caller guarantees that no other thread accesses the
+            // environment. The function is safe before the 2024 edition.
+            #[allow(unused_unsafe)]
+            unsafe {
+                env::set_var(name, path);
+            }
+        }
+        TempDirGuard {
+            previous,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl Drop for TempDirGuard<'_> {
+    fn drop(&mut self) {
+        for (name, value) in self.previous.drain(..).rev() {
+            // SAFETY: The caller of `set` guarantees that no other thread
+            // accesses the environment until the guard is dropped.
+            #[allow(unused_unsafe)]
+            unsafe {
+                match value {
+                    Some(value) => env::set_var(name, value),
+                    _ => env::remove_var(name),
+                }
+            }
+        }
+    }
+}
+
+/// Run a closure with the temporary folder pointed at a folder.
+///
+/// The previous values of the variables are restored once the closure returns
+/// or panics.
+///
+/// # Safety
+///
+/// No other thread may read or write the environment until the function
+/// returns, including via C code such as `getenv`. Doing so is undefined
+/// behavior on some platforms. See [`Folder::set_as_temp_dir`].
+#[inline]
+pub unsafe fn with_temp_dir_override<F, T>(folder: &Folder, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    // SAFETY: The caller upholds the conditions of `set_as_temp_dir`.
+    let _guard = unsafe { folder.set_as_temp_dir() };
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::Folder;
+
+    #[test]
+    fn set() {
+        const CHILD: &str = "TEMPORARY_TEST_TEMP_DIR";
+
+        // The environment is changed in a separate process as it is shared by
+        // the tests run in parallel.
+        if env::var_os(CHILD).is_none() {
+            let status = std::process::Command::new(env::current_exe().unwrap())
+                .args(["tempdir::tests::set", "--exact"])
+                .env(CHILD, "1")
+                .status()
+                .unwrap();
+            assert!(status.success());
+            return;
+        }
+        let previous = env::temp_dir();
+        let one = Folder::new("foo").unwrap();
+        let other = Folder::new("bar").unwrap();
+        // SAFETY: The test is the only one run in the process.
+        {
+            let _one = unsafe { one.set_as_temp_dir() };
+            assert_eq!(env::temp_dir(), one.path());
+            {
+                let _other = unsafe { other.set_as_temp_dir() };
+                assert_eq!(env::temp_dir(), other.path());
+                assert_eq!(env::var_os(crate::env::DIR).unwrap(), other.as_os_str());
+            }
+            assert_eq!(env::temp_dir(), one.path());
+        }
+        assert_eq!(env::temp_dir(), previous);
+        assert!(env::var_os(crate::env::DIR).is_none());
+
+        let path = unsafe { super::with_temp_dir_override(&one, env::temp_dir) };
+        assert_eq!(path, one.path());
+        assert_eq!(env::temp_dir(), previous);
+    }
+}
@@ -0,0 +1,15 @@
+use crate::{Builder, Folder};
+
+/// Create a folder for a test named after its module and function.
+///
+/// The folder is kept if the test panics.
+pub fn folder(module: &str, name: &str) -> Folder {
+    let prefix = format!("{}-{}", module.replace("::", "-"), name);
+    Builder::new()
+        .prefix(prefix)
+        .sanitize_prefix(true)
+        .truncate_prefix(true)
+        .keep_on_panic(true)
+        .folder()
+        .unwrap_or_else(|error| panic!("failed to create a folder for test {}: {}", name, error))
+}
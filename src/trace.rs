@@ -0,0 +1,76 @@
+//! Events of creation and removal for the `log` and `tracing` features.
+//!
+//! Without the features, everything here compiles to nothing.
+
+use std::ffi::OsStr;
+use std::path::Path;
+
+/// A span covering the creation of an entry.
+#[cfg(feature = "tracing")]
+pub type Span = tracing::span::EnteredSpan;
+
+/// A span covering the creation of an entry.
+#[cfg(not(feature = "tracing"))]
+pub struct Span;
+
+/// A measurement of the time taken by a removal.
+#[derive(Clone, Copy)]
+pub struct Timer {
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    start: std::time::Instant,
+}
+
+impl Timer {
+    #[inline]
+    pub fn start() -> Timer {
+        Timer {
+            #[cfg(any(feature = "log", feature = "tracing"))]
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+/// Enter a span for the creation of an entry.
+#[allow(unused_variables)]
+#[inline]
+pub fn creating(kind: &'static str, prefix: &OsStr) -> Span {
+    #[cfg(feature = "tracing")]
+    return tracing::debug_span!("temporary", kind, prefix = ?prefix).entered();
+    #[cfg(not(feature = "tracing"))]
+    Span
+}
+
+/// Report the creation of an entry.
+#[allow(unused_variables)]
+#[inline]
+pub fn created(kind: &'static str, path: &Path, attempts: u32) {
+    #[cfg(feature = "log")]
+    log::debug!(
+        "created the temporary {} {} after {} attempts",
+        kind,
+        path.display(),
+        attempts,
+    );
+    #[cfg(feature = "tracing")]
+    tracing::debug!(kind, path = %path.display(), attempts, "created");
+}
+
+/// Report the removal of an entry.
+#[allow(unused_variables)]
+#[inline]
+pub fn removed(kind: &'static str, path: &Path, timer: Timer) {
+    #[cfg(feature = "log")]
+    log::debug!(
+        "removed the temporary {} {} in {:?}",
+        kind,
+        path.display(),
+        timer.start.elapsed(),
+    );
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        kind,
+        path = %path.display(),
+        duration = ?timer.start.elapsed(),
+        "removed",
+    );
+}
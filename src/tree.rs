@@ -0,0 +1,359 @@
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::{Folder, Operation};
+
+/// A declaration of a tree of files, folders, and symbolic links.
+///
+/// ```rust
+/// use temporary::TempTree;
+///
+/// let tree = TempTree::new()
+///     .file("README.md", "# Foo")
+///     .dir("src", |src| src.file("main.rs", "fn main() {}"))
+///     .empty_dir("target")
+///     .clone();
+/// let folder = tree.build("foo").unwrap();
+/// assert_eq!(folder.read_to_string("src/main.rs").unwrap(), "fn main() {}");
+/// tree.assert_matches(&folder);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TempTree {
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    name: OsString,
+    kind: Kind,
+    #[cfg(unix)]
+    mode: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    File(Vec<u8>),
+    Folder(TempTree),
+    Link(PathBuf),
+}
+
+impl TempTree {
+    /// Start an empty tree.
+    #[inline]
+    pub fn new() -> TempTree {
+        TempTree::default()
+    }
+
+    /// Declare a file with the given contents.
+    pub fn file<T: AsRef<OsStr>, U: AsRef<[u8]>>(&mut self, name: T, contents: U) -> &mut Self {
+        self.push(name, Kind::File(contents.as_ref().to_vec()))
+    }
+
+    /// Declare a folder with the content declared by `f`.
+    pub fn dir<T, F>(&mut self, name: T, f: F) -> &mut Self
+    where
+        T: AsRef<OsStr>,
+        F: FnOnce(&mut TempTree) -> &mut TempTree,
+    {
+        let mut tree = TempTree::new();
+        f(&mut tree);
+        self.push(name, Kind::Folder(tree))
+    }
+
+    /// Declare an empty folder.
+    #[inline]
+    pub fn empty_dir<T: AsRef<OsStr>>(&mut self, name: T) -> &mut Self {
+        self.push(name, Kind::Folder(TempTree::new()))
+    }
+
+    /// Declare a symbolic link pointing at `target`.
+    ///
+    /// The target is stored as given and does not have to exist. Links are
+    /// created after all other entries. On Windows, a link to a folder is
+    /// created if the target is a folder at that moment.
+    pub fn symlink<T: AsRef<OsStr>, U: AsRef<Path>>(&mut self, name: T, target: U) -> &mut Self {
+        self.push(name, Kind::Link(target.as_ref().to_path_buf()))
+    }
+
+    /// Set the mode of the entry declared last.
+    ///
+    /// Modes are applied after the content of folders has been created, and
+    /// they are not subject to the umask. Links have no modes of their own.
+    ///
+    /// # Panics
+    ///
+    /// The function panics if no entry has been declared.
+    #[cfg(unix)]
+    pub fn mode(&mut self, mode: u32) -> &mut Self {
+        let node = self.nodes.last_mut().expect("no entry has been declared");
+        node.mode = Some(mode);
+        self
+    }
+
+    /// Create a temporary folder with the declared content.
+    ///
+    /// Either the whole tree is created, or the folder is removed before the
+    /// error is returned. Names have to be valid file names, and duplicate
+    /// names result in an error.
+    pub fn build<T: AsRef<OsStr>>(&self, prefix: T) -> crate::Result<Folder> {
+        self.validate().map_err(|error| {
+            crate::Error::new(Operation::CreateFolder, crate::env::default_parent(), error)
+        })?;
+        let folder = Folder::new(prefix)?;
+        let path = folder.path();
+        let result = self
+            .create(path, false)
+            .and_then(|_| self.create(path, true))
+            .and_then(|_| self.apply(path));
+        if let Err(error) = result {
+            let error = crate::Error::new(Operation::PopulateFolder, path, error);
+            let _ = folder.force_remove();
+            return Err(error);
+        }
+        Ok(folder)
+    }
+
+    /// Assert that the content of `path` matches the declared tree.
+    ///
+    /// The kinds of entries, the contents of files, the targets of links,
+    /// and the modes given via `mode` are compared, and entries that are not
+    /// declared are reported too.
+    ///
+    /// # Panics
+    ///
+    /// The function panics with a list of all differences if there are any.
+    #[track_caller]
+    pub fn assert_matches<T: AsRef<Path>>(&self, path: T) {
+        let path = path.as_ref();
+        let mut differences = Vec::new();
+        self.compare(path, Path::new(""), &mut differences);
+        if !differences.is_empty() {
+            panic!(
+                "the content of {} does not match the tree:\n{}",
+                path.display(),
+                differences.join("\n"),
+            );
+        }
+    }
+
+    fn push<T: AsRef<OsStr>>(&mut self, name: T, kind: Kind) -> &mut Self {
+        self.nodes.push(Node {
+            name: name.as_ref().to_os_string(),
+            kind,
+            #[cfg(unix)]
+            mode: None,
+        });
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut names = std::collections::BTreeSet::new();
+        for node in &self.nodes {
+            crate::name::check(&node.name, "name")?;
+            if !names.insert(&node.name) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("the name {:?} is declared more than once", node.name),
+                ));
+            }
+            if let Kind::Folder(tree) = &node.kind {
+                tree.validate()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn create(&self, parent: &Path, links: bool) -> Result<()> {
+        for node in &self.nodes {
+            let path = parent.join(&node.name);
+            let context = |error: Error| {
+                Error::new(
+                    error.kind(),
+                    format!("failed to create {}: {}", path.display(), error),
+                )
+            };
+            match &node.kind {
+                Kind::File(contents) if !links => fs::write(&path, contents).map_err(context)?,
+                Kind::Folder(tree) => {
+                    if !links {
+                        fs::create_dir(&path).map_err(context)?;
+                    }
+                    tree.create(&path, links)?;
+                }
+                Kind::Link(target) if links => {
+                    crate::link::symlink(target, &path).map_err(context)?
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn apply(&self, parent: &Path) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        for node in &self.nodes {
+            let path = parent.join(&node.name);
+            if let Kind::Folder(tree) = &node.kind {
+                tree.apply(&path)?;
+            }
+            match (&node.kind, node.mode) {
+                (Kind::Link(_), _) | (_, None) => {}
+                (_, Some(mode)) => fs::set_permissions(&path, fs::Permissions::from_mode(mode))
+                    .map_err(|error| {
+                        Error::new(
+                            error.kind(),
+                            format!("failed to set the mode of {}: {}", path.display(), error),
+                        )
+                    })?,
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    #[inline]
+    fn apply(&self, _: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    fn compare(&self, root: &Path, relative: &Path, differences: &mut Vec<String>) {
+        let mut declared = std::collections::BTreeSet::new();
+        for node in &self.nodes {
+            declared.insert(node.name.clone());
+            let relative = relative.join(&node.name);
+            let path = root.join(&relative);
+            let metadata = match fs::symlink_metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(error) => {
+                    differences.push(format!("{}: {}", relative.display(), error));
+                    continue;
+                }
+            };
+            let file_type = metadata.file_type();
+            match &node.kind {
+                Kind::File(contents) if file_type.is_file() => match fs::read(&path) {
+                    Ok(actual) if actual == *contents => {}
+                    Ok(_) => {
+                        differences.push(format!("{}: different contents", relative.display()))
+                    }
+                    Err(error) => differences.push(format!("{}: {}", relative.display(), error)),
+                },
+                Kind::Folder(tree) if file_type.is_dir() => {
+                    tree.compare(root, &relative, differences)
+                }
+                Kind::Link(target) if file_type.is_symlink() => match fs::read_link(&path) {
+                    Ok(actual) if actual == *target => {}
+                    Ok(actual) => differences.push(format!(
+                        "{}: a link to {} instead of {}",
+                        relative.display(),
+                        actual.display(),
+                        target.display(),
+                    )),
+                    Err(error) => differences.push(format!("{}: {}", relative.display(), error)),
+                },
+                kind => {
+                    differences.push(format!(
+                        "{}: a {} instead of a {}",
+                        relative.display(),
+                        describe(&file_type),
+                        match kind {
+                            Kind::File(_) => "file",
+                            Kind::Folder(_) => "folder",
+                            Kind::Link(_) => "link",
+                        },
+                    ));
+                    continue;
+                }
+            }
+            #[cfg(unix)]
+            if let (Some(mode), false) = (node.mode, file_type.is_symlink()) {
+                use std::os::unix::fs::PermissionsExt;
+
+                let actual = metadata.permissions().mode() & 0o7777;
+                if actual != mode {
+                    differences.push(format!(
+                        "{}: mode {:o} instead of {:o}",
+                        relative.display(),
+                        actual,
+                        mode,
+                    ));
+                }
+            }
+        }
+        let entries = match fs::read_dir(root.join(relative)) {
+            Ok(entries) => entries,
+            Err(error) => {
+                differences.push(format!("{}: {}", relative.display(), error));
+                return;
+            }
+        };
+        let mut unexpected = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.file_name()))
+            .filter(|name| !declared.contains(name))
+            .collect::<Vec<_>>();
+        unexpected.sort();
+        for name in unexpected {
+            differences.push(format!("{}: not declared", relative.join(name).display()));
+        }
+    }
+}
+
+fn describe(file_type: &fs::FileType) -> &'static str {
+    if file_type.is_symlink() {
+        "link"
+    } else if file_type.is_dir() {
+        "folder"
+    } else if file_type.is_file() {
+        "file"
+    } else {
+        "special file"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TempTree;
+
+    #[test]
+    fn build() {
+        let mut tree = TempTree::new();
+        tree.file("foo.txt", "Hi there!")
+            .dir("bar", |bar| bar.file("baz.txt", "Bye!").empty_dir("qux"))
+            .empty_dir("baz");
+        #[cfg(unix)]
+        tree.file("script.sh", "exit 0")
+            .mode(0o755)
+            .symlink("qux.txt", "bar/baz.txt");
+        let folder = tree.build("foo").unwrap();
+        assert_eq!(folder.read_to_string("bar/baz.txt").unwrap(), "Bye!");
+        assert!(folder.join("bar").join("qux").is_dir());
+        tree.assert_matches(&folder);
+
+        folder.write("bar/baz.txt", "Hi there!").unwrap();
+        folder.touch("bar/qux/qux.txt").unwrap();
+        std::fs::remove_dir(folder.join("baz")).unwrap();
+        let message = std::panic::catch_unwind(|| tree.assert_matches(&folder))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(message.contains("different contents"));
+        assert!(message.contains("not declared"));
+        assert!(message.contains("baz"));
+    }
+
+    #[test]
+    fn build_invalid() {
+        let mut tree = TempTree::new();
+        tree.file("foo.txt", "Hi there!").file("foo.txt", "Bye!");
+        assert!(tree.build("foo").is_err());
+        assert!(TempTree::new().file("..", "").build("foo").is_err());
+        assert!(TempTree::new()
+            .dir("foo", |foo| foo.file("bar/baz.txt", ""))
+            .build("foo")
+            .is_err());
+    }
+}
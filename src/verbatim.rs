@@ -0,0 +1,141 @@
+use std::io::{Error, Result};
+use std::path::{Path, PathBuf};
+
+/// Convert a path to the extended-length form.
+///
+/// On Windows, the path is made absolute and normalized and given the `\\?\`
+/// prefix, which lifts the limit of `MAX_PATH` characters. Paths that already
+/// have the prefix are left intact. Elsewhere, the path is returned as is.
+#[cfg(windows)]
+pub fn path(path: &Path) -> Result<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use std::path::{Component, Prefix};
+
+    let path = std::path::absolute(path)?;
+    let mut value = OsString::from(r"\\?\");
+    match path.components().next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => value.push(path.as_os_str()),
+            Prefix::UNC(..) => {
+                value.push("UNC");
+                let units = path.as_os_str().encode_wide().skip(1).collect::<Vec<_>>();
+                value.push(OsString::from_wide(&units));
+            }
+            _ => return Ok(path),
+        },
+        _ => return Ok(path),
+    }
+    Ok(value.into())
+}
+
+#[cfg(not(windows))]
+#[inline]
+pub fn path(path: &Path) -> Result<PathBuf> {
+    Ok(path.to_path_buf())
+}
+
+/// Check if a path has the `\\?\` prefix.
+#[cfg(windows)]
+pub fn is_verbatim(path: &Path) -> bool {
+    use std::path::Component;
+
+    matches!(
+        path.components().next(),
+        Some(Component::Prefix(prefix)) if prefix.kind().is_verbatim()
+    )
+}
+
+/// Replace forward slashes with backslashes unless the path is verbatim.
+///
+/// `None` is returned if there is nothing to replace.
+#[cfg(windows)]
+pub fn backslashed(path: &Path) -> Option<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+    const SLASH: u16 = b'/' as u16;
+    const BACKSLASH: u16 = b'\\' as u16;
+
+    if is_verbatim(path) || !path.as_os_str().encode_wide().any(|unit| unit == SLASH) {
+        return None;
+    }
+    let units = path
+        .as_os_str()
+        .encode_wide()
+        .map(|unit| if unit == SLASH { BACKSLASH } else { unit })
+        .collect::<Vec<_>>();
+    Some(OsString::from_wide(&units).into())
+}
+
+/// Name `original` instead of `verbatim` in an error concerning the latter or
+/// the entries inside it.
+pub fn restore(error: Error, verbatim: &Path, original: &Path) -> Error {
+    if verbatim == original {
+        return error;
+    }
+    let message = error.to_string();
+    let from = verbatim.display().to_string();
+    if !message.contains(&from) {
+        return error;
+    }
+    let to = original.display().to_string();
+    Error::new(error.kind(), message.replacen(&from, &to, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error, ErrorKind};
+    use std::path::Path;
+
+    #[test]
+    fn restore() {
+        let (verbatim, original) = (Path::new("/foo/bar"), Path::new("bar"));
+        let error = Error::new(
+            ErrorKind::PermissionDenied,
+            "failed to remove /foo/bar/baz: denied",
+        );
+        let error = super::restore(error, verbatim, original);
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(error.to_string(), "failed to remove bar/baz: denied");
+        let error = super::restore(error, verbatim, verbatim);
+        assert_eq!(error.to_string(), "failed to remove bar/baz: denied");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn backslashed() {
+        for (path, expected) in [
+            (r"\\server\share\foo", None),
+            (r"//server/share/foo", Some(r"\\server\share\foo")),
+            (r"\\server\share/foo/bar", Some(r"\\server\share\foo\bar")),
+            (r"\\?\UNC\server\share\foo/bar", None),
+            (r"C:/foo", Some(r"C:\foo")),
+        ] {
+            assert_eq!(
+                super::backslashed(Path::new(path)).as_deref(),
+                expected.map(Path::new)
+            );
+        }
+        assert!(super::is_verbatim(Path::new(r"\\?\UNC\server\share")));
+        assert!(super::is_verbatim(Path::new(r"\\?\C:\foo")));
+        assert!(!super::is_verbatim(Path::new(r"\\server\share")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn path() {
+        for (path, expected) in [
+            (r"C:\foo\bar", r"\\?\C:\foo\bar"),
+            (r"C:\foo\.\baz\..\bar", r"\\?\C:\foo\bar"),
+            (r"C:/foo/bar", r"\\?\C:\foo\bar"),
+            (r"\\server\share\foo", r"\\?\UNC\server\share\foo"),
+            (r"\\?\C:\foo\bar", r"\\?\C:\foo\bar"),
+            (r"\\?\UNC\server\share\foo", r"\\?\UNC\server\share\foo"),
+            (r"\\server\share\foo\..\bar", r"\\?\UNC\server\share\bar"),
+            (r"//server/share/foo", r"\\?\UNC\server\share\foo"),
+        ] {
+            assert_eq!(super::path(Path::new(path)).unwrap(), Path::new(expected));
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::fs::{self, Metadata};
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// Visit every entry inside `root` without following symbolic links.
+///
+/// Entries disappearing during the walk are skipped.
+pub fn each<F>(root: &Path, mut visit: F) -> Result<()>
+where
+    F: FnMut(&Path, &Metadata),
+{
+    let mut stack: Vec<PathBuf> = vec![root.to_path_buf()];
+    while let Some(folder) = stack.pop() {
+        let entries = match fs::read_dir(&folder) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == ErrorKind::NotFound && folder != root => continue,
+            Err(error) => return Err(error),
+        };
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            };
+            let path = entry.path();
+            // The metadata of symbolic links themselves is returned.
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            };
+            visit(&path, &metadata);
+            if metadata.is_dir() {
+                stack.push(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Visit every entry inside `root` on `filesystem` without following symbolic
+/// links.
+///
+/// Entries disappearing during the walk are skipped.
+pub fn each_in<F>(filesystem: &dyn crate::Filesystem, root: &Path, mut visit: F) -> Result<()>
+where
+    F: FnMut(&Path, &Metadata),
+{
+    let mut stack: Vec<PathBuf> = vec![root.to_path_buf()];
+    while let Some(folder) = stack.pop() {
+        let paths = match filesystem.read_dir(&folder) {
+            Ok(paths) => paths,
+            Err(error) if error.kind() == ErrorKind::NotFound && folder != root => continue,
+            Err(error) => return Err(error),
+        };
+        for path in paths {
+            let metadata = match filesystem.symlink_metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            };
+            visit(&path, &metadata);
+            if metadata.is_dir() {
+                stack.push(path);
+            }
+        }
+    }
+    Ok(())
+}
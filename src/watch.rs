@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Result};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use notify::event::{Event, EventKind};
+use notify::Watcher as _;
+
+use crate::Folder;
+
+/// A change to an entry inside a watched folder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// The entry has been created.
+    Created(PathBuf),
+    /// The content or the metadata of the entry has been modified, or the entry
+    /// has been renamed.
+    Modified(PathBuf),
+    /// The entry has been removed.
+    Removed(PathBuf),
+}
+
+impl Change {
+    /// Return the path to the entry.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Created(path) | Change::Modified(path) | Change::Removed(path) => path,
+        }
+    }
+}
+
+/// A watcher of changes inside a folder and its descendants.
+///
+/// The watcher borrows the folder, so that it is stopped before the folder is
+/// removed, which matters on Windows, where the watched folder is kept open.
+/// Changes are yielded by iterating over the watcher, which blocks until the
+/// next change arrives.
+pub struct Watcher<'l> {
+    watcher: notify::RecommendedWatcher,
+    receiver: Receiver<notify::Result<Event>>,
+    pending: VecDeque<Change>,
+    phantom: PhantomData<&'l Folder>,
+}
+
+impl<'l> Watcher<'l> {
+    pub(crate) fn new(folder: &'l Folder) -> Result<Watcher<'l>> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(convert)?;
+        watcher
+            .watch(folder.path(), notify::RecursiveMode::Recursive)
+            .map_err(convert)?;
+        Ok(Watcher {
+            watcher,
+            receiver,
+            pending: VecDeque::new(),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Wait for the next change for at most `timeout`.
+    ///
+    /// `None` is returned if nothing has changed in time.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Change>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(change) = self.pending.pop_front() {
+                return Ok(Some(change));
+            }
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.receiver.recv_timeout(timeout) {
+                Ok(event) => self.push(event.map_err(convert)?),
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => return Err(disconnected()),
+            }
+        }
+    }
+
+    fn push(&mut self, event: Event) {
+        let change: fn(PathBuf) -> Change = match event.kind {
+            EventKind::Create(_) => Change::Created,
+            EventKind::Modify(_) => Change::Modified,
+            EventKind::Remove(_) => Change::Removed,
+            _ => return,
+        };
+        self.pending.extend(event.paths.into_iter().map(change));
+    }
+}
+
+impl Iterator for Watcher<'_> {
+    type Item = Result<Change>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(change) = self.pending.pop_front() {
+                return Some(Ok(change));
+            }
+            match self.receiver.recv() {
+                Ok(Ok(event)) => self.push(event),
+                Ok(Err(error)) => return Some(Err(convert(error))),
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Watcher<'_> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("Watcher")
+            .field("watcher", &self.watcher)
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
+
+/// Wait until an entry appears at `path` within the folder.
+pub fn wait_for(folder: &Folder, path: &Path, timeout: Duration) -> Result<()> {
+    let target = folder.join(path);
+    // The watcher is started first so that no change is missed in between.
+    let mut watcher = Watcher::new(folder)?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        if target.exists() {
+            return Ok(());
+        }
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if timeout.is_zero() {
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!("{} did not appear in time", target.display()),
+            ));
+        }
+        // Events of intermediate folders do not always come through, and the
+        // target is checked periodically too.
+        watcher.next_timeout(timeout.min(Duration::from_millis(100)))?;
+    }
+}
+
+fn convert(error: notify::Error) -> Error {
+    match error.kind {
+        notify::ErrorKind::Io(error) => error,
+        notify::ErrorKind::PathNotFound => Error::new(ErrorKind::NotFound, error),
+        _ => Error::other(error),
+    }
+}
+
+fn disconnected() -> Error {
+    Error::other("the watcher has stopped")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::Change;
+    use crate::Folder;
+
+    #[test]
+    fn watch() {
+        let folder = Folder::new("foo").unwrap();
+        let mut watcher = folder.watch().unwrap();
+        fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+        // Paths might be reported canonicalized, and there might be other
+        // changes before, such as of the folder itself.
+        let change = loop {
+            let change = watcher.next_timeout(Duration::from_secs(10)).unwrap();
+            let change = change.expect("the change should arrive");
+            if change.path().ends_with("foo.txt") {
+                break change;
+            }
+        };
+        assert!(matches!(change, Change::Created(_)));
+        drop(watcher);
+        folder.remove().unwrap();
+    }
+
+    #[test]
+    fn wait_for() {
+        let folder = Folder::new("foo").unwrap();
+        let path = folder.join("bar").join("baz.txt");
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            fs::create_dir(path.parent().unwrap()).unwrap();
+            fs::write(path, "Hi there!").unwrap();
+        });
+        folder
+            .wait_for("bar/baz.txt", Duration::from_secs(10))
+            .unwrap();
+        handle.join().unwrap();
+        let error = folder
+            .wait_for("qux.txt", Duration::from_millis(50))
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    }
+}
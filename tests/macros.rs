@@ -0,0 +1,30 @@
+#![cfg(feature = "macros")]
+
+use std::path::Path;
+
+use temporary::Folder;
+
+#[temporary::test]
+fn folder(folder: &Folder) {
+    folder.write("foo.txt", "Hi there!").unwrap();
+    let name = folder.file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("macros-folder."));
+}
+
+#[temporary::test]
+fn path(path: &Path) {
+    assert!(path.is_dir());
+}
+
+#[temporary::test]
+fn result(folder: &Folder) -> std::io::Result<()> {
+    folder.write("foo.txt", "Hi there!")?;
+    Ok(())
+}
+
+#[temporary::test]
+#[tokio::test]
+async fn asynchronous(folder: &Folder) {
+    tokio::task::yield_now().await;
+    assert!(folder.exists());
+}
@@ -0,0 +1,37 @@
+//! Tests to be run under a WASI runtime with a preopened folder, such as
+//! `wasmtime run --dir /tmp --env TMPDIR=/tmp`.
+
+#![cfg(target_os = "wasi")]
+
+use std::fs;
+
+use temporary::{Builder, Folder};
+
+#[test]
+fn create() {
+    let folder = Folder::new("foo").unwrap();
+    fs::create_dir_all(folder.join("bar").join("baz")).unwrap();
+    fs::write(folder.join("bar").join("baz").join("qux.txt"), "Hi there!").unwrap();
+    let path = folder.to_path_buf();
+    drop(folder);
+    assert!(fs::metadata(path).is_err());
+}
+
+#[test]
+fn collide() {
+    let mut builder = Builder::new();
+    builder.prefix("foo").deterministic_seed(42);
+    let one = builder.folder().unwrap();
+    let other = builder.folder().unwrap();
+    assert_ne!(one.path(), other.path());
+    assert_eq!(other.creation_attempts(), 2);
+}
+
+#[test]
+fn remove() {
+    let folder = Folder::new("foo").unwrap();
+    fs::write(folder.join("foo.txt"), "Hi there!").unwrap();
+    let path = folder.to_path_buf();
+    folder.remove().unwrap();
+    assert!(fs::metadata(path).is_err());
+}